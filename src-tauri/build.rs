@@ -1,3 +1,5 @@
 fn main() {
+  // The headless daemon build has no Tauri context to generate.
+  #[cfg(feature = "gui")]
   tauri_build::build()
 }
@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+  cf_ddns_ipv6_tauri_lib::run_cli()
+}
@@ -0,0 +1,570 @@
+use std::{
+  path::{Path, PathBuf},
+  process::ExitCode,
+  sync::{atomic::Ordering, Arc},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+
+use crate::engine::{self, AppState, EngineEvents, EngineOptions};
+use crate::models::{HistoryExportFormat, PrefixChangedEvent};
+use crate::systemd::{self, UnitOptions, UnitScope};
+use crate::{config, control, daemon, rt};
+
+const USAGE: &str = "Usage: cf-ddns-daemon [--config <dir>] [--json] [--standalone] [<command>]
+
+Commands:
+  run                       Run the DDNS engine in the foreground (default)
+  status                    Show the current IPv6 address, record target and last sync
+  push                      Detect addresses and push them to Cloudflare now
+  interfaces                List network interfaces and their global IPv6 addresses
+  lookup-record [--zone-id <id>] [--domain <name>]
+                            Look up the AAAA record ID and store it as the record target
+  stats                     Summarize prefix lifetimes, pushes per day, failure rate and address uptime
+  export [--format csv|json]
+                            Print the sync history as CSV (default) or as JSON with the statistics
+  config get [<key>]        Print one setting, e.g. `cloudflare.domain`, or all settings
+  config set <key> <value>  Change one setting; <value> is taken verbatim for text settings and as JSON otherwise
+  systemd-unit [--user | --system --run-as <user>]
+                            Print a hardened systemd unit that runs this binary with the same config directory
+
+Every command except `run` and `systemd-unit` goes to the app or daemon already running for the same config directory,
+and runs on its own when there is none.
+
+Options:
+  --config <dir>        Directory holding settings.json (default: the desktop app's config directory)
+  --resource-dir <dir>  Directory with bundled GeoIP databases and homepage assets (`run` only; default: next to the binary)
+  --json                Print the result as JSON
+  --standalone          Do not contact a running instance
+  -h, --help            Print this help";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+  Run,
+  Status,
+  Push,
+  Interfaces,
+  LookupRecord { zone_id: Option<String>, domain: Option<String> },
+  Stats,
+  Export { format: HistoryExportFormat },
+  ConfigGet { key: Option<String> },
+  ConfigSet { key: String, text: String },
+  SystemdUnit { scope: UnitScope, run_as: Option<String> },
+}
+
+struct CliArgs {
+  command: Command,
+  config_dir: Option<PathBuf>,
+  resource_dir: Option<PathBuf>,
+  json: bool,
+  standalone: bool,
+}
+
+// Standalone runs only need results; the interesting events are already part of them.
+struct QuietEvents;
+
+impl EngineEvents for QuietEvents {
+  fn snapshot_changed(&self, _state: &Arc<AppState>) {}
+
+  fn network_changed(&self, _state: &Arc<AppState>) {}
+
+  fn prefix_changed(&self, _event: &PrefixChangedEvent) {}
+
+  fn alert(&self, message: &str) {
+    eprintln!("warning: {message}");
+  }
+
+  fn settings_applied(&self, _state: &Arc<AppState>) -> Result<(), String> {
+    Ok(())
+  }
+}
+
+pub fn run_cli() -> ExitCode {
+  let args = match parse_args(std::env::args().skip(1)) {
+    Ok(Some(args)) => args,
+    Ok(None) => {
+      println!("{USAGE}");
+      return ExitCode::SUCCESS;
+    }
+    Err(error) => {
+      eprintln!("{error}\n\n{USAGE}");
+      return ExitCode::from(2);
+    }
+  };
+  let config_dir = match args.config_dir.clone().or_else(|| config::default_config_dir(engine::APP_IDENTIFIER)) {
+    Some(dir) => dir,
+    None => {
+      eprintln!("cannot determine the config directory; pass --config <dir>");
+      return ExitCode::from(2);
+    }
+  };
+
+  let result = match args.command {
+    Command::Run => daemon::serve(&config_dir, args.resource_dir),
+    Command::SystemdUnit { scope, run_as } => print_systemd_unit(&config_dir, scope, run_as),
+    command => {
+      let (method, params) = request_for(&command);
+      execute(&config_dir, args.standalone, method, params).map(|result| print_result(&command, &result, args.json))
+    }
+  };
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(error) => {
+      eprintln!("error: {error:#}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+// `Ok(None)` means help was requested.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<CliArgs>, String> {
+  let mut config_dir = None;
+  let mut resource_dir = None;
+  let mut zone_id = None;
+  let mut domain = None;
+  let mut json = false;
+  let mut standalone = false;
+  let mut unit_scope = UnitScope::User;
+  let mut run_as = None;
+  let mut export_format = HistoryExportFormat::Csv;
+  let mut positional = Vec::new();
+
+  while let Some(arg) = args.next() {
+    if !arg.starts_with("--") || arg == "--" {
+      match arg.as_str() {
+        "-h" => return Ok(None),
+        "--" => positional.extend(args.by_ref()),
+        _ => positional.push(arg),
+      }
+      continue;
+    }
+    let (flag, inline_value) = match arg.split_once('=') {
+      Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+      None => (arg, None),
+    };
+    let mut value = || {
+      inline_value
+        .clone()
+        .or_else(|| args.next())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| format!("{flag} needs a value"))
+    };
+    match flag.as_str() {
+      "--help" => return Ok(None),
+      "--json" => json = true,
+      "--standalone" => standalone = true,
+      "--config" => config_dir = Some(PathBuf::from(value()?)),
+      "--resource-dir" => resource_dir = Some(PathBuf::from(value()?)),
+      "--zone-id" => zone_id = Some(value()?),
+      "--domain" => domain = Some(value()?),
+      "--user" => unit_scope = UnitScope::User,
+      "--system" => unit_scope = UnitScope::System,
+      "--run-as" => run_as = Some(value()?),
+      "--format" => {
+        export_format = match value()?.as_str() {
+          "csv" => HistoryExportFormat::Csv,
+          "json" => HistoryExportFormat::Json,
+          other => return Err(format!("unknown export format `{other}`; use csv or json")),
+        }
+      }
+      _ => return Err(format!("unknown option `{flag}`")),
+    }
+  }
+
+  let mut positional = positional.into_iter();
+  let command = match positional.next().as_deref() {
+    None | Some("run") => Command::Run,
+    Some("status") => Command::Status,
+    Some("push") => Command::Push,
+    Some("interfaces") => Command::Interfaces,
+    Some("lookup-record") => Command::LookupRecord { zone_id, domain },
+    Some("stats") => Command::Stats,
+    Some("export") => Command::Export { format: export_format },
+    Some("config") => match positional.next().as_deref() {
+      Some("get") => Command::ConfigGet { key: positional.next() },
+      Some("set") => {
+        let key = positional.next().ok_or("config set needs a key")?;
+        // Converted by the engine according to the setting's type, so `cloudflare.domain home.example.com` needs no
+        // JSON quoting and numeric-looking strings stay strings.
+        let text = positional.next().ok_or("config set needs a value")?;
+        Command::ConfigSet { key, text }
+      }
+      Some(other) => return Err(format!("unknown config command `{other}`")),
+      None => return Err("config needs `get` or `set`".to_string()),
+    },
+    Some("systemd-unit") => Command::SystemdUnit {
+      scope: unit_scope,
+      run_as,
+    },
+    Some(other) => return Err(format!("unknown command `{other}`")),
+  };
+  if let Some(extra) = positional.next() {
+    return Err(format!("unexpected argument `{extra}`"));
+  }
+  Ok(Some(CliArgs {
+    command,
+    config_dir,
+    resource_dir,
+    json,
+    standalone,
+  }))
+}
+
+fn print_systemd_unit(config_dir: &Path, scope: UnitScope, run_as: Option<String>) -> Result<()> {
+  let executable = std::env::current_exe().context("failed to locate this binary")?;
+  let executable = executable.canonicalize().unwrap_or(executable);
+  // The unit runs from `/`, so the config directory has to be absolute.
+  let config_dir = std::path::absolute(config_dir).context("failed to resolve the config directory")?;
+  // A system unit is usually generated under sudo, where `$USER` is root and the default config directory is root's,
+  // so the account is never guessed. Root needs no `User=`.
+  let run_as = match scope {
+    UnitScope::User => None,
+    UnitScope::System => {
+      let user = run_as
+        .filter(|user| !user.is_empty())
+        .context("--system needs --run-as <user> naming the account the daemon runs as (use `root` for root)")?;
+      (user != "root").then_some(user)
+    }
+  };
+  print!(
+    "{}",
+    systemd::unit_file(&UnitOptions {
+      scope,
+      executable: &executable,
+      config_dir: &config_dir,
+      run_as: run_as.as_deref(),
+    })
+  );
+  Ok(())
+}
+
+fn request_for(command: &Command) -> (&'static str, Value) {
+  match command {
+    Command::Run | Command::SystemdUnit { .. } => unreachable!("handled without sending a request"),
+    Command::Status => ("get_snapshot", Value::Null),
+    Command::Push => ("manual_push_now", Value::Null),
+    Command::Interfaces => ("get_interfaces", Value::Null),
+    Command::LookupRecord { zone_id, domain } => ("lookup_record_id", json!({ "zoneId": zone_id, "domain": domain })),
+    Command::Stats => ("get_history_stats", Value::Null),
+    Command::Export { format } => ("export_history", json!({ "format": format })),
+    Command::ConfigGet { key } => ("get_setting", json!({ "key": key })),
+    Command::ConfigSet { key, text } => ("set_setting", json!({ "key": key, "text": text })),
+  }
+}
+
+fn execute(config_dir: &Path, standalone: bool, method: &'static str, params: Value) -> Result<Value> {
+  rt::block_on(async move {
+    if !standalone {
+      if let Some(result) = control::call(config_dir, method, params.clone()).await? {
+        return Ok(result);
+      }
+    }
+
+    let (loaded_config, config_path, config_recovery) = config::load_or_default_in(config_dir)?;
+    let state = AppState::new(
+      EngineOptions {
+        config: loaded_config,
+        config_path,
+        config_recovery,
+        resource_dir: None,
+        // One command runs and exits; there is no worker to wake up.
+        watch_network: false,
+      },
+      Arc::new(QuietEvents),
+    );
+    // Status and interface listings need a detection pass; a push detects on its own, settings need neither.
+    if matches!(method, "get_snapshot" | "get_interfaces") {
+      engine::bootstrap(&state).await;
+    } else {
+      engine::detect_token_backend(&state).await;
+    }
+    let result = control::dispatch(&state, method, params).await;
+    state.shutting_down.store(true, Ordering::SeqCst);
+    engine::flush_runtime_state(&state);
+    result.map_err(|error| anyhow!(error.message))
+  })
+}
+
+fn print_result(command: &Command, result: &Value, json: bool) {
+  // The export is already the requested format; `--json` would only wrap it in a string.
+  if let (Command::Export { .. }, Value::String(document)) = (command, result) {
+    print!("{document}");
+    return;
+  }
+  if json {
+    println!("{}", serde_json::to_string_pretty(result).unwrap_or_default());
+    return;
+  }
+  match command {
+    Command::Run | Command::SystemdUnit { .. } => {}
+    Command::Status => print_status(result),
+    Command::Push => {
+      let addresses = string_list(result.pointer("/currentIpv6Set"));
+      println!("pushed {}", addresses.join(", "));
+    }
+    Command::Interfaces => print_interfaces(result),
+    Command::Stats => print_stats(result),
+    Command::Export { .. } => {}
+    Command::LookupRecord { .. } => {
+      println!("{}", text(result, "/settings/cloudflare/recordId").unwrap_or("none"));
+    }
+    Command::ConfigGet { .. } => match result {
+      Value::String(value) => println!("{value}"),
+      other => println!("{}", serde_json::to_string_pretty(other).unwrap_or_default()),
+    },
+    Command::ConfigSet { key, .. } => println!("{key} = {result}"),
+  }
+}
+
+fn print_status(snapshot: &Value) {
+  let addresses = string_list(snapshot.pointer("/currentIpv6Set"));
+  let domain = text(snapshot, "/settings/cloudflare/domain").filter(|domain| !domain.is_empty());
+  let record_id = text(snapshot, "/settings/cloudflare/recordId").filter(|id| !id.is_empty());
+  let auto_push = snapshot.pointer("/settings/autoPush").and_then(Value::as_bool).unwrap_or(false);
+  let has_token = snapshot.pointer("/hasToken").and_then(Value::as_bool).unwrap_or(false);
+
+  println!("IPv6:         {}", text(snapshot, "/currentIpv6").unwrap_or("none"));
+  if addresses.len() > 1 {
+    println!("Publishing:   {}", addresses.join(", "));
+  }
+  println!(
+    "Interface:    {}",
+    text(snapshot, "/interfaceSelection/resolvedInterface").unwrap_or("automatic")
+  );
+  if let Some(warning) = text(snapshot, "/interfaceSelection/warning") {
+    println!("              {warning}");
+  }
+  println!(
+    "Record:       {} ({})",
+    domain.unwrap_or("not configured"),
+    record_id.unwrap_or("no record ID")
+  );
+  println!("Auto push:    {}", if auto_push { "on" } else { "off" });
+  match text(snapshot, "/cache/lastSyncTime") {
+    Some(time) => {
+      let kind = text(snapshot, "/cache/lastSyncStatus/kind").unwrap_or("idle");
+      match text(snapshot, "/cache/lastSyncStatus/message") {
+        Some(message) => println!("Last sync:    {time} ({kind}: {message})"),
+        None => println!("Last sync:    {time} ({kind})"),
+      }
+    }
+    None => println!("Last sync:    never"),
+  }
+  let token = if has_token { "set" } else { "not set" };
+  match text(snapshot, "/tokenStatus/message") {
+    Some(message) => println!("API token:    {token} ({message})"),
+    None => println!("API token:    {token}"),
+  }
+}
+
+fn print_stats(stats: &Value) {
+  let number = |pointer: &str| stats.pointer(pointer).and_then(Value::as_f64);
+  let count = |pointer: &str| stats.pointer(pointer).and_then(Value::as_u64).unwrap_or(0);
+
+  println!("Since:             {}", text(stats, "/periodStart").unwrap_or("no history yet"));
+  println!(
+    "Prefix lifetimes:  {} completed, median {}, mean {}, min {}, max {}",
+    count("/prefixLifetimes/completed"),
+    duration(number("/prefixLifetimes/medianSeconds")),
+    duration(number("/prefixLifetimes/meanSeconds")),
+    duration(number("/prefixLifetimes/minSeconds")),
+    duration(number("/prefixLifetimes/maxSeconds"))
+  );
+  for bucket in stats.pointer("/prefixLifetimes/buckets").and_then(Value::as_array).into_iter().flatten() {
+    println!(
+      "  {:<10} {}",
+      text(bucket, "/label").unwrap_or("?"),
+      bucket.pointer("/count").and_then(Value::as_u64).unwrap_or(0)
+    );
+  }
+  println!("Current prefix:    {}", duration(number("/prefixLifetimes/currentAgeSeconds")));
+
+  let failure_rate = number("/failureRate").map_or("n/a".to_string(), |rate| format!("{:.1}%", rate * 100.0));
+  println!(
+    "Pushes:            {} attempted, {} failed ({failure_rate})",
+    count("/pushAttempts"),
+    count("/pushFailures")
+  );
+  if let Some(codes) = stats.pointer("/failuresByCode").and_then(Value::as_object) {
+    for (code, failures) in codes {
+      println!("  {code:<10} {failures}");
+    }
+  }
+  println!(
+    "Time to update:    {} (mean over {} changes)",
+    duration(number("/meanTimeToUpdateSeconds")),
+    count("/updatesMeasured")
+  );
+  match number("/meanPushLatencyMs") {
+    Some(latency) => println!("Push latency:      {latency:.0} ms (mean)"),
+    None => println!("Push latency:      n/a"),
+  }
+
+  println!("Pushes per day:");
+  for day in stats.pointer("/pushesPerDay").and_then(Value::as_array).into_iter().flatten() {
+    println!(
+      "  {}  {} ok, {} failed",
+      text(day, "/date").unwrap_or("?"),
+      day.pointer("/succeeded").and_then(Value::as_u64).unwrap_or(0),
+      day.pointer("/failed").and_then(Value::as_u64).unwrap_or(0)
+    );
+  }
+  println!("Address uptime:");
+  for address in stats.pointer("/addressUptime").and_then(Value::as_array).into_iter().flatten() {
+    let seconds = address.pointer("/uptimeSeconds").and_then(Value::as_f64);
+    let until = text(address, "/lastSeenTime").unwrap_or("now");
+    println!(
+      "  {}  {} (from {} until {until})",
+      text(address, "/address").unwrap_or("?"),
+      duration(seconds),
+      text(address, "/firstSeenTime").unwrap_or("?")
+    );
+  }
+}
+
+// Compact human-readable duration, e.g. `3d 4h` or `12m 5s`.
+fn duration(seconds: Option<f64>) -> String {
+  let Some(seconds) = seconds else {
+    return "n/a".to_string();
+  };
+  let seconds = seconds.max(0.0).round() as u64;
+  let (days, hours, minutes) = (seconds / 86_400, seconds / 3_600 % 24, seconds / 60 % 60);
+  match (days, hours, minutes) {
+    (0, 0, 0) => format!("{seconds}s"),
+    (0, 0, _) => format!("{minutes}m {}s", seconds % 60),
+    (0, _, _) => format!("{hours}h {minutes}m"),
+    _ => format!("{days}d {hours}h"),
+  }
+}
+
+fn print_interfaces(interfaces: &Value) {
+  for interface in interfaces.as_array().into_iter().flatten() {
+    let label = text(interface, "/label").unwrap_or("?");
+    let id = text(interface, "/id").unwrap_or("?");
+    let kind = text(interface, "/kind").unwrap_or("unknown");
+    let excluded = interface.pointer("/excluded").and_then(Value::as_bool).unwrap_or(false);
+    let suffix = if excluded { ", skipped automatically" } else { "" };
+    if label == id {
+      println!("{label} [{kind}{suffix}]");
+    } else {
+      println!("{label} ({id}) [{kind}{suffix}]");
+    }
+    for address in string_list(interface.pointer("/ipv6Addresses")) {
+      println!("  {address}");
+    }
+  }
+}
+
+fn text<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+  value.pointer(pointer).and_then(Value::as_str)
+}
+
+fn string_list(value: Option<&Value>) -> Vec<&str> {
+  value
+    .and_then(Value::as_array)
+    .map(|items| items.iter().filter_map(Value::as_str).collect())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<Option<CliArgs>, String> {
+    parse_args(args.iter().map(|arg| arg.to_string()))
+  }
+
+  fn command(args: &[&str]) -> Command {
+    parse(args).unwrap().expect("not a help request").command
+  }
+
+  #[test]
+  fn commands_and_their_options_are_parsed() {
+    assert_eq!(command(&[]), Command::Run);
+    assert_eq!(command(&["status"]), Command::Status);
+    assert_eq!(
+      command(&["lookup-record", "--zone-id", "abc", "--domain=home.example.com"]),
+      Command::LookupRecord {
+        zone_id: Some("abc".to_string()),
+        domain: Some("home.example.com".to_string()),
+      }
+    );
+    assert_eq!(command(&["export"]), Command::Export { format: HistoryExportFormat::Csv });
+    assert_eq!(
+      command(&["export", "--format", "json"]),
+      Command::Export { format: HistoryExportFormat::Json }
+    );
+    assert_eq!(command(&["config", "get"]), Command::ConfigGet { key: None });
+    assert_eq!(
+      command(&["config", "get", "cloudflare.domain"]),
+      Command::ConfigGet {
+        key: Some("cloudflare.domain".to_string()),
+      }
+    );
+    assert_eq!(
+      command(&["systemd-unit", "--system", "--run-as", "ddns"]),
+      Command::SystemdUnit {
+        scope: UnitScope::System,
+        run_as: Some("ddns".to_string()),
+      }
+    );
+  }
+
+  #[test]
+  fn config_set_keeps_the_value_as_typed() {
+    for value in ["0123", "true", "home example", "{\"a\":1}"] {
+      assert_eq!(
+        command(&["config", "set", "cloudflare.recordId", value]),
+        Command::ConfigSet {
+          key: "cloudflare.recordId".to_string(),
+          text: value.to_string(),
+        }
+      );
+    }
+    // Everything after `--` is positional, so values may start with dashes.
+    assert_eq!(
+      command(&["config", "set", "--", "cloudflare.domain", "--odd"]),
+      Command::ConfigSet {
+        key: "cloudflare.domain".to_string(),
+        text: "--odd".to_string(),
+      }
+    );
+  }
+
+  #[test]
+  fn global_options_may_appear_anywhere() {
+    let args = parse(&["--json", "status", "--config", "/tmp/cf ddns", "--standalone"])
+      .unwrap()
+      .unwrap();
+    assert_eq!(args.command, Command::Status);
+    assert_eq!(args.config_dir, Some(PathBuf::from("/tmp/cf ddns")));
+    assert!(args.json);
+    assert!(args.standalone);
+  }
+
+  #[test]
+  fn help_is_not_an_error() {
+    for args in [&["-h"][..], &["--help"], &["status", "--help"]] {
+      assert!(parse(args).unwrap().is_none(), "{args:?}");
+    }
+  }
+
+  #[test]
+  fn mistakes_are_reported() {
+    let cases: [(&[&str], &str); 9] = [
+      (&["frobnicate"], "unknown command `frobnicate`"),
+      (&["status", "--verbose"], "unknown option `--verbose`"),
+      (&["status", "--config"], "--config needs a value"),
+      (&["status", "--config="], "--config needs a value"),
+      (&["export", "--format", "xml"], "unknown export format `xml`; use csv or json"),
+      (&["config"], "config needs `get` or `set`"),
+      (&["config", "set", "cloudflare.domain"], "config set needs a value"),
+      (&["config", "delete"], "unknown config command `delete`"),
+      (&["status", "extra"], "unexpected argument `extra`"),
+    ];
+    for (args, expected) in cases {
+      assert_eq!(parse(args).err().as_deref(), Some(expected), "{args:?}");
+    }
+  }
+}
@@ -1,12 +1,25 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use reqwest::StatusCode;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+#[derive(Debug, Serialize)]
+struct CreateDnsRecordRequest<'a> {
+  #[serde(rename = "type")]
+  record_type: &'a str,
+  name: &'a str,
+  content: &'a str,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  ttl: Option<u32>,
+}
+
 #[derive(Debug, Serialize)]
 struct UpdateDnsRecordRequest {
   content: String,
   #[serde(skip_serializing_if = "Option::is_none")]
   ttl: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  proxied: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -27,6 +40,75 @@ struct CloudflareErrorMessage {
 #[derive(Debug, Deserialize)]
 pub struct DnsRecordSummary {
   pub id: String,
+  #[serde(default)]
+  pub content: String,
+  // 1 means "automatic".
+  #[serde(default)]
+  pub ttl: Option<u32>,
+  #[serde(default)]
+  pub proxied: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenVerification {
+  pub status: String,
+  #[serde(default)]
+  pub expires_on: Option<DateTime<Utc>>,
+  #[serde(default)]
+  pub not_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZoneSummary {
+  id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZoneDetails {
+  account: AccountSummary,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountSummary {
+  id: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Cloudflare API request failed with HTTP {status}: {message}")]
+pub struct CloudflareApiError {
+  pub status: u16,
+  pub message: String,
+}
+
+// Cloudflare answers PATCH on a deleted record id with HTTP 404 (error code 81044).
+pub fn is_record_missing(error: &anyhow::Error) -> bool {
+  error
+    .downcast_ref::<CloudflareApiError>()
+    .is_some_and(|api_error| api_error.status == StatusCode::NOT_FOUND.as_u16())
+}
+
+fn is_unauthorized(error: &anyhow::Error) -> bool {
+  error
+    .downcast_ref::<CloudflareApiError>()
+    .is_some_and(|api_error| api_error.status == StatusCode::UNAUTHORIZED.as_u16())
+}
+
+// 401/403 mean the token itself was refused, as opposed to a missing record or a network error.
+pub fn is_auth_failure(error: &anyhow::Error) -> bool {
+  error.downcast_ref::<CloudflareApiError>().is_some_and(|api_error| {
+    api_error.status == StatusCode::UNAUTHORIZED.as_u16() || api_error.status == StatusCode::FORBIDDEN.as_u16()
+  })
+}
+
+// Short failure category for the history log: `http<status>` for API responses, `network` for transport errors.
+pub fn error_code(error: &anyhow::Error) -> String {
+  if let Some(api_error) = error.downcast_ref::<CloudflareApiError>() {
+    return format!("http{}", api_error.status);
+  }
+  if error.chain().any(|cause| cause.is::<reqwest::Error>()) {
+    return "network".to_string();
+  }
+  "other".to_string()
 }
 
 fn cloudflare_client() -> Result<reqwest::Client> {
@@ -59,14 +141,88 @@ async fn parse_envelope<T: DeserializeOwned>(response: reqwest::Response) -> Res
     .map(|item| format!("{}: {}", item.code, item.message))
     .collect::<Vec<_>>()
     .join(", ");
-  Err(anyhow::anyhow!(
-    "Cloudflare API request failed with HTTP {}: {}",
-    status.as_u16(),
-    if message.is_empty() { text } else { message }
-  ))
+  Err(
+    CloudflareApiError {
+      status: status.as_u16(),
+      message: if message.is_empty() { text } else { message },
+    }
+    .into(),
+  )
+}
+
+// `zone_id` locates the owning account for account-owned tokens, which the user endpoint answers with 401.
+pub async fn verify_token(token: &str, zone_id: &str) -> Result<TokenVerification> {
+  // Cloudflare API docs: "User API Tokens" -> "Verify Token" (`GET /user/tokens/verify`)
+  // returns `status` (`active`, `disabled`, `expired`) and the optional `expires_on` / `not_before` bounds.
+  let user_result = request_token_verification("https://api.cloudflare.com/client/v4/user/tokens/verify", token).await;
+  let zone_id = zone_id.trim();
+  match user_result {
+    Err(error) if is_unauthorized(&error) && !zone_id.is_empty() => {
+      // A token that cannot even read its zone is invalid either way; report the original 401 then.
+      let Ok(account_id) = zone_account_id(zone_id, token).await else {
+        return Err(error);
+      };
+      // Cloudflare API docs: "Account API Tokens" -> "Verify Token" (`GET /accounts/{account_id}/tokens/verify`).
+      let url = format!("https://api.cloudflare.com/client/v4/accounts/{account_id}/tokens/verify");
+      request_token_verification(&url, token).await
+    }
+    result => result,
+  }
+}
+
+async fn request_token_verification(url: &str, token: &str) -> Result<TokenVerification> {
+  let client = cloudflare_client()?;
+  let response = client
+    .get(url)
+    .bearer_auth(token)
+    .send()
+    .await
+    .context("failed to send Cloudflare token verify request")?;
+
+  parse_envelope::<TokenVerification>(response).await
+}
+
+async fn zone_account_id(zone_id: &str, token: &str) -> Result<String> {
+  // Cloudflare API docs: "Zone" -> "Zone Details" (`GET /zones/{zone_id}`) includes the owning `account`.
+  let client = cloudflare_client()?;
+  let response = client
+    .get(format!("https://api.cloudflare.com/client/v4/zones/{zone_id}"))
+    .bearer_auth(token)
+    .send()
+    .await
+    .context("failed to send Cloudflare zone details request")?;
+
+  let zone = parse_envelope::<ZoneDetails>(response).await?;
+  Ok(zone.account.id)
+}
+
+pub async fn find_zone_id(zone_name: &str, token: &str) -> Result<Option<String>> {
+  // Cloudflare API docs: "Zone" -> "List Zones" (`GET /zones`) supports exact `name` filtering.
+  let client = cloudflare_client()?;
+  let response = client
+    .get("https://api.cloudflare.com/client/v4/zones")
+    .bearer_auth(token)
+    .query(&[("name", zone_name)])
+    .send()
+    .await
+    .context("failed to send Cloudflare zone lookup request")?;
+
+  let zones = parse_envelope::<Vec<ZoneSummary>>(response).await?;
+  Ok(zones.into_iter().next().map(|zone| zone.id))
 }
 
 pub async fn find_aaaa_record_id(zone_id: &str, domain: &str, token: &str) -> Result<String> {
+  lookup_aaaa_record(zone_id, domain, token)
+    .await?
+    .map(|record| record.id)
+    .ok_or_else(|| anyhow::anyhow!("no AAAA record found for domain {}", domain))
+}
+
+pub async fn lookup_aaaa_record(zone_id: &str, domain: &str, token: &str) -> Result<Option<DnsRecordSummary>> {
+  Ok(list_aaaa_records(zone_id, domain, token).await?.into_iter().next())
+}
+
+pub async fn list_aaaa_records(zone_id: &str, domain: &str, token: &str) -> Result<Vec<DnsRecordSummary>> {
   // Cloudflare API docs: "DNS Records for a Zone" -> "List DNS Records"
   // (`GET /zones/{zone_id}/dns_records`) supports `type` and `name` query filtering.
   let url = format!("https://api.cloudflare.com/client/v4/zones/{zone_id}/dns_records");
@@ -79,12 +235,37 @@ pub async fn find_aaaa_record_id(zone_id: &str, domain: &str, token: &str) -> Re
     .await
     .context("failed to send Cloudflare record lookup request")?;
 
-  let records = parse_envelope::<Vec<DnsRecordSummary>>(response).await?;
-  records
-    .into_iter()
-    .next()
-    .map(|record| record.id)
-    .ok_or_else(|| anyhow::anyhow!("no AAAA record found for domain {}", domain))
+  parse_envelope::<Vec<DnsRecordSummary>>(response).await
+}
+
+pub async fn create_aaaa_record(
+  zone_id: &str,
+  domain: &str,
+  token: &str,
+  ipv6: &str,
+  ttl: Option<u32>,
+) -> Result<String> {
+  // Cloudflare API docs: "DNS Records for a Zone" -> "Create DNS Record"
+  // (`POST /zones/{zone_id}/dns_records`) returns the created record including its id.
+  let url = format!("https://api.cloudflare.com/client/v4/zones/{zone_id}/dns_records");
+  let body = CreateDnsRecordRequest {
+    record_type: "AAAA",
+    name: domain,
+    content: ipv6,
+    ttl,
+  };
+
+  let client = cloudflare_client()?;
+  let response = client
+    .post(url)
+    .bearer_auth(token)
+    .json(&body)
+    .send()
+    .await
+    .context("failed to send Cloudflare record create request")?;
+
+  let record = parse_envelope::<DnsRecordSummary>(response).await?;
+  Ok(record.id)
 }
 
 pub async fn update_aaaa_record(
@@ -94,24 +275,28 @@ pub async fn update_aaaa_record(
   ipv6: &str,
   ttl: Option<u32>,
 ) -> Result<()> {
-  // Cloudflare API docs: "DNS Records for a Zone" -> "Update DNS Record"
-  // (`PATCH /zones/{zone_id}/dns_records/{dns_record_id}`) with bearer token auth.
-  let url = format!(
-    "https://api.cloudflare.com/client/v4/zones/{zone_id}/dns_records/{record_id}"
-  );
   let body = UpdateDnsRecordRequest {
     // Cloudflare "Update DNS Record" accepts partial PATCH payloads.
     // We send only mutable AAAA data (`content` + optional `ttl`) to avoid unnecessary field churn.
     content: ipv6.to_string(),
     ttl,
+    proxied: None,
   };
+  patch_dns_record(zone_id, record_id, token, &body).await
+}
 
+async fn patch_dns_record(zone_id: &str, record_id: &str, token: &str, body: &UpdateDnsRecordRequest) -> Result<()> {
+  // Cloudflare API docs: "DNS Records for a Zone" -> "Update DNS Record"
+  // (`PATCH /zones/{zone_id}/dns_records/{dns_record_id}`) with bearer token auth.
+  let url = format!(
+    "https://api.cloudflare.com/client/v4/zones/{zone_id}/dns_records/{record_id}"
+  );
   let client = cloudflare_client()?;
 
   let response = client
     .patch(url)
     .bearer_auth(token)
-    .json(&body)
+    .json(body)
     .send()
     .await
     .context("failed to send Cloudflare update request")?;
@@ -119,3 +304,194 @@ pub async fn update_aaaa_record(
   let _ = parse_envelope::<serde_json::Value>(response).await?;
   Ok(())
 }
+
+pub async fn delete_dns_record(zone_id: &str, record_id: &str, token: &str) -> Result<()> {
+  // Cloudflare API docs: "DNS Records for a Zone" -> "Delete DNS Record"
+  // (`DELETE /zones/{zone_id}/dns_records/{dns_record_id}`).
+  let url = format!(
+    "https://api.cloudflare.com/client/v4/zones/{zone_id}/dns_records/{record_id}"
+  );
+  let client = cloudflare_client()?;
+  let response = client
+    .delete(url)
+    .bearer_auth(token)
+    .send()
+    .await
+    .context("failed to send Cloudflare delete request")?;
+
+  let _ = parse_envelope::<serde_json::Value>(response).await?;
+  Ok(())
+}
+
+#[derive(Debug, Default)]
+pub struct RrsetSyncSummary {
+  pub created: usize,
+  pub updated: usize,
+  pub deleted: usize,
+}
+
+pub async fn sync_aaaa_rrset(
+  zone_id: &str,
+  domain: &str,
+  token: &str,
+  addresses: &[String],
+  ttl: Option<u32>,
+) -> Result<RrsetSyncSummary> {
+  let existing = list_aaaa_records(zone_id, domain, token).await?;
+  let mut summary = RrsetSyncSummary::default();
+  for action in plan_rrset(&existing, addresses, ttl) {
+    match action {
+      RrsetAction::Update { record_id, address } => {
+        let body = UpdateDnsRecordRequest {
+          content: address.to_string(),
+          ttl,
+          proxied: Some(false),
+        };
+        patch_dns_record(zone_id, record_id, token, &body).await?;
+        summary.updated += 1;
+      }
+      RrsetAction::Delete { record_id } => {
+        delete_dns_record(zone_id, record_id, token).await?;
+        summary.deleted += 1;
+      }
+      RrsetAction::Create { address } => {
+        create_aaaa_record(zone_id, domain, token, address, ttl).await?;
+        summary.created += 1;
+      }
+    }
+  }
+  Ok(summary)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RrsetAction<'a> {
+  Update { record_id: &'a str, address: &'a str },
+  Delete { record_id: &'a str },
+  Create { address: &'a str },
+}
+
+// Records are published unproxied, like the ones `create_aaaa_record` makes: the point is a directly reachable
+// address. `ttl: None` keeps whatever TTL a matching record already has, as in single mode.
+fn plan_rrset<'a>(existing: &'a [DnsRecordSummary], addresses: &'a [String], ttl: Option<u32>) -> Vec<RrsetAction<'a>> {
+  let mut missing = addresses
+    .iter()
+    .filter(|address| !existing.iter().any(|record| record.content == **address))
+    .collect::<Vec<_>>();
+  let mut seen = Vec::new();
+  let mut actions = Vec::new();
+  for record in existing {
+    if addresses.contains(&record.content) && !seen.contains(&&record.content) {
+      seen.push(&record.content);
+      let ttl_differs = ttl.is_some_and(|ttl| record.ttl != Some(ttl));
+      if ttl_differs || record.proxied {
+        actions.push(RrsetAction::Update {
+          record_id: &record.id,
+          address: &record.content,
+        });
+      }
+      continue;
+    }
+    // Stale (or duplicate) records are repointed before creating new ones to keep record ids stable.
+    match missing.pop() {
+      Some(address) => actions.push(RrsetAction::Update {
+        record_id: &record.id,
+        address,
+      }),
+      None => actions.push(RrsetAction::Delete { record_id: &record.id }),
+    }
+  }
+  actions.extend(missing.into_iter().map(|address| RrsetAction::Create { address }));
+  actions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn record(id: &str, content: &str, ttl: u32, proxied: bool) -> DnsRecordSummary {
+    DnsRecordSummary {
+      id: id.to_string(),
+      content: content.to_string(),
+      ttl: Some(ttl),
+      proxied,
+    }
+  }
+
+  fn addresses(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+  }
+
+  #[test]
+  fn rrset_plan_repoints_stale_records_before_creating_or_deleting() {
+    let existing = [
+      record("a", "2001:db8::1", 300, false),
+      record("b", "2001:db8::old", 300, false),
+      record("c", "2001:db8::1", 300, false),
+    ];
+    let wanted = addresses(&["2001:db8::1", "2001:db8::2", "2001:db8::3"]);
+    assert_eq!(
+      plan_rrset(&existing, &wanted, Some(300)),
+      vec![
+        RrsetAction::Update {
+          record_id: "b",
+          address: "2001:db8::3",
+        },
+        RrsetAction::Update {
+          record_id: "c",
+          address: "2001:db8::2",
+        },
+      ]
+    );
+
+    let wanted = addresses(&["2001:db8::1"]);
+    assert_eq!(
+      plan_rrset(&existing, &wanted, Some(300)),
+      vec![
+        RrsetAction::Delete { record_id: "b" },
+        RrsetAction::Delete { record_id: "c" },
+      ]
+    );
+
+    let wanted = addresses(&["2001:db8::1", "2001:db8::2", "2001:db8::3", "2001:db8::4"]);
+    let existing = [record("a", "2001:db8::1", 300, false)];
+    assert_eq!(
+      plan_rrset(&existing, &wanted, Some(300)),
+      vec![
+        RrsetAction::Create { address: "2001:db8::2" },
+        RrsetAction::Create { address: "2001:db8::3" },
+        RrsetAction::Create { address: "2001:db8::4" },
+      ]
+    );
+  }
+
+  #[test]
+  fn rrset_plan_updates_matching_records_with_another_ttl_or_proxied() {
+    let existing = [
+      record("a", "2001:db8::1", 300, false),
+      record("b", "2001:db8::2", 1, false),
+      record("c", "2001:db8::3", 300, true),
+    ];
+    let wanted = addresses(&["2001:db8::1", "2001:db8::2", "2001:db8::3"]);
+    assert_eq!(
+      plan_rrset(&existing, &wanted, Some(300)),
+      vec![
+        RrsetAction::Update {
+          record_id: "b",
+          address: "2001:db8::2",
+        },
+        RrsetAction::Update {
+          record_id: "c",
+          address: "2001:db8::3",
+        },
+      ]
+    );
+    // Without a configured TTL only the proxied record needs fixing.
+    assert_eq!(
+      plan_rrset(&existing, &wanted, None),
+      vec![RrsetAction::Update {
+        record_id: "c",
+        address: "2001:db8::3",
+      }]
+    );
+  }
+}
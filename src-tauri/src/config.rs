@@ -1,36 +1,141 @@
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::Value;
+#[cfg(feature = "gui")]
 use tauri::{AppHandle, Manager};
 
-use crate::models::AppConfig;
+use crate::models::{AppConfig, ConfigRecovery, CONFIG_VERSION};
+use crate::runtime_state::RuntimeStateStore;
 
 const CONFIG_FILE_NAME: &str = "settings.json";
+// Rolling copies of the last good config: `settings.json.bak.1` is the newest.
+const CONFIG_BACKUP_COUNT: usize = 3;
+// First version that keeps the runtime cache in runtime_state.json instead of settings.json.
+const RUNTIME_STATE_SPLIT_VERSION: u32 = 2;
 
-pub fn load_or_default(app: &AppHandle) -> Result<(AppConfig, PathBuf)> {
+#[derive(Debug, thiserror::Error)]
+#[error("config version {found} is newer than supported version {supported}; update the app to load it")]
+pub struct UnsupportedConfigVersion {
+  pub found: u32,
+  pub supported: u32,
+}
+
+type Migration = fn(&mut Value) -> Result<()>;
+
+// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`; the last entry ends at `CONFIG_VERSION`.
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1, migrate_v1_to_v2];
+
+#[cfg(feature = "gui")]
+pub fn load_or_default(app: &AppHandle) -> Result<(AppConfig, PathBuf, Option<ConfigRecovery>)> {
   // Tauri Rust API docs: `PathResolver::app_config_dir` is the app-owned config directory.
   let config_dir = app
     .path()
     .app_config_dir()
     .context("failed to resolve app config directory")?;
-  std::fs::create_dir_all(&config_dir).context("failed to create app config directory")?;
+  load_or_default_in(&config_dir)
+}
+
+// Same directory Tauri's `app_config_dir` resolves to, so the daemon and the GUI share one settings.json.
+pub fn default_config_dir(app_identifier: &str) -> Option<PathBuf> {
+  #[cfg(target_os = "windows")]
+  let base = std::env::var_os("APPDATA").map(PathBuf::from);
+  #[cfg(target_os = "macos")]
+  let base = std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"));
+  #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+  let base = std::env::var_os("XDG_CONFIG_HOME")
+    .map(PathBuf::from)
+    .filter(|path| path.is_absolute())
+    .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+  base.map(|base| base.join(app_identifier))
+}
+
+pub fn load_or_default_in(config_dir: &Path) -> Result<(AppConfig, PathBuf, Option<ConfigRecovery>)> {
+  std::fs::create_dir_all(config_dir).context("failed to create app config directory")?;
   let config_path = config_dir.join(CONFIG_FILE_NAME);
 
   if !config_path.exists() {
     let config = AppConfig::default();
     save_config(&config_path, &config)?;
-    return Ok((config, config_path));
+    return Ok((config, config_path, None));
   }
 
-  let text = std::fs::read_to_string(&config_path).context("failed to read config file")?;
-  let mut config = serde_json::from_str::<AppConfig>(&text).context("failed to parse config json")?;
-  normalize(&mut config);
-  Ok((config, config_path))
+  match read_config_versioned(&config_path) {
+    Ok((mut config, stored_version)) => {
+      if stored_version < CONFIG_VERSION {
+        // Keep the file exactly as the older app wrote it before the upgraded shape replaces it.
+        backup_before_migration(&config_path, stored_version)?;
+        if stored_version < RUNTIME_STATE_SPLIT_VERSION {
+          RuntimeStateStore::new(&config_path).carry_over_legacy(&config.cache)?;
+        }
+        config.version = CONFIG_VERSION;
+        normalize(&mut config);
+        save_config(&config_path, &config)?;
+        return Ok((config, config_path, None));
+      }
+      normalize(&mut config);
+      Ok((config, config_path, None))
+    }
+    // A newer app wrote this file; restoring an older backup over it would silently drop its settings.
+    Err(error) if error.is::<UnsupportedConfigVersion>() => Err(error),
+    Err(error) => {
+      let (mut config, recovery) = recover_from_backup(&config_path, &error)?;
+      normalize(&mut config);
+      Ok((config, config_path, Some(recovery)))
+    }
+  }
 }
 
 pub fn save_config(path: &Path, config: &AppConfig) -> Result<()> {
   let json = serde_json::to_string_pretty(config).context("failed to serialize config")?;
-  std::fs::write(path, json).with_context(|| format!("failed to write config to {}", path.display()))?;
+  rotate_backups(path);
+  write_file_atomic(path, json.as_bytes()).with_context(|| format!("failed to write config to {}", path.display()))?;
+  Ok(())
+}
+
+// Writes to a sibling temp file, fsyncs it and renames it over `path`, so readers never see a partial file.
+pub fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+  write_file_atomic_with_mode(path, contents, None)
+}
+
+// Like `write_file_atomic`, but the file is owner-only (0600) from the moment it exists, not just after a chmod.
+pub fn write_private_file_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+  write_file_atomic_with_mode(path, contents, Some(0o600))
+}
+
+// `mode` is a Unix permission mode applied when the temp file is created; other platforms ignore it.
+fn write_file_atomic_with_mode(path: &Path, contents: &[u8], mode: Option<u32>) -> Result<()> {
+  let mut temp_name = path.file_name().context("path has no file name")?.to_os_string();
+  temp_name.push(".tmp");
+  let temp_path = path.with_file_name(temp_name);
+
+  let mut options = std::fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  if let Some(mode) = mode {
+    // The mode only applies to newly created files, so a temp file left behind by a crash must not be reused.
+    let _ = std::fs::remove_file(&temp_path);
+    options.create_new(true);
+    #[cfg(unix)]
+    options.mode(mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+  }
+  let mut file = options
+    .open(&temp_path)
+    .with_context(|| format!("failed to create temp file {}", temp_path.display()))?;
+  file.write_all(contents).context("failed to write temp file")?;
+  file.sync_all().context("failed to flush temp file")?;
+  drop(file);
+
+  // Rust std docs: `fs::rename` replaces the destination atomically on the same filesystem
+  // (`rename(2)` on Unix, `MoveFileExW` with `MOVEFILE_REPLACE_EXISTING` on Windows).
+  std::fs::rename(&temp_path, path).with_context(|| format!("failed to replace {}", path.display()))?;
+  sync_parent_dir(path);
   Ok(())
 }
 
@@ -38,4 +143,380 @@ pub fn normalize(config: &mut AppConfig) {
   if config.settings.local_homepage.web_port == 0 {
     config.settings.local_homepage.web_port = 8089;
   }
+  let cloudflare = &mut config.settings.cloudflare;
+  cloudflare.credential_id = cloudflare
+    .credential_id
+    .take()
+    .map(|id| id.trim().to_string())
+    .filter(|id| !id.is_empty());
+}
+
+// Parses and migrates a config file without touching it on disk.
+pub fn read_config(path: &Path) -> Result<AppConfig> {
+  read_config_versioned(path).map(|(config, _)| config)
+}
+
+// Modification time and size, used to notice edits made outside the app.
+pub fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+  let metadata = std::fs::metadata(path).ok()?;
+  Some((metadata.modified().ok()?, metadata.len()))
+}
+
+// Returns the migrated config together with the version the file was stored with.
+fn read_config_versioned(path: &Path) -> Result<(AppConfig, u32)> {
+  let text = std::fs::read_to_string(path).context("failed to read config file")?;
+  let document = serde_json::from_str::<Value>(&text).context("failed to parse config json")?;
+  parse_config_document(document)
+}
+
+// Runs a parsed config document of any stored version through the migration chain.
+pub fn parse_config_document(mut document: Value) -> Result<(AppConfig, u32)> {
+  let stored_version = document
+    .get("version")
+    .and_then(Value::as_u64)
+    .map(|version| version.min(u64::from(u32::MAX)) as u32)
+    .unwrap_or(0);
+  if stored_version > CONFIG_VERSION {
+    return Err(
+      UnsupportedConfigVersion {
+        found: stored_version,
+        supported: CONFIG_VERSION,
+      }
+      .into(),
+    );
+  }
+  migrate(&mut document, stored_version)?;
+  let config = serde_json::from_value::<AppConfig>(document).context("failed to parse config json")?;
+  Ok((config, stored_version))
+}
+
+fn migrate(document: &mut Value, stored_version: u32) -> Result<()> {
+  for (version, migration) in MIGRATIONS.iter().enumerate().skip(stored_version as usize) {
+    migration(document).with_context(|| format!("failed to migrate config from version {version}"))?;
+  }
+  if stored_version < CONFIG_VERSION {
+    document
+      .as_object_mut()
+      .context("config root is not a json object")?
+      .insert("version".to_string(), Value::from(CONFIG_VERSION));
+  }
+  Ok(())
+}
+
+// v0 (app 0.1.6 and earlier) had no `version`, and its cache only tracked a single `lastKnownIpv6`.
+fn migrate_v0_to_v1(document: &mut Value) -> Result<()> {
+  let Some(cache) = document.get_mut("cache").and_then(Value::as_object_mut) else {
+    return Ok(());
+  };
+  let has_set = cache
+    .get("lastKnownIpv6Set")
+    .and_then(Value::as_array)
+    .is_some_and(|set| !set.is_empty());
+  if !has_set {
+    if let Some(address) = cache.get("lastKnownIpv6").and_then(Value::as_str).map(str::to_string) {
+      cache.insert("lastKnownIpv6Set".to_string(), Value::Array(vec![Value::String(address)]));
+    }
+  }
+  Ok(())
+}
+
+// v2 moved the runtime cache to runtime_state.json. The document has no path to write it to, so the `cache` key is
+// left in place here and `load_or_default_in` carries it over before saving settings.json without it.
+fn migrate_v1_to_v2(_document: &mut Value) -> Result<()> {
+  Ok(())
+}
+
+fn backup_before_migration(path: &Path, stored_version: u32) -> Result<()> {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(format!(".v{stored_version}.bak"));
+  let backup = path.with_file_name(name);
+  // Never overwrite an earlier pre-migration copy; it is the only record of that shape.
+  if backup.exists() {
+    return Ok(());
+  }
+  std::fs::copy(path, &backup)
+    .with_context(|| format!("failed to back up config to {}", backup.display()))?;
+  Ok(())
+}
+
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(format!(".bak.{index}"));
+  path.with_file_name(name)
+}
+
+fn rotate_backups(path: &Path) {
+  // Only a config that still parses is worth keeping; never let a corrupt file push out a good backup.
+  if read_config(path).is_err() {
+    return;
+  }
+  for index in (1..CONFIG_BACKUP_COUNT).rev() {
+    let from = backup_path(path, index);
+    if from.exists() {
+      let _ = std::fs::rename(&from, backup_path(path, index + 1));
+    }
+  }
+  let _ = std::fs::copy(path, backup_path(path, 1));
+}
+
+fn recover_from_backup(path: &Path, load_error: &anyhow::Error) -> Result<(AppConfig, ConfigRecovery)> {
+  let reason = format!("{load_error:#}");
+  for index in 1..=CONFIG_BACKUP_COUNT {
+    let backup = backup_path(path, index);
+    let Ok(config) = read_config(&backup) else {
+      continue;
+    };
+
+    // Keep the damaged file around for inspection instead of silently overwriting it.
+    let mut corrupt_name = path.file_name().unwrap_or_default().to_os_string();
+    corrupt_name.push(".corrupt");
+    let corrupt_path = path.with_file_name(corrupt_name);
+    let corrupt_copy = std::fs::rename(path, &corrupt_path)
+      .ok()
+      .map(|_| corrupt_path.display().to_string());
+
+    let json = serde_json::to_string_pretty(&config).context("failed to serialize config")?;
+    write_file_atomic(path, json.as_bytes())
+      .with_context(|| format!("failed to restore config to {}", path.display()))?;
+    eprintln!("config file was unreadable ({reason}), restored from {}", backup.display());
+
+    return Ok((
+      config,
+      ConfigRecovery {
+        recovered_from: backup.display().to_string(),
+        reason,
+        corrupt_copy,
+        recovered_time: Utc::now().to_rfc3339(),
+      },
+    ));
+  }
+  Err(anyhow::anyhow!("{reason}; no valid config backup found next to {}", path.display()))
+}
+
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+  // POSIX: the rename itself is only durable once the containing directory is fsynced.
+  if let Some(dir) = path.parent() {
+    if let Ok(dir) = std::fs::File::open(dir) {
+      let _ = dir.sync_all();
+    }
+  }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::models::RecordMode;
+
+  // settings.json as written by app 0.1.6, before `version` existed.
+  const V0_SETTINGS: &str = r#"{
+  "settings": {
+    "selectedInterface": "eth0",
+    "autoPush": true,
+    "launchOnStartup": false,
+    "lightweightMode": false,
+    "followSystemTheme": true,
+    "themeMode": "dark",
+    "languageMode": "system",
+    "cloudflare": {
+      "zoneId": "023e105f4ecef8ad9ca31a8372d0c353",
+      "domain": "home.example.com",
+      "recordId": "372e67954025e0ba6aaa6d586b9e0b59",
+      "ttl": 120
+    },
+    "localHomepage": {
+      "webPort": 8089,
+      "services": []
+    }
+  },
+  "cache": {
+    "lastKnownIpv6": "2001:db8:1::10",
+    "lastIpv6ChangeTime": "2025-03-02T08:15:00+00:00",
+    "lastSyncTime": "2025-03-02T08:15:04+00:00",
+    "lastSyncStatus": {
+      "kind": "success",
+      "message": "AAAA record updated"
+    }
+  }
+}"#;
+
+  // settings.json as written by the first multi-value release, still carrying the cache inline.
+  const V1_SETTINGS: &str = r#"{
+  "version": 1,
+  "settings": {
+    "selectedInterface": null,
+    "interfaceCandidates": [],
+    "autoPush": true,
+    "launchOnStartup": true,
+    "lightweightMode": false,
+    "followSystemTheme": false,
+    "themeMode": "light",
+    "languageMode": "system",
+    "cloudflare": {
+      "zoneId": "023e105f4ecef8ad9ca31a8372d0c353",
+      "domain": "nas.example.com",
+      "recordId": "",
+      "ttl": null,
+      "recordMode": "multiValue",
+      "maxRecords": 2
+    }
+  },
+  "cache": {
+    "lastKnownIpv6": "2001:db8:1::20",
+    "lastKnownIpv6Set": ["2001:db8:1::20", "2001:db8:1::21"],
+    "lastIpv6ChangeTime": "2025-06-11T19:40:00+00:00",
+    "lastSyncTime": "2025-06-11T19:40:02+00:00",
+    "lastSyncStatus": {
+      "kind": "success",
+      "message": null
+    }
+  }
+}"#;
+
+  fn temp_config_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cf-ddns-config-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn migrate_fills_the_address_set_of_a_pre_version_document() {
+    let mut document = serde_json::from_str::<Value>(V0_SETTINGS).unwrap();
+    migrate(&mut document, 0).unwrap();
+    assert_eq!(document["version"], json!(CONFIG_VERSION));
+    assert_eq!(document["cache"]["lastKnownIpv6Set"], json!(["2001:db8:1::10"]));
+    assert_eq!(document["settings"]["cloudflare"]["recordId"], json!("372e67954025e0ba6aaa6d586b9e0b59"));
+  }
+
+  #[test]
+  fn migrate_keeps_an_existing_address_set() {
+    let mut document = serde_json::from_str::<Value>(V1_SETTINGS).unwrap();
+    let before = document.clone();
+    migrate(&mut document, 1).unwrap();
+    assert_eq!(document["version"], json!(CONFIG_VERSION));
+    assert_eq!(document["cache"], before["cache"]);
+    assert_eq!(document["settings"], before["settings"]);
+  }
+
+  #[test]
+  fn read_config_upgrades_a_pre_version_file() {
+    let dir = temp_config_dir("read-v0");
+    let path = dir.join(CONFIG_FILE_NAME);
+    std::fs::write(&path, V0_SETTINGS).unwrap();
+
+    let config = read_config(&path).unwrap();
+    assert_eq!(config.version, CONFIG_VERSION);
+    assert_eq!(config.settings.selected_interface.as_deref(), Some("eth0"));
+    assert_eq!(config.settings.cloudflare.domain, "home.example.com");
+    assert_eq!(config.settings.cloudflare.ttl, Some(120));
+    assert_eq!(config.settings.cloudflare.record_mode, RecordMode::Single);
+    assert_eq!(config.cache.last_known_ipv6_set, vec!["2001:db8:1::10".to_string()]);
+    // Reading alone never rewrites the file.
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), V0_SETTINGS);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn read_config_upgrades_a_v1_file() {
+    let dir = temp_config_dir("read-v1");
+    let path = dir.join(CONFIG_FILE_NAME);
+    std::fs::write(&path, V1_SETTINGS).unwrap();
+
+    let config = read_config(&path).unwrap();
+    assert_eq!(config.version, CONFIG_VERSION);
+    assert_eq!(config.settings.cloudflare.record_mode, RecordMode::MultiValue);
+    assert_eq!(config.settings.cloudflare.max_records, 2);
+    assert_eq!(
+      config.cache.committed_addresses(),
+      vec!["2001:db8:1::20".to_string(), "2001:db8:1::21".to_string()]
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn loading_an_old_file_backs_it_up_before_rewriting() {
+    for (stored_version, fixture) in [(0, V0_SETTINGS), (1, V1_SETTINGS)] {
+      let dir = temp_config_dir(&format!("backup-v{stored_version}"));
+      let path = dir.join(CONFIG_FILE_NAME);
+      std::fs::write(&path, fixture).unwrap();
+
+      let (config, _, recovery) = load_or_default_in(&dir).unwrap();
+      assert!(recovery.is_none());
+      assert_eq!(config.version, CONFIG_VERSION);
+      let backup = dir.join(format!("{CONFIG_FILE_NAME}.v{stored_version}.bak"));
+      assert_eq!(std::fs::read_to_string(&backup).unwrap(), fixture);
+      let rewritten = serde_json::from_str::<Value>(&std::fs::read_to_string(&path).unwrap()).unwrap();
+      assert_eq!(rewritten["version"], json!(CONFIG_VERSION));
+
+      let _ = std::fs::remove_dir_all(&dir);
+    }
+  }
+
+  #[test]
+  fn upgrading_a_v1_file_moves_its_cache_to_runtime_state() {
+    let dir = temp_config_dir("cache-v1");
+    let path = dir.join(CONFIG_FILE_NAME);
+    std::fs::write(&path, V1_SETTINGS).unwrap();
+
+    load_or_default_in(&dir).unwrap();
+    let rewritten = serde_json::from_str::<Value>(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(rewritten.get("cache").is_none());
+    let cache = RuntimeStateStore::new(&path).load().expect("runtime_state.json was written");
+    assert_eq!(cache.last_known_ipv6.as_deref(), Some("2001:db8:1::20"));
+    assert_eq!(cache.last_known_ipv6_set, vec!["2001:db8:1::20".to_string(), "2001:db8:1::21".to_string()]);
+    assert_eq!(cache.last_sync_time.as_deref(), Some("2025-06-11T19:40:02+00:00"));
+
+    // A second start reads the upgraded file and keeps using the carried-over state.
+    let (config, _, _) = load_or_default_in(&dir).unwrap();
+    assert!(config.cache.last_known_ipv6_set.is_empty());
+    assert_eq!(RuntimeStateStore::new(&path).load().unwrap().last_known_ipv6_set.len(), 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn carry_over_never_replaces_existing_runtime_state() {
+    let dir = temp_config_dir("cache-existing");
+    let path = dir.join(CONFIG_FILE_NAME);
+    let store = RuntimeStateStore::new(&path);
+    let mut newer = crate::models::RuntimeCache::default();
+    newer.commit_addresses(&["2001:db8:9::1".to_string()], "2025-07-01T00:00:00+00:00".to_string());
+    store.carry_over_legacy(&newer).unwrap();
+    std::fs::write(&path, V1_SETTINGS).unwrap();
+
+    load_or_default_in(&dir).unwrap();
+    assert_eq!(store.load().unwrap().last_known_ipv6_set, vec!["2001:db8:9::1".to_string()]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn a_file_from_a_newer_app_is_rejected_and_left_alone() {
+    let dir = temp_config_dir("newer");
+    let path = dir.join(CONFIG_FILE_NAME);
+    let mut document = serde_json::from_str::<Value>(V1_SETTINGS).unwrap();
+    document["version"] = json!(CONFIG_VERSION + 1);
+    let text = serde_json::to_string_pretty(&document).unwrap();
+    std::fs::write(&path, &text).unwrap();
+
+    let error = read_config(&path).unwrap_err();
+    let unsupported = error.downcast_ref::<UnsupportedConfigVersion>().unwrap();
+    assert_eq!(unsupported.found, CONFIG_VERSION + 1);
+
+    let error = load_or_default_in(&dir).unwrap_err();
+    assert!(error.is::<UnsupportedConfigVersion>());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+    assert!(!dir.join(format!("{CONFIG_FILE_NAME}.corrupt")).exists());
+    assert!(!dir.join(format!("{CONFIG_FILE_NAME}.v{}.bak", CONFIG_VERSION + 1)).exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
 }
@@ -0,0 +1,639 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::engine::{self, AppState};
+use crate::models::{
+  AppSettings, ExportHistoryRequest, ExportSettingsRequest, ExternalImportRequest, HistoryQuery, ImportSettingsRequest,
+  LookupRecordIdRequest, RotateTokenRequest, SaveCredentialRequest, SaveSettingsRequest,
+};
+use crate::{rt, validation};
+
+// JSON-RPC 2.0 specification, section 5.1: pre-defined error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Start of the range the specification reserves for implementation-defined server errors.
+const OPERATION_FAILED: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+  jsonrpc: String,
+  method: String,
+  #[serde(default)]
+  params: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+  jsonrpc: String,
+  id: Value,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  result: Option<Value>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+  pub code: i64,
+  pub message: String,
+  // Structured details, e.g. the field errors of a rejected save.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub data: Option<Value>,
+}
+
+impl RpcError {
+  fn new(code: i64, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+      data: None,
+    }
+  }
+
+  fn failed(message: String) -> Self {
+    Self::new(OPERATION_FAILED, message)
+  }
+}
+
+impl From<engine::SaveSettingsError> for RpcError {
+  fn from(error: engine::SaveSettingsError) -> Self {
+    match error {
+      engine::SaveSettingsError::Invalid(errors) => Self {
+        code: INVALID_PARAMS,
+        message: validation::describe_errors(&errors),
+        data: serde_json::to_value(errors).ok(),
+      },
+      engine::SaveSettingsError::Failed(message) => Self::failed(message),
+    }
+  }
+}
+
+impl From<String> for RpcError {
+  fn from(message: String) -> Self {
+    Self::failed(message)
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidateSettingsParams {
+  settings: AppSettings,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteCredentialParams {
+  id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GetSettingParams {
+  key: Option<String>,
+}
+
+// `text` is a command-line string converted to the type of the setting it replaces; `value` is used as given.
+#[derive(Debug, Deserialize)]
+struct SetSettingParams {
+  key: String,
+  #[serde(default)]
+  value: Option<Value>,
+  #[serde(default)]
+  text: Option<String>,
+}
+
+// Runs one method against the engine. Methods share names with the Tauri commands; where a command takes a
+// `request`, that object is the params, and other arguments (`settings`, `id`) are passed by name.
+// The socket server and the CLI's standalone mode both come through here, so a call behaves the same everywhere.
+pub async fn dispatch(state: &Arc<AppState>, method: &str, params: Value) -> Result<Value, RpcError> {
+  match method {
+    "get_snapshot" => encode(state.snapshot()),
+    "save_settings" => {
+      let request: SaveSettingsRequest = decode_params(params)?;
+      blocking(state, move |state| engine::save_settings(state, request)).await?;
+      encode(state.snapshot())
+    }
+    "validate_settings" => {
+      let params: ValidateSettingsParams = decode_params(params)?;
+      encode(state.validate(&params.settings))
+    }
+    "manual_push_now" => {
+      engine::run_manual_push(state).await.map_err(RpcError::failed)?;
+      encode(state.snapshot())
+    }
+    "lookup_record_id" => {
+      let request: LookupRecordIdRequest = decode_params(params)?;
+      engine::lookup_record_id(state, request).await?;
+      encode(state.snapshot())
+    }
+    "save_credential" => {
+      let request: SaveCredentialRequest = decode_params(params)?;
+      blocking(state, move |state| engine::save_credential(state, request)).await?;
+      encode(state.snapshot())
+    }
+    "delete_credential" => {
+      let params: DeleteCredentialParams = decode_params(params)?;
+      blocking(state, move |state| engine::delete_credential(state, &params.id)).await?;
+      encode(state.snapshot())
+    }
+    "rotate_token" => {
+      let request: RotateTokenRequest = decode_params(params)?;
+      encode(engine::rotate_token(state, request).await?)
+    }
+    "export_settings_bundle" => {
+      let request: ExportSettingsRequest = decode_params(params)?;
+      blocking(state, move |state| engine::export_settings_bundle(state, request)).await?;
+      Ok(Value::Null)
+    }
+    "preview_settings_import" => {
+      let request: ImportSettingsRequest = decode_params(params)?;
+      encode(blocking(state, move |state| engine::preview_settings_import(state, &request)).await?)
+    }
+    "import_settings_bundle" => {
+      let request: ImportSettingsRequest = decode_params(params)?;
+      blocking(state, move |state| engine::import_settings_bundle(state, &request)).await?;
+      encode(state.snapshot())
+    }
+    "import_external_config" => {
+      let request: ExternalImportRequest = decode_params(params)?;
+      encode(engine::import_external_config(state, request).await?)
+    }
+    "get_interfaces" => encode(state.interfaces.lock().clone()),
+    "get_prefix_history" => encode(state.prefix_history.lock().records().to_vec()),
+    "get_history" => {
+      let query: HistoryQuery = decode_params(params)?;
+      encode(state.history.lock().page(&query))
+    }
+    "get_history_stats" => encode(engine::history_stats(state)),
+    "export_history" => {
+      let request: ExportHistoryRequest = decode_params(params)?;
+      encode(engine::export_history(state, request)?)
+    }
+    "get_setting" => {
+      let params: GetSettingParams = decode_params(params)?;
+      let settings = encode(state.config.lock().settings.clone())?;
+      match params.key.as_deref() {
+        Some(key) => setting_at(&settings, key).cloned(),
+        None => Ok(settings),
+      }
+    }
+    "set_setting" => {
+      let params: SetSettingParams = decode_params(params)?;
+      let current = encode(state.config.lock().settings.clone())?;
+      let candidates = match (params.value, params.text) {
+        (Some(value), None) => vec![value],
+        (None, Some(text)) => values_from_text(setting_at(&current, &params.key)?, &text),
+        _ => return Err(RpcError::new(INVALID_PARAMS, "set_setting needs either `value` or `text`")),
+      };
+      // The first candidate that makes valid settings wins; the error of the last one is reported otherwise.
+      let mut settings = Err(RpcError::new(
+        INVALID_PARAMS,
+        format!("invalid value for `{}`: expected JSON", params.key),
+      ));
+      for candidate in candidates {
+        let mut document = current.clone();
+        *setting_at_mut(&mut document, &params.key)? = candidate;
+        settings = serde_json::from_value::<AppSettings>(document)
+          .map_err(|error| RpcError::new(INVALID_PARAMS, format!("invalid value for `{}`: {error}", params.key)));
+        if settings.is_ok() {
+          break;
+        }
+      }
+      engine::replace_settings(state, settings?)?;
+      let settings = encode(state.config.lock().settings.clone())?;
+      setting_at(&settings, &params.key).cloned()
+    }
+    _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method `{method}`"))),
+  }
+}
+
+// The keyring, credential helpers and passphrase KDF block, so keep them off the async workers.
+async fn blocking<T, E>(
+  state: &Arc<AppState>,
+  call: impl FnOnce(&Arc<AppState>) -> Result<T, E> + Send + 'static,
+) -> Result<T, RpcError>
+where
+  T: Send + 'static,
+  E: Send + 'static,
+  RpcError: From<E>,
+{
+  let state = state.clone();
+  rt::spawn_blocking(move || call(&state))
+    .await
+    .map_err(|error| RpcError::failed(format!("background task failed: {error}")))?
+    .map_err(RpcError::from)
+}
+
+fn encode<T: Serialize>(value: T) -> Result<Value, RpcError> {
+  serde_json::to_value(value).map_err(|error| RpcError::failed(format!("failed to encode result: {error}")))
+}
+
+fn decode_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+  let params = if params.is_null() { json!({}) } else { params };
+  serde_json::from_value(params).map_err(|error| RpcError::new(INVALID_PARAMS, format!("invalid params: {error}")))
+}
+
+// Settings keys are the camelCase names from settings.json joined with dots; array items are addressed by index,
+// e.g. `cloudflare.domain` or `localHomepage.services.0.port`.
+fn setting_at<'a>(settings: &'a Value, key: &str) -> Result<&'a Value, RpcError> {
+  key
+    .split('.')
+    .try_fold(settings, |value, segment| match value {
+      Value::Object(map) => map.get(segment),
+      Value::Array(items) => segment.parse::<usize>().ok().and_then(|index| items.get(index)),
+      _ => None,
+    })
+    .ok_or_else(|| unknown_setting(key))
+}
+
+fn setting_at_mut<'a>(settings: &'a mut Value, key: &str) -> Result<&'a mut Value, RpcError> {
+  key
+    .split('.')
+    .try_fold(settings, |value, segment| match value {
+      Value::Object(map) => map.get_mut(segment),
+      Value::Array(items) => segment.parse::<usize>().ok().and_then(|index| items.get_mut(index)),
+      _ => None,
+    })
+    .ok_or_else(|| unknown_setting(key))
+}
+
+// String settings take the text verbatim, so `0123` or `true` stay strings. Other types need JSON. Unset optional
+// settings have no type to go by, so they try JSON first and then the plain string.
+fn values_from_text(current: &Value, text: &str) -> Vec<Value> {
+  let parsed = serde_json::from_str::<Value>(text).ok();
+  match current {
+    Value::String(_) => vec![Value::String(text.to_string())],
+    Value::Null => parsed.into_iter().chain([Value::String(text.to_string())]).collect(),
+    _ => parsed.into_iter().collect(),
+  }
+}
+
+fn unknown_setting(key: &str) -> RpcError {
+  RpcError::new(INVALID_PARAMS, format!("unknown setting `{key}`"))
+}
+
+// Serves the control socket for as long as the engine runs.
+pub fn spawn_server(state: Arc<AppState>) {
+  let Some(config_dir) = state.config_path.parent().map(Path::to_path_buf) else {
+    return;
+  };
+  rt::spawn(async move {
+    if let Err(error) = serve(state, &config_dir).await {
+      eprintln!("control socket unavailable: {error:#}");
+    }
+  });
+}
+
+#[cfg(unix)]
+fn socket_path(config_dir: &Path) -> std::path::PathBuf {
+  config_dir.join("control.sock")
+}
+
+// Pipe names are machine-wide, so derive one from the config directory to keep separate setups apart.
+#[cfg(windows)]
+fn pipe_name(config_dir: &Path) -> String {
+  // FNV-1a; stable across builds, unlike `DefaultHasher`.
+  let hash = config_dir
+    .to_string_lossy()
+    .to_lowercase()
+    .bytes()
+    .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+      (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+  format!(r"\\.\pipe\cf-ddns-ipv6-{hash:016x}")
+}
+
+#[cfg(unix)]
+async fn serve(state: Arc<AppState>, config_dir: &Path) -> Result<()> {
+  use std::os::unix::fs::{MetadataExt, PermissionsExt};
+  use tokio::net::{UnixListener, UnixStream};
+
+  let path = socket_path(config_dir);
+  if UnixStream::connect(&path).await.is_ok() {
+    anyhow::bail!("another instance is already listening on {}", path.display());
+  }
+  // A socket file left behind by an instance that did not shut down cleanly would make `bind` fail.
+  let _ = std::fs::remove_file(&path);
+  let listener = UnixListener::bind(&path).with_context(|| format!("failed to bind {}", path.display()))?;
+  // Connecting needs write access to the socket file, so owner-only permissions keep other users out.
+  std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+    .with_context(|| format!("failed to restrict permissions of {}", path.display()))?;
+  let owner = std::fs::metadata(&path)
+    .with_context(|| format!("failed to read {}", path.display()))?
+    .uid();
+
+  loop {
+    tokio::select! {
+      accepted = listener.accept() => {
+        let (stream, _) = accepted.context("failed to accept control connection")?;
+        // Some systems ignore the mode of socket files, and there is a window between `bind` and
+        // `set_permissions`; checking the peer's user closes both gaps.
+        match stream.peer_cred() {
+          Ok(peer) if peer.uid() == owner => {
+            rt::spawn(handle_connection(state.clone(), stream));
+          }
+          _ => eprintln!("rejected control connection from another user"),
+        }
+      }
+      _ = engine::wait_for_shutdown(state.shutting_down.clone()) => break,
+    }
+  }
+  let _ = std::fs::remove_file(&path);
+  Ok(())
+}
+
+#[cfg(windows)]
+async fn serve(state: Arc<AppState>, config_dir: &Path) -> Result<()> {
+  use tokio::net::windows::named_pipe::ServerOptions;
+
+  let name = pipe_name(config_dir);
+  // Tokio named pipe docs: `first_pipe_instance` makes creation fail when another process already owns the name.
+  // The default security descriptor gives full access to the creating user, SYSTEM and administrators only;
+  // everyone else may open the pipe for reading, which is not enough to send a request.
+  let mut server = ServerOptions::new()
+    .first_pipe_instance(true)
+    .reject_remote_clients(true)
+    .create(&name)
+    .with_context(|| format!("failed to create {name}"))?;
+
+  loop {
+    tokio::select! {
+      connected = server.connect() => {
+        connected.context("failed to accept control connection")?;
+        // Each client needs its own pipe instance; create the next one before handing this one off.
+        let next = ServerOptions::new()
+          .reject_remote_clients(true)
+          .create(&name)
+          .with_context(|| format!("failed to create {name}"))?;
+        let stream = std::mem::replace(&mut server, next);
+        rt::spawn(handle_connection(state.clone(), stream));
+      }
+      _ = engine::wait_for_shutdown(state.shutting_down.clone()) => break,
+    }
+  }
+  Ok(())
+}
+
+// One JSON-RPC request per line, one response line per request that is not a notification.
+async fn handle_connection<S>(state: Arc<AppState>, stream: S)
+where
+  S: AsyncRead + AsyncWrite + Send + 'static,
+{
+  let (reader, mut writer) = tokio::io::split(stream);
+  let mut lines = BufReader::new(reader).lines();
+  while let Ok(Some(line)) = lines.next_line().await {
+    if line.trim().is_empty() {
+      continue;
+    }
+    let Some(response) = handle_line(&state, &line).await else {
+      continue;
+    };
+    let Ok(mut text) = serde_json::to_string(&response) else {
+      break;
+    };
+    text.push('\n');
+    if writer.write_all(text.as_bytes()).await.is_err() {
+      break;
+    }
+  }
+}
+
+// Returns `None` for a notification, a request without an `id` member; the specification forbids answering those.
+// Requests that cannot be parsed are always answered, with a null `id` when theirs cannot be read.
+async fn handle_line(state: &Arc<AppState>, line: &str) -> Option<Response> {
+  let value = match serde_json::from_str::<Value>(line) {
+    Ok(value) => value,
+    Err(error) => {
+      return Some(response(Value::Null, Err(RpcError::new(PARSE_ERROR, format!("parse error: {error}")))));
+    }
+  };
+  let id = value.get("id").cloned();
+  let request = match serde_json::from_value::<Request>(value) {
+    Ok(request) if request.jsonrpc == "2.0" => request,
+    Ok(_) => {
+      let error = RpcError::new(INVALID_REQUEST, "`jsonrpc` must be \"2.0\"");
+      return Some(response(id.unwrap_or(Value::Null), Err(error)));
+    }
+    Err(error) => {
+      let error = RpcError::new(INVALID_REQUEST, format!("invalid request: {error}"));
+      return Some(response(id.unwrap_or(Value::Null), Err(error)));
+    }
+  };
+  let result = dispatch(state, &request.method, request.params).await;
+  Some(response(id?, result))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Response {
+  let (result, error) = match result {
+    Ok(value) => (Some(value), None),
+    Err(error) => (None, Some(error)),
+  };
+  Response {
+    jsonrpc: "2.0".to_string(),
+    id,
+    result,
+    error,
+  }
+}
+
+// Sends one request to the instance serving `config_dir`; `Ok(None)` when no instance is listening.
+pub async fn call(config_dir: &Path, method: &str, params: Value) -> Result<Option<Value>> {
+  let stream = match connect(config_dir).await {
+    Ok(stream) => stream,
+    Err(error) if matches!(error.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused) => {
+      return Ok(None);
+    }
+    Err(error) => return Err(error).context("failed to connect to the running instance"),
+  };
+  let (reader, mut writer) = tokio::io::split(stream);
+  let mut request = serde_json::to_string(&json!({
+    "jsonrpc": "2.0",
+    "id": 1,
+    "method": method,
+    "params": params,
+  }))?;
+  request.push('\n');
+  writer
+    .write_all(request.as_bytes())
+    .await
+    .context("failed to send request to the running instance")?;
+
+  let line = BufReader::new(reader)
+    .lines()
+    .next_line()
+    .await
+    .context("failed to read the running instance's response")?
+    .ok_or_else(|| anyhow!("the running instance closed the connection without answering"))?;
+  let response: Response = serde_json::from_str(&line).context("the running instance sent an invalid response")?;
+  match response.error {
+    Some(error) => Err(anyhow!(error.message)),
+    None => Ok(Some(response.result.unwrap_or(Value::Null))),
+  }
+}
+
+#[cfg(unix)]
+async fn connect(config_dir: &Path) -> std::io::Result<tokio::net::UnixStream> {
+  tokio::net::UnixStream::connect(socket_path(config_dir)).await
+}
+
+#[cfg(windows)]
+async fn connect(config_dir: &Path) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+  use tokio::net::windows::named_pipe::ClientOptions;
+  use windows_sys::Win32::Foundation::ERROR_PIPE_BUSY;
+
+  let name = pipe_name(config_dir);
+  // Tokio named pipe docs: retry while every server instance is busy with another client.
+  for _ in 0..20 {
+    match ClientOptions::new().open(&name) {
+      Err(error) if error.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+      }
+      result => return result,
+    }
+  }
+  ClientOptions::new().open(&name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::engine::{EngineEvents, EngineOptions};
+  use crate::models::{AppConfig, PrefixChangedEvent};
+
+  struct NoEvents;
+
+  impl EngineEvents for NoEvents {
+    fn snapshot_changed(&self, _state: &Arc<AppState>) {}
+    fn network_changed(&self, _state: &Arc<AppState>) {}
+    fn prefix_changed(&self, _event: &PrefixChangedEvent) {}
+    fn alert(&self, _message: &str) {}
+    fn settings_applied(&self, _state: &Arc<AppState>) -> Result<(), String> {
+      Ok(())
+    }
+  }
+
+  fn test_state(name: &str) -> Arc<AppState> {
+    let dir = std::env::temp_dir().join(format!("cf-ddns-control-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    AppState::new(
+      EngineOptions {
+        config: AppConfig::default(),
+        config_path: dir.join("settings.json"),
+        config_recovery: None,
+        resource_dir: None,
+        watch_network: false,
+      },
+      Arc::new(NoEvents),
+    )
+  }
+
+  async fn answer(state: &Arc<AppState>, line: &str) -> Value {
+    let response = handle_line(state, line).await.expect("request should be answered");
+    serde_json::to_value(response).unwrap()
+  }
+
+  #[tokio::test]
+  async fn dispatch_reads_and_writes_settings_by_key() {
+    let state = test_state("settings");
+    let params = json!({ "key": "cloudflare.domain", "text": "home.example.com" });
+    assert_eq!(dispatch(&state, "set_setting", params).await.unwrap(), json!("home.example.com"));
+    assert_eq!(state.config.lock().settings.cloudflare.domain, "home.example.com");
+
+    let params = json!({ "key": "cloudflare.domain" });
+    assert_eq!(dispatch(&state, "get_setting", params).await.unwrap(), json!("home.example.com"));
+    let settings = dispatch(&state, "get_setting", Value::Null).await.unwrap();
+    assert_eq!(settings["cloudflare"]["domain"], json!("home.example.com"));
+  }
+
+  #[tokio::test]
+  async fn dispatch_rejects_unknown_methods_and_bad_params() {
+    let state = test_state("errors");
+    let error = dispatch(&state, "drop_tables", Value::Null).await.unwrap_err();
+    assert_eq!(error.code, METHOD_NOT_FOUND);
+
+    let error = dispatch(&state, "get_history", json!({ "limit": "ten" })).await.unwrap_err();
+    assert_eq!(error.code, INVALID_PARAMS);
+    let error = dispatch(&state, "delete_credential", json!({})).await.unwrap_err();
+    assert_eq!(error.code, INVALID_PARAMS);
+    let error = dispatch(&state, "get_setting", json!({ "key": "cloudflare.nope" })).await.unwrap_err();
+    assert_eq!(error.code, INVALID_PARAMS);
+  }
+
+  #[tokio::test]
+  async fn invalid_settings_come_back_with_their_field_errors() {
+    let state = test_state("invalid");
+    let params = json!({ "key": "cloudflare.domain", "text": "not a domain" });
+    let error = dispatch(&state, "set_setting", params).await.unwrap_err();
+    assert_eq!(error.code, INVALID_PARAMS);
+    let fields = error.data.expect("field errors");
+    assert!(fields.as_array().is_some_and(|fields| !fields.is_empty()), "{fields}");
+    assert_eq!(state.config.lock().settings.cloudflare.domain, "");
+  }
+
+  #[tokio::test]
+  async fn export_history_returns_the_document_without_a_path() {
+    let state = test_state("export");
+    let csv = dispatch(&state, "export_history", json!({ "format": "csv" })).await.unwrap();
+    assert!(csv.as_str().is_some_and(|csv| csv.starts_with("time,")), "{csv}");
+
+    let path = state.config_path.with_file_name("history.json");
+    let params = json!({ "format": "json", "path": path.to_string_lossy() });
+    assert_eq!(dispatch(&state, "export_history", params).await.unwrap(), Value::Null);
+    let written: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(written.is_object());
+  }
+
+  #[tokio::test]
+  async fn malformed_lines_get_error_responses() {
+    let state = test_state("malformed");
+    let response = answer(&state, "{not json").await;
+    assert_eq!(response["id"], Value::Null);
+    assert_eq!(response["error"]["code"], json!(PARSE_ERROR));
+
+    let response = answer(&state, r#"{"jsonrpc":"1.0","id":7,"method":"get_snapshot"}"#).await;
+    assert_eq!(response["id"], json!(7));
+    assert_eq!(response["error"]["code"], json!(INVALID_REQUEST));
+
+    let response = answer(&state, r#"{"jsonrpc":"2.0","id":"a","params":{}}"#).await;
+    assert_eq!(response["id"], json!("a"));
+    assert_eq!(response["error"]["code"], json!(INVALID_REQUEST));
+
+    let response = answer(&state, r#"[{"jsonrpc":"2.0","id":1,"method":"get_snapshot"}]"#).await;
+    assert_eq!(response["id"], Value::Null);
+    assert_eq!(response["error"]["code"], json!(INVALID_REQUEST));
+  }
+
+  #[tokio::test]
+  async fn notifications_run_without_a_response() {
+    let state = test_state("notify");
+    let line = r#"{"jsonrpc":"2.0","method":"set_setting","params":{"key":"cloudflare.domain","text":"a.example.com"}}"#;
+    assert!(handle_line(&state, line).await.is_none());
+    assert_eq!(state.config.lock().settings.cloudflare.domain, "a.example.com");
+
+    let line = r#"{"jsonrpc":"2.0","id":null,"method":"get_setting","params":{"key":"cloudflare.domain"}}"#;
+    let response = answer(&state, line).await;
+    assert_eq!(response["id"], Value::Null);
+    assert_eq!(response["result"], json!("a.example.com"));
+  }
+
+  #[test]
+  fn text_values_follow_the_type_of_the_setting() {
+    let text = |value: &str| Value::String(value.to_string());
+    let cases = [
+      (json!("example.com"), "0123", vec![text("0123")]),
+      (json!("example.com"), "true", vec![text("true")]),
+      (json!(300), "120", vec![json!(120)]),
+      (json!(300), "soon", vec![]),
+      (json!(true), "false", vec![json!(false)]),
+      (Value::Null, "120", vec![json!(120), text("120")]),
+      (Value::Null, "eth0", vec![text("eth0")]),
+    ];
+    for (current, input, expected) in cases {
+      assert_eq!(values_from_text(&current, input), expected, "{current} <- {input}");
+    }
+  }
+}
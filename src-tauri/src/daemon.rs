@@ -0,0 +1,124 @@
+use std::{
+  path::{Path, PathBuf},
+  sync::{atomic::Ordering, Arc},
+};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+
+use crate::engine::{self, AppState, EngineEvents, EngineOptions};
+use crate::models::{PrefixChangedEvent, SyncStatusKind};
+use crate::{config, rt, systemd};
+
+// Headless hosts have no webview to refresh, so the engine's events become log lines on stdout.
+#[derive(Default)]
+struct StdoutEvents {
+  // Sync time of the last status written, so repeated snapshots of the same push are logged once.
+  logged_sync_time: Mutex<Option<String>>,
+}
+
+impl EngineEvents for StdoutEvents {
+  fn snapshot_changed(&self, state: &Arc<AppState>) {
+    let (sync_time, status) = {
+      let config = state.config.lock();
+      (config.cache.last_sync_time.clone(), config.cache.last_sync_status.clone())
+    };
+    {
+      let mut logged = self.logged_sync_time.lock();
+      if sync_time.is_none() || *logged == sync_time {
+        return;
+      }
+      logged.clone_from(&sync_time);
+    }
+    let message = status.message.unwrap_or_default();
+    match status.kind {
+      SyncStatusKind::Error => eprintln!("sync failed: {message}"),
+      SyncStatusKind::Success => println!("sync succeeded: {message}"),
+      SyncStatusKind::Idle => {}
+    }
+  }
+
+  fn network_changed(&self, state: &Arc<AppState>) {
+    let addresses = state.current_ipv6_set.lock().clone();
+    if addresses.is_empty() {
+      println!("network changed: no eligible global IPv6 address");
+    } else {
+      println!("network changed: {}", addresses.join(", "));
+    }
+  }
+
+  fn prefix_changed(&self, event: &PrefixChangedEvent) {
+    println!(
+      "prefix changed: {} -> {}/{}",
+      event.previous_prefix,
+      event.prefix,
+      event.prefix_len
+    );
+  }
+
+  fn alert(&self, message: &str) {
+    eprintln!("warning: {message}");
+  }
+
+  // Autostart belongs to the desktop app; service managers start the daemon.
+  fn settings_applied(&self, _state: &Arc<AppState>) -> Result<(), String> {
+    Ok(())
+  }
+}
+
+// Runs the engine in the foreground until Ctrl+C or SIGTERM.
+pub fn serve(config_dir: &Path, resource_dir: Option<PathBuf>) -> Result<()> {
+  let resource_dir = resource_dir.or_else(|| {
+    std::env::current_exe()
+      .ok()
+      .and_then(|exe| exe.parent().map(PathBuf::from))
+  });
+  let (mut loaded_config, config_path, config_recovery) = config::load_or_default_in(config_dir)?;
+  if let Some(recovery) = config_recovery.as_ref() {
+    eprintln!(
+      "settings.json could not be loaded ({}); restored from {}",
+      recovery.reason, recovery.recovered_from
+    );
+  }
+  // There is no window to hide; keep the flag off so snapshots do not claim otherwise.
+  loaded_config.settings.lightweight_mode = false;
+  println!("using {}", config_path.display());
+
+  rt::block_on(async move {
+    let state = AppState::new(
+      EngineOptions {
+        config: loaded_config,
+        config_path,
+        config_recovery,
+        resource_dir,
+        watch_network: true,
+      },
+      Arc::new(StdoutEvents::default()),
+    );
+    engine::start(&state);
+
+    wait_for_stop_signal().await?;
+    println!("shutting down");
+    systemd::notify("STOPPING=1");
+    state.shutting_down.store(true, Ordering::SeqCst);
+    engine::flush_runtime_state(&state);
+    Ok(())
+  })
+}
+
+// Ctrl+C everywhere, plus SIGTERM on Unix since that is what service managers send.
+async fn wait_for_stop_signal() -> Result<()> {
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate()).context("failed to listen for SIGTERM")?;
+    tokio::select! {
+      result = tokio::signal::ctrl_c() => result.context("failed to listen for Ctrl+C"),
+      _ = terminate.recv() => Ok(()),
+    }
+  }
+  #[cfg(not(unix))]
+  {
+    tokio::signal::ctrl_c().await.context("failed to listen for Ctrl+C")
+  }
+}
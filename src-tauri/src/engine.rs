@@ -0,0 +1,1701 @@
+use std::{
+  net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
+  path::{Path as FsPath, PathBuf},
+  sync::{
+    atomic::{AtomicBool, AtomicU16, Ordering},
+    Arc,
+  },
+  time::{Duration as StdDuration, Instant},
+};
+
+use axum::{
+  extract::{Path, State},
+  http::{header, StatusCode},
+  response::{IntoResponse, Redirect, Response},
+  routing::get,
+  Json, Router,
+};
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{Mutex as AsyncMutex, Notify};
+use tokio::time::Duration;
+
+use crate::models::{
+  AppConfig, AppSettings, AppSnapshot, ConfigRecovery, ConfigReloadStatus, CredentialSummary, ExportHistoryRequest,
+  ExportSettingsRequest, ExternalImportReport, ExternalImportRequest, FieldError, HistoryEntry, HistoryEventKind,
+  HistoryExportFormat, HistoryStats, ImportSettingsRequest, InterfaceInfo, InterfaceSelectionStatus, LanguageMode,
+  LocalHomepageRuntime, LookupRecordIdRequest, PrefixChangedEvent, ReachabilityReport, RecordMode, RotateTokenRequest,
+  SaveCredentialRequest, SaveSettingsRequest, ServiceModel, ServiceRuntimeModel, SettingsImportPreview, SyncStatus,
+  SyncStatusKind, ThemeMode, TokenHealth, TokenRotationReport, TokenStatus,
+};
+use crate::secure_store::SecureTokenStore;
+use crate::sync_policy::{self, PushDecision, PushScheduler};
+use crate::{
+  cloudflare, config, control, external_import, geoip, history, network, platform, prefix_history, reachability, rt,
+  runtime_state, secure_store, settings_bundle, stats, systemd, token_expiry, validation,
+};
+
+pub const APP_IDENTIFIER: &str = "dev.plfjy.cloudflare-ipv6-ddns";
+const FALLBACK_POLL_INTERVAL_SECS: u64 = 300;
+const CONFIG_WATCH_INTERVAL_SECS: u64 = 2;
+const HOMEPAGE_FALLBACK_HTML: &str = r#"<!doctype html><html><head><meta charset="utf-8"/><meta name="viewport" content="width=device-width,initial-scale=1"/><title>Local Host Homepage</title></head><body style="font-family:Segoe UI,Arial,sans-serif;padding:24px"><h2>Local Host Homepage</h2><p>Homepage assets are not available yet.</p><p>Build frontend assets with <code>pnpm build</code> and restart the app.</p></body></html>"#;
+
+// How the engine reports back to whatever hosts it: the Tauri GUI emits events and updates the tray,
+// the headless daemon logs to stdout.
+pub trait EngineEvents: Send + Sync {
+  // Anything visible in the snapshot changed.
+  fn snapshot_changed(&self, state: &Arc<AppState>);
+  fn network_changed(&self, state: &Arc<AppState>);
+  fn prefix_changed(&self, event: &PrefixChangedEvent);
+  // A warning the user should see even when nobody is looking at the snapshot.
+  fn alert(&self, message: &str);
+  // Host-specific side effects of new settings, e.g. the GUI's autostart entry.
+  fn settings_applied(&self, state: &Arc<AppState>) -> Result<(), String>;
+}
+
+// Everything the host resolves before the engine starts.
+pub struct EngineOptions {
+  pub config: AppConfig,
+  pub config_path: PathBuf,
+  pub config_recovery: Option<ConfigRecovery>,
+  // Bundled resources (GeoIP databases, homepage assets, icons), when the host has such a directory.
+  pub resource_dir: Option<PathBuf>,
+  // Subscribe to OS address events; off for one-off CLI commands, which never start the worker.
+  pub watch_network: bool,
+}
+
+pub struct AppState {
+  pub config_path: PathBuf,
+  pub config_recovery: Option<ConfigRecovery>,
+  pub config_reload: Mutex<Option<ConfigReloadStatus>>,
+  pub config: Mutex<AppConfig>,
+  pub interfaces: Mutex<Vec<InterfaceInfo>>,
+  pub current_ipv6: Mutex<Option<String>>,
+  pub current_ipv6_set: Mutex<Vec<String>>,
+  pub interface_selection: Mutex<InterfaceSelectionStatus>,
+  pub reachability: Mutex<Option<ReachabilityReport>>,
+  pub geoip_lookup: RwLock<geoip::GeoIpLookup>,
+  pub geoip_download_inflight: AtomicBool,
+  pub linux_theme_hint: Option<ThemeMode>,
+  pub token_store: SecureTokenStore,
+  pub notify: Arc<Notify>,
+  pub sync_lock: AsyncMutex<()>,
+  pub push_scheduler: Mutex<PushScheduler>,
+  // Wake-up for the next settle, hold-down or heartbeat deadline; replaced by every detection cycle.
+  pub detection_recheck: Mutex<Option<rt::JoinHandle<()>>>,
+  pub runtime_state: runtime_state::RuntimeStateStore,
+  pub prefix_history: Mutex<prefix_history::PrefixHistory>,
+  pub history: Mutex<history::HistoryStore>,
+  pub platform_watcher: Option<platform::PlatformWatcher>,
+  pub shutting_down: Arc<AtomicBool>,
+  pub homepage_running: Arc<AtomicBool>,
+  pub homepage_bound_port: Arc<AtomicU16>,
+  pub homepage_dist_dir: PathBuf,
+  pub app_icon_path: Option<PathBuf>,
+  pub resource_dir: Option<PathBuf>,
+  pub has_token_cached: Arc<AtomicBool>,
+  pub token_status: Mutex<Option<TokenStatus>>,
+  // Warning text last shown as a notification, so each distinct warning is only raised once.
+  pub token_warning_notified: Mutex<Option<String>>,
+  pub bootstrapping: Arc<AtomicBool>,
+  // Set once the service manager has been told the first detection cycle finished.
+  pub service_ready: AtomicBool,
+  pub events: Arc<dyn EngineEvents>,
+}
+
+impl AppState {
+  pub fn new(options: EngineOptions, events: Arc<dyn EngineEvents>) -> Arc<Self> {
+    let EngineOptions {
+      mut config,
+      config_path,
+      config_recovery,
+      resource_dir,
+      watch_network,
+    } = options;
+
+    let runtime_state = runtime_state::RuntimeStateStore::new(&config_path);
+    match runtime_state.load() {
+      Some(cache) => config.cache = cache,
+      // Legacy caches were carried over while loading the config; this seeds a fresh or unreadable state file.
+      None => runtime_state.save_debounced(&config.cache),
+    }
+
+    let notify = Arc::new(Notify::new());
+    let platform_watcher = match watch_network.then(|| platform::start_network_watcher(notify.clone())) {
+      None => None,
+      Some(Ok(watcher)) => watcher,
+      Some(Err(error)) => {
+        // Detection keeps running on the polling fallback when the OS watcher is unavailable.
+        eprintln!("network watcher failed to start, falling back to polling: {error}");
+        None
+      }
+    };
+    let token_store = SecureTokenStore::new(APP_IDENTIFIER, &config_path);
+    token_store.set_sources(config.settings.token_sources.clone());
+    let geoip_lookup = geoip::GeoIpLookup::new(&config_path, resource_dir.clone());
+    let prefix_history = prefix_history::PrefixHistory::load(&config_path);
+    let history = history::HistoryStore::new(&config_path);
+
+    Arc::new(Self {
+      homepage_dist_dir: resolve_homepage_dist_dir(resource_dir.as_deref()),
+      app_icon_path: resolve_app_icon_path(resource_dir.as_deref()),
+      config_path,
+      config: Mutex::new(config),
+      config_recovery,
+      config_reload: Mutex::new(None),
+      interfaces: Mutex::new(Vec::new()),
+      current_ipv6: Mutex::new(None),
+      current_ipv6_set: Mutex::new(Vec::new()),
+      interface_selection: Mutex::new(InterfaceSelectionStatus::default()),
+      reachability: Mutex::new(None),
+      geoip_lookup: RwLock::new(geoip_lookup),
+      geoip_download_inflight: AtomicBool::new(false),
+      linux_theme_hint: platform::detect_theme_hint(),
+      token_store,
+      notify,
+      sync_lock: AsyncMutex::new(()),
+      push_scheduler: Mutex::new(PushScheduler::default()),
+      detection_recheck: Mutex::new(None),
+      runtime_state,
+      prefix_history: Mutex::new(prefix_history),
+      history: Mutex::new(history),
+      platform_watcher,
+      shutting_down: Arc::new(AtomicBool::new(false)),
+      homepage_running: Arc::new(AtomicBool::new(false)),
+      homepage_bound_port: Arc::new(AtomicU16::new(0)),
+      resource_dir,
+      has_token_cached: Arc::new(AtomicBool::new(false)),
+      token_status: Mutex::new(None),
+      token_warning_notified: Mutex::new(None),
+      bootstrapping: Arc::new(AtomicBool::new(true)),
+      service_ready: AtomicBool::new(false),
+      events,
+    })
+  }
+
+  // Stores a detection result and reports whether interfaces or the selected address changed.
+  pub fn apply_detection(&self, detection: network::NetworkDetection) -> bool {
+    let mut interfaces = self.interfaces.lock();
+    let mut current_ipv6 = self.current_ipv6.lock();
+    let mut current_ipv6_set = self.current_ipv6_set.lock();
+    let changed = *interfaces != detection.interfaces
+      || *current_ipv6 != detection.current_ipv6
+      || *current_ipv6_set != detection.publish_ipv6;
+    *interfaces = detection.interfaces;
+    *current_ipv6 = detection.current_ipv6;
+    *current_ipv6_set = detection.publish_ipv6;
+    *self.interface_selection.lock() = detection.selection;
+    *self.reachability.lock() = detection.reachability;
+    changed
+  }
+
+  // Token for the configured record target, honouring its named credential.
+  pub fn target_token(&self) -> anyhow::Result<Option<String>> {
+    let credential_id = self.config.lock().settings.cloudflare.credential_id.clone();
+    self.token_store.token_for(credential_id.as_deref())
+  }
+
+  // Validates against the named credentials stored on this machine.
+  pub fn validate(&self, settings: &AppSettings) -> Vec<FieldError> {
+    validation::validate_settings(settings, &self.token_store.credentials())
+  }
+
+  pub fn snapshot(&self) -> AppSnapshot {
+    let bootstrapping = self.bootstrapping.load(Ordering::SeqCst);
+    let has_token = self.has_token_cached.load(Ordering::SeqCst);
+    let config = self.config.lock().clone();
+    let configured_port = config.settings.local_homepage.web_port;
+    let web_port = if self.homepage_running.load(Ordering::SeqCst) {
+      self.homepage_bound_port.load(Ordering::SeqCst)
+    } else {
+      configured_port
+    };
+    let current_ipv6 = self.current_ipv6.lock().clone();
+    let current_ipv6_geo = current_ipv6
+      .as_deref()
+      .and_then(|ip| self.geoip_lookup.read().lookup_ip(ip));
+    let pending_push = self.push_scheduler.lock().pending_info(&config.settings.sync_policy);
+    let preferred_host = if bootstrapping {
+      let domain = config.settings.cloudflare.domain.trim();
+      if !domain.is_empty() {
+        domain.to_string()
+      } else if let Some(ipv6) = current_ipv6.clone() {
+        ipv6
+      } else {
+        "127.0.0.1".to_string()
+      }
+    } else {
+      preferred_share_host(&config, current_ipv6.clone())
+    };
+    let service_statuses = if bootstrapping {
+      build_service_runtime_models_without_probe(&config.settings.local_homepage.services, &preferred_host)
+    } else {
+      build_service_runtime_models(&config.settings.local_homepage.services, &preferred_host)
+    };
+    AppSnapshot {
+      bootstrapping,
+      settings: config.settings,
+      cache: config.cache,
+      current_ipv6,
+      current_ipv6_set: self.current_ipv6_set.lock().clone(),
+      current_ipv6_geo,
+      current_prefix: self.prefix_history.lock().current().cloned(),
+      reachability: self.reachability.lock().clone(),
+      pending_push,
+      interfaces: self.interfaces.lock().clone(),
+      interface_selection: self.interface_selection.lock().clone(),
+      has_token,
+      token_backend: self.token_store.backend(),
+      token_source: self.token_store.active_source(),
+      credentials: self.token_store.credentials(),
+      token_status: self.token_status.lock().clone(),
+      config_recovery: self.config_recovery.clone(),
+      config_reload: self.config_reload.lock().clone(),
+      linux_theme_hint: self.linux_theme_hint,
+      local_homepage: LocalHomepageRuntime {
+        running: self.homepage_running.load(Ordering::SeqCst),
+        web_port,
+        web_url: format!("http://{}:{}/index.html", preferred_host, web_port),
+        preferred_host,
+        services: service_statuses,
+      },
+    }
+  }
+}
+
+#[derive(Clone)]
+struct LocalHomepageServerState {
+  state: Arc<AppState>,
+}
+
+fn preferred_share_host(config: &AppConfig, current_ipv6: Option<String>) -> String {
+  let domain = config.settings.cloudflare.domain.trim();
+  if !domain.is_empty() {
+    return domain.to_string();
+  }
+  if let Some(ipv4) = network::detect_outbound_source_ipv4() {
+    return ipv4.to_string();
+  }
+  if let Some(ipv6) = current_ipv6 {
+    return ipv6;
+  }
+  "127.0.0.1".to_string()
+}
+
+fn can_use_scheme(preset_type: &str) -> Option<&'static str> {
+  match preset_type {
+    "SMB" => Some("smb://"),
+    "HTTP" => Some("http://"),
+    "HTTPS" => Some("https://"),
+    "FTP" => Some("ftp://"),
+    "SSH" => Some("ssh://"),
+    "RDP" => Some("rdp://"),
+    _ => None,
+  }
+}
+
+fn build_share_url(service: &ServiceModel, host: &str) -> String {
+  if let Some(scheme) = can_use_scheme(service.preset_type.as_str()) {
+    return format!("{scheme}{host}:{}", service.port);
+  }
+  format!("{host}:{}", service.port)
+}
+
+fn is_port_online(port: u16) -> bool {
+  let ipv4 = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+  if TcpStream::connect_timeout(&ipv4, StdDuration::from_millis(250)).is_ok() {
+    return true;
+  }
+  let ipv6 = SocketAddr::new(IpAddr::V6(std::net::Ipv6Addr::LOCALHOST), port);
+  TcpStream::connect_timeout(&ipv6, StdDuration::from_millis(250)).is_ok()
+}
+
+fn build_service_runtime_models(services: &[ServiceModel], preferred_host: &str) -> Vec<ServiceRuntimeModel> {
+  services
+    .iter()
+    .map(|service| ServiceRuntimeModel {
+      id: service.id.clone(),
+      name: service.name.clone(),
+      port: service.port,
+      icon: service.icon.clone(),
+      description: service.description.clone(),
+      preset_type: service.preset_type.clone(),
+      is_online: is_port_online(service.port),
+      share_url: build_share_url(service, preferred_host),
+    })
+    .collect()
+}
+
+fn build_service_runtime_models_without_probe(services: &[ServiceModel], preferred_host: &str) -> Vec<ServiceRuntimeModel> {
+  services
+    .iter()
+    .map(|service| ServiceRuntimeModel {
+      id: service.id.clone(),
+      name: service.name.clone(),
+      port: service.port,
+      icon: service.icon.clone(),
+      description: service.description.clone(),
+      preset_type: service.preset_type.clone(),
+      is_online: false,
+      share_url: build_share_url(service, preferred_host),
+    })
+    .collect()
+}
+
+fn spawn_startup_refresh_task(state: Arc<AppState>) {
+  rt::spawn(async move {
+    bootstrap(&state).await;
+    emit_snapshot(&state);
+    spawn_token_expiry_checker(state);
+  });
+}
+
+// First detection and token lookup; until this finishes the snapshot reports `bootstrapping`.
+pub async fn bootstrap(state: &Arc<AppState>) {
+  let settings = state.config.lock().settings.clone();
+  let reachability_settings = settings.reachability.clone();
+  let publish_limit = settings.cloudflare.publish_limit();
+  let interface_result = tokio::task::spawn_blocking(move || network::collect_interfaces_and_ipv6(&settings)).await;
+  if let Ok(mut detection) = interface_result {
+    reachability::verify_detection(&mut detection, &reachability_settings, publish_limit).await;
+    state.apply_detection(detection);
+  }
+  detect_token_backend(state).await;
+}
+
+// Picks the keyring or the token file and notes whether a token is available; the rest of `bootstrap` for callers
+// that detect addresses themselves.
+pub async fn detect_token_backend(state: &Arc<AppState>) {
+  let token_state = state.clone();
+  let has_token = tokio::task::spawn_blocking(move || {
+    token_state.token_store.detect_backend();
+    token_state.target_token().ok().flatten().is_some()
+  })
+  .await
+  .unwrap_or(false);
+  state.has_token_cached.store(has_token, Ordering::SeqCst);
+  state.bootstrapping.store(false, Ordering::SeqCst);
+}
+
+fn spawn_token_expiry_checker(state: Arc<AppState>) {
+  rt::spawn(async move {
+    while !state.shutting_down.load(Ordering::SeqCst) {
+      check_token_expiry(&state).await;
+      tokio::time::sleep(token_expiry::CHECK_INTERVAL).await;
+    }
+  });
+}
+
+// Verifies the record target's token with Cloudflare and raises a warning when it is close to expiry or unusable.
+async fn check_token_expiry(state: &Arc<AppState>) {
+  let token_state = state.clone();
+  let token = rt::spawn_blocking(move || token_state.target_token())
+    .await
+    .ok()
+    .and_then(|token| token.ok().flatten());
+  let status = match token {
+    Some(token) => {
+      let now = Utc::now();
+      let (warn_days, zone_id) = {
+        let config = state.config.lock();
+        (config.settings.token_expiry.warn_days, config.settings.cloudflare.zone_id.clone())
+      };
+      Some(match cloudflare::verify_token(&token, &zone_id).await {
+        Ok(verification) => token_expiry::evaluate(&verification, warn_days, now),
+        Err(error) => token_expiry::from_error(&error, now),
+      })
+    }
+    None => None,
+  };
+  *state.token_status.lock() = status.clone();
+
+  let warning = status.as_ref().and_then(token_expiry::warning);
+  let notify_enabled = state.config.lock().settings.token_expiry.notify;
+  let newly_raised = {
+    let mut notified = state.token_warning_notified.lock();
+    let changed = *notified != warning;
+    notified.clone_from(&warning);
+    changed
+  };
+  if let Some(warning) = warning.filter(|_| newly_raised && notify_enabled) {
+    state.events.alert(&warning);
+  }
+  emit_snapshot(state);
+}
+
+// Why settings were not saved: field errors the caller can attach to inputs, or a failure while applying them.
+#[derive(Debug, thiserror::Error)]
+pub enum SaveSettingsError {
+  #[error("{}", validation::describe_errors(.0))]
+  Invalid(Vec<FieldError>),
+  #[error("{0}")]
+  Failed(String),
+}
+
+impl From<String> for SaveSettingsError {
+  fn from(message: String) -> Self {
+    Self::Failed(message)
+  }
+}
+
+impl From<SaveSettingsError> for String {
+  fn from(error: SaveSettingsError) -> Self {
+    error.to_string()
+  }
+}
+
+pub fn ensure_valid_settings(state: &Arc<AppState>, settings: &AppSettings) -> Result<(), SaveSettingsError> {
+  let errors = state.validate(settings);
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(SaveSettingsError::Invalid(errors))
+  }
+}
+
+// Carries runtime-only state over from the current config into newly supplied settings.
+fn merge_settings(state: &Arc<AppState>, mut config: AppConfig) -> AppConfig {
+  let current = state.config.lock().clone();
+  // Cloudflare lookup uses domain+zone. If domain changed, old record id is stale.
+  if !current
+    .settings
+    .cloudflare
+    .domain
+    .trim()
+    .eq_ignore_ascii_case(config.settings.cloudflare.domain.trim())
+  {
+    config.settings.cloudflare.record_id.clear();
+  }
+  config.cache = current.cache;
+  config::normalize(&mut config);
+  config
+}
+
+// Saves the settings page: new settings plus an optional token change, validated before anything is written.
+pub fn save_settings(state: &Arc<AppState>, request: SaveSettingsRequest) -> Result<(), SaveSettingsError> {
+  // Validate before touching the keyring so a rejected save leaves everything as it was.
+  let config = merge_settings(
+    state,
+    AppConfig {
+      settings: request.settings,
+      ..Default::default()
+    },
+  );
+  ensure_valid_settings(state, &config.settings)?;
+
+  if request.clear_token {
+    state
+      .token_store
+      .clear_token()
+      .map_err(|error| format!("failed to clear API token: {error}"))?;
+    state.has_token_cached.store(false, Ordering::SeqCst);
+  }
+  if let Some(token) = request.api_token.as_ref() {
+    if !token.is_empty() {
+      state
+        .token_store
+        .set_token(token)
+        .map_err(|error| format!("failed to save API token securely: {error}"))?;
+      state.has_token_cached.store(true, Ordering::SeqCst);
+    }
+  }
+
+  {
+    config::save_config(&state.config_path, &config).map_err(|error| format!("failed to persist settings: {error}"))?;
+    let mut current = state.config.lock();
+    // A detection cycle may have updated the cache while the keyring was busy; keep the newer one.
+    let cache = std::mem::take(&mut current.cache);
+    *current = AppConfig { cache, ..config };
+  }
+
+  Ok(apply_settings_side_effects(state)?)
+}
+
+// Validates and persists new settings, then applies them like a save from the settings page.
+pub fn replace_settings(state: &Arc<AppState>, settings: AppSettings) -> Result<(), SaveSettingsError> {
+  let mut config = merge_settings(
+    state,
+    AppConfig {
+      settings,
+      ..Default::default()
+    },
+  );
+  ensure_valid_settings(state, &config.settings)?;
+  // Lightweight mode is runtime-only and belongs to the host.
+  config.settings.lightweight_mode = state.config.lock().settings.lightweight_mode;
+  config::save_config(&state.config_path, &config).map_err(|error| format!("failed to persist settings: {error}"))?;
+  *state.config.lock() = config;
+  Ok(apply_settings_side_effects(state)?)
+}
+
+pub fn apply_settings_side_effects(state: &Arc<AppState>) -> Result<(), String> {
+  let token_sources = state.config.lock().settings.token_sources.clone();
+  let host_result = state.events.settings_applied(state);
+  state.token_store.set_sources(token_sources);
+  refresh_token_presence(state);
+
+  state.notify.notify_one();
+  emit_snapshot(state);
+  host_result
+}
+
+pub fn refresh_token_presence(state: &Arc<AppState>) {
+  // External sources may run a credential helper, so resolve them off the async runtime.
+  let state = state.clone();
+  rt::spawn_blocking(move || {
+    let has_token = state.target_token().ok().flatten().is_some();
+    state.has_token_cached.store(has_token, Ordering::SeqCst);
+    emit_snapshot(&state);
+    // The token or the credential it comes from may have changed, so its expiry has to be checked again.
+    if !state.bootstrapping.load(Ordering::SeqCst) {
+      rt::spawn(async move {
+        check_token_expiry(&state).await;
+      });
+    }
+  });
+}
+
+// Reads a config of another DDNS tool and reports what importing it would change; `apply` also saves it.
+pub async fn import_external_config(
+  state: &Arc<AppState>,
+  request: ExternalImportRequest,
+) -> Result<ExternalImportReport, SaveSettingsError> {
+  let path = request.path.trim();
+  let text = std::fs::read_to_string(path).map_err(|error| format!("failed to read {path}: {error}"))?;
+  let current = state.config.lock().settings.clone();
+  let mut import = external_import::parse(request.kind, &text, &current)
+    .map_err(|error| format!("failed to import config: {error:#}"))?;
+
+  // Neither tool stores the zone ID, so resolve it from the zone name (or the domain) with whichever token we have.
+  import.settings.cloudflare.zone_id.clear();
+  let token = match import.api_token.clone() {
+    Some(token) => Some(token),
+    None => state.target_token().ok().flatten(),
+  };
+  let zone_names = match import.zone_name.clone() {
+    Some(zone_name) => vec![zone_name],
+    None => external_import::zone_name_candidates(&import.settings.cloudflare.domain),
+  };
+  if let Some(token) = token.as_deref() {
+    for zone_name in zone_names {
+      match cloudflare::find_zone_id(&zone_name, token).await {
+        Ok(Some(zone_id)) => {
+          import.settings.cloudflare.zone_id = zone_id;
+          import.zone_name = Some(zone_name);
+          break;
+        }
+        Ok(None) => {}
+        Err(error) => {
+          import
+            .unsupported
+            .push(format!("zone lookup for `{zone_name}` failed: {error}"));
+          break;
+        }
+      }
+    }
+  }
+  let zone_id_resolved = !import.settings.cloudflare.zone_id.is_empty();
+
+  let mut report = ExternalImportReport {
+    changes: settings_bundle::diff_settings(&current, &import.settings),
+    unsupported: import.unsupported,
+    zone_name: import.zone_name,
+    zone_id_resolved,
+    token_found: import.api_token.is_some(),
+    validation_errors: state.validate(&import.settings),
+    applied: false,
+  };
+  if !request.apply {
+    return Ok(report);
+  }
+  if !report.validation_errors.is_empty() {
+    return Err(SaveSettingsError::Invalid(report.validation_errors));
+  }
+
+  save_settings(
+    state,
+    SaveSettingsRequest {
+      settings: import.settings,
+      api_token: import.api_token,
+      clear_token: false,
+    },
+  )?;
+  report.applied = true;
+  Ok(report)
+}
+
+// Writes the current settings to a bundle; with a passphrase the API token goes along, encrypted with it.
+pub fn export_settings_bundle(state: &Arc<AppState>, request: ExportSettingsRequest) -> Result<(), String> {
+  let settings = state.config.lock().settings.clone();
+  let passphrase = request.passphrase.filter(|passphrase| !passphrase.is_empty());
+  let token = match passphrase.as_ref() {
+    Some(_) => Some(
+      state
+        .target_token()
+        .map_err(|error| format!("failed reading secure API token: {error}"))?
+        .ok_or_else(|| "API token is not set".to_string())?,
+    ),
+    None => None,
+  };
+  let token = passphrase.as_deref().zip(token.as_deref());
+  settings_bundle::export_bundle(FsPath::new(request.path.trim()), &settings, token)
+    .map_err(|error| format!("failed to export settings: {error:#}"))
+}
+
+pub fn preview_settings_import(
+  state: &Arc<AppState>,
+  request: &ImportSettingsRequest,
+) -> Result<SettingsImportPreview, String> {
+  prepare_settings_import(state, request).map(|(_, _, preview)| preview)
+}
+
+pub fn import_settings_bundle(state: &Arc<AppState>, request: &ImportSettingsRequest) -> Result<(), SaveSettingsError> {
+  let (settings, token, preview) = prepare_settings_import(state, request)?;
+  if !preview.validation_errors.is_empty() {
+    return Err(SaveSettingsError::Invalid(preview.validation_errors));
+  }
+  save_settings(
+    state,
+    SaveSettingsRequest {
+      settings,
+      api_token: token,
+      clear_token: false,
+    },
+  )
+}
+
+// Reads and validates a bundle, returning the settings to apply, the decrypted token and a preview of the changes.
+fn prepare_settings_import(
+  state: &Arc<AppState>,
+  request: &ImportSettingsRequest,
+) -> Result<(AppSettings, Option<String>, SettingsImportPreview), String> {
+  let bundle = settings_bundle::read_bundle(FsPath::new(request.path.trim()))
+    .map_err(|error| format!("failed to read settings bundle: {error:#}"))?;
+  let current = state.config.lock().settings.clone();
+  let mut settings = bundle.settings;
+  // Window visibility is per-session, not part of a portable setup.
+  settings.lightweight_mode = current.lightweight_mode;
+
+  let mut warnings = Vec::new();
+  let passphrase = request.passphrase.as_deref().filter(|passphrase| !passphrase.is_empty());
+  let token = match (bundle.sealed_token.as_ref(), passphrase) {
+    (Some(sealed), Some(passphrase)) => Some(
+      settings_bundle::decrypt_token(sealed, passphrase).map_err(|error| format!("failed to decrypt API token: {error:#}"))?,
+    ),
+    (Some(_), None) => {
+      warnings.push("bundle contains an encrypted API token; enter its passphrase to import it".to_string());
+      None
+    }
+    (None, _) => None,
+  };
+  let credentials = state.token_store.credentials();
+  if let Some(credential_id) = settings.cloudflare.credential_id.take() {
+    if credentials.iter().any(|credential| credential.id == credential_id) {
+      settings.cloudflare.credential_id = Some(credential_id);
+    } else {
+      warnings.push(format!(
+        "credential `{credential_id}` does not exist on this machine; the default API token will be used"
+      ));
+    }
+  }
+  if settings.selected_interface.as_deref().is_some_and(|name| !name.is_empty()) || !settings.interface_candidates.is_empty() {
+    warnings.push("interface selection was made on another machine and may not match local interfaces".to_string());
+  }
+
+  let preview = SettingsImportPreview {
+    exported_time: bundle.exported_time,
+    app_version: bundle.app_version,
+    changes: settings_bundle::diff_settings(&current, &settings),
+    includes_token: bundle.sealed_token.is_some(),
+    token_will_import: token.is_some(),
+    warnings,
+    validation_errors: state.validate(&settings),
+  };
+  Ok((settings, token, preview))
+}
+
+// Adds or replaces a named credential; a new credential gets an ID derived from its name.
+pub fn save_credential(state: &Arc<AppState>, request: SaveCredentialRequest) -> Result<(), String> {
+  let name = request.name.trim();
+  if name.is_empty() {
+    return Err("credential name is required".to_string());
+  }
+  if request.token.trim().is_empty() {
+    return Err("credential token is required".to_string());
+  }
+  let store = &state.token_store;
+  let id = match request.id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+    Some(id) => id.to_string(),
+    None => unique_credential_id(&store.credentials(), name),
+  };
+  if !secure_store::is_valid_credential_id(&id) {
+    return Err(format!("invalid credential id `{id}`"));
+  }
+  store
+    .set_named_credential(&id, name, request.token.trim())
+    .map_err(|error| format!("failed to save credential securely: {error:#}"))?;
+
+  refresh_token_presence(state);
+  Ok(())
+}
+
+pub fn delete_credential(state: &Arc<AppState>, id: &str) -> Result<(), String> {
+  let in_use = state.config.lock().settings.cloudflare.credential_id.as_deref() == Some(id);
+  if in_use {
+    return Err(format!("credential `{id}` is used by the record target; pick another credential first"));
+  }
+  state
+    .token_store
+    .delete_named_credential(id)
+    .map_err(|error| format!("failed to delete credential: {error:#}"))?;
+
+  emit_snapshot(state);
+  Ok(())
+}
+
+// Appends `-2`, `-3`, ... so a second credential with the same name does not overwrite the first.
+fn unique_credential_id(existing: &[CredentialSummary], name: &str) -> String {
+  let base = match secure_store::credential_id_from_name(name) {
+    id if id.is_empty() => "credential".to_string(),
+    id => id,
+  };
+  let taken = |id: &str| existing.iter().any(|credential| credential.id == id);
+  if !taken(&base) {
+    return base;
+  }
+  let mut suffix = 2;
+  loop {
+    let id = format!("{base}-{suffix}");
+    if !taken(&id) {
+      return id;
+    }
+    suffix += 1;
+  }
+}
+
+// Checks a replacement token against Cloudflare and, when asked to apply, stores it for the record target.
+pub async fn rotate_token(state: &Arc<AppState>, request: RotateTokenRequest) -> Result<TokenRotationReport, String> {
+  let token = request.token.trim().to_string();
+  if token.is_empty() {
+    return Err("new API token is required".to_string());
+  }
+  let (credential_id, zone_id, domain, warn_days) = {
+    let config = state.config.lock();
+    let cloudflare = &config.settings.cloudflare;
+    (
+      cloudflare.credential_id.clone(),
+      cloudflare.zone_id.trim().to_string(),
+      cloudflare.domain.trim().to_string(),
+      config.settings.token_expiry.warn_days,
+    )
+  };
+  if credential_id.is_none() {
+    // A token from env/file/command shadows the stored one, so replacing the stored token would change nothing.
+    let source = state.token_store.active_source();
+    if let Some(source) = source.filter(|source| !matches!(source.as_str(), "keyring" | "encryptedFile")) {
+      return Err(format!("the API token is supplied by {source}; rotate it there"));
+    }
+  }
+
+  let verification = cloudflare::verify_token(&token, &zone_id)
+    .await
+    .map_err(|error| format!("new API token failed verification: {error:#}"))?;
+  let status = token_expiry::evaluate(&verification, warn_days, Utc::now());
+  if matches!(status.health, TokenHealth::Expired | TokenHealth::Disabled) {
+    return Err(format!(
+      "new API token is not usable: {}",
+      status.message.clone().unwrap_or_else(|| verification.status.clone())
+    ));
+  }
+  let zone_access_verified = if zone_id.is_empty() || domain.is_empty() {
+    false
+  } else {
+    cloudflare::list_aaaa_records(&zone_id, &domain, &token)
+      .await
+      .map_err(|error| format!("new API token cannot read DNS records of {domain}: {error:#}"))?;
+    true
+  };
+
+  let mut report = TokenRotationReport {
+    status,
+    zone_access_verified,
+    applied: false,
+  };
+  if !request.apply {
+    return Ok(report);
+  }
+
+  let store_state = state.clone();
+  let stored_token = token.clone();
+  rt::spawn_blocking(move || {
+    let store = &store_state.token_store;
+    match credential_id.as_deref() {
+      Some(id) => {
+        let name = store
+          .credentials()
+          .into_iter()
+          .find(|credential| credential.id == id)
+          .map(|credential| credential.name)
+          .ok_or_else(|| anyhow::anyhow!("credential `{id}` does not exist"))?;
+        store.set_named_credential(id, &name, &stored_token)
+      }
+      None => store.set_token(&stored_token),
+    }
+  })
+  .await
+  .map_err(|error| format!("failed to save API token securely: {error}"))?
+  .map_err(|error| format!("failed to save API token securely: {error:#}"))?;
+
+  state.has_token_cached.store(true, Ordering::SeqCst);
+  *state.token_status.lock() = Some(report.status.clone());
+  *state.token_warning_notified.lock() = token_expiry::warning(&report.status);
+  emit_snapshot(state);
+  state.notify.notify_one();
+  report.applied = true;
+  Ok(report)
+}
+
+fn spawn_config_file_watcher(state: Arc<AppState>) {
+  rt::spawn(async move {
+    // Polling the file stamp works the same on every platform and for editors that replace the file.
+    let mut last_stamp = config::file_stamp(&state.config_path);
+    loop {
+      tokio::time::sleep(Duration::from_secs(CONFIG_WATCH_INTERVAL_SECS)).await;
+      if state.shutting_down.load(Ordering::SeqCst) {
+        break;
+      }
+      let stamp = config::file_stamp(&state.config_path);
+      if stamp.is_none() || stamp == last_stamp {
+        continue;
+      }
+      last_stamp = stamp;
+      reload_config_from_disk(&state);
+    }
+  });
+}
+
+fn reload_config_from_disk(state: &Arc<AppState>) {
+  let edited = match config::read_config(&state.config_path) {
+    Ok(edited) => edited,
+    Err(error) => {
+      // Keep running on the last good settings; the next valid save overwrites the broken file.
+      set_config_reload_status(state, false, format!("ignored invalid edit to settings.json: {error:#}"));
+      return;
+    }
+  };
+
+  let mut config = merge_settings(state, edited);
+  let errors = state.validate(&config.settings);
+  if !errors.is_empty() {
+    set_config_reload_status(
+      state,
+      false,
+      format!("ignored edit to settings.json, {}", validation::describe_errors(&errors)),
+    );
+    return;
+  }
+  {
+    let current = state.config.lock();
+    // Lightweight mode is runtime-only and never read back from disk.
+    config.settings.lightweight_mode = current.settings.lightweight_mode;
+    // Our own writes land here too; they match the in-memory settings and need no reload.
+    if serde_json::to_value(&config.settings).ok() == serde_json::to_value(&current.settings).ok() {
+      return;
+    }
+  }
+  *state.config.lock() = config;
+
+  let message = match apply_settings_side_effects(state) {
+    Ok(()) => "applied external edit to settings.json".to_string(),
+    Err(error) => format!("applied external edit to settings.json, but {error}"),
+  };
+  set_config_reload_status(state, true, message);
+}
+
+fn set_config_reload_status(state: &Arc<AppState>, applied: bool, message: String) {
+  if !applied {
+    eprintln!("{message}");
+  }
+  *state.config_reload.lock() = Some(ConfigReloadStatus {
+    applied,
+    message,
+    time: Utc::now().to_rfc3339(),
+  });
+  emit_snapshot(state);
+}
+
+// Finds the AAAA record ID for `domain` and stores it, together with the zone and domain, as the record target.
+pub async fn lookup_record_id(state: &Arc<AppState>, request: LookupRecordIdRequest) -> Result<String, String> {
+  let token = state
+    .target_token()
+    .map_err(|error| format!("failed reading secure API token: {error}"))?
+    .ok_or_else(|| "API token is not set".to_string())?;
+
+  let (zone_id, domain) = {
+    let config = state.config.lock();
+    let cloudflare = &config.settings.cloudflare;
+    (
+      request.zone_id.unwrap_or_else(|| cloudflare.zone_id.clone()).trim().to_string(),
+      request.domain.unwrap_or_else(|| cloudflare.domain.clone()).trim().to_string(),
+    )
+  };
+
+  if zone_id.is_empty() || domain.is_empty() {
+    return Err("Zone ID and domain are required to lookup AAAA record".to_string());
+  }
+
+  let record_id = cloudflare::find_aaaa_record_id(&zone_id, &domain, &token)
+    .await
+    .map_err(|error| error.to_string())?;
+
+  {
+    let mut config = state.config.lock();
+    config.settings.cloudflare.zone_id = zone_id;
+    config.settings.cloudflare.domain = domain;
+    config.settings.cloudflare.record_id = record_id.clone();
+    config::save_config(&state.config_path, &config)
+      .map_err(|error| format!("failed to persist looked-up record id: {error}"))?;
+  }
+
+  emit_snapshot(state);
+  Ok(record_id)
+}
+
+pub async fn run_manual_push(state: &Arc<AppState>) -> Result<(), String> {
+  let settings = state.config.lock().settings.clone();
+  let mut detection = network::collect_interfaces_and_ipv6(&settings);
+  reachability::verify_detection(&mut detection, &settings.reachability, settings.cloudflare.publish_limit()).await;
+  let addresses = detection.publish_ipv6.clone();
+  state.apply_detection(detection);
+
+  if addresses.is_empty() {
+    let message = "no eligible global IPv6 address is currently available";
+    update_sync_status(state, SyncStatusKind::Error, Some(message.to_string()));
+    return Err(message.to_string());
+  }
+
+  push_addresses_to_cloudflare(state, addresses.clone()).await?;
+  // Cloudflare now holds this set, so it becomes the committed one. Otherwise the worker would settle on it and push
+  // it again, and a standalone CLI push would leave the cache behind the record.
+  let mut config = state.config.lock();
+  if !sync_policy::same_addresses(&config.cache.committed_addresses(), &addresses) {
+    commit_addresses(state, &mut config, &addresses, Utc::now());
+  }
+  Ok(())
+}
+
+// Makes `addresses` the committed set and records the change in the history log.
+fn commit_addresses(state: &Arc<AppState>, config: &mut AppConfig, addresses: &[String], now: DateTime<Utc>) {
+  let previous_addresses = config.cache.committed_addresses();
+  config.cache.commit_addresses(addresses, now.to_rfc3339());
+  record_history(
+    state,
+    HistoryEntry {
+      time: now.to_rfc3339(),
+      kind: HistoryEventKind::AddressChanged,
+      addresses: addresses.to_vec(),
+      previous_addresses,
+      domain: None,
+      latency_ms: None,
+      error_code: None,
+      message: None,
+    },
+  );
+  state.runtime_state.save_debounced(&config.cache);
+}
+
+fn spawn_ip_change_worker(state: Arc<AppState>) {
+  let state_for_tick = state.clone();
+  let state_for_poll = state.clone();
+  platform::spawn_background_worker(
+    state.notify.clone(),
+    state.shutting_down.clone(),
+    move || {
+      let configured = state_for_poll.config.lock().settings.sync_policy.poll_interval();
+      if state_for_poll.platform_watcher.is_none() {
+        // Without an OS watcher polling is the only detection trigger, so it cannot be disabled.
+        return configured.or(Some(StdDuration::from_secs(FALLBACK_POLL_INTERVAL_SECS)));
+      }
+      configured
+    },
+    // Pinging at half the timeout, as sd_watchdog_enabled(3) recommends, from the loop itself means a stuck
+    // cycle stops the pings and systemd restarts the service.
+    systemd::watchdog_timeout().map(|timeout| (timeout / 2, || systemd::notify("WATCHDOG=1"))),
+    move || {
+      let state = state_for_tick.clone();
+      async move {
+        let _ = run_detection_cycle(&state).await;
+        report_service_status(&state);
+      }
+    },
+  );
+}
+
+// Readiness means the first detection cycle has finished; later cycles only refresh the status line.
+fn report_service_status(state: &Arc<AppState>) {
+  let addresses = state.current_ipv6_set.lock().clone();
+  let status = if addresses.is_empty() {
+    "STATUS=No eligible global IPv6 address".to_string()
+  } else {
+    format!("STATUS=Publishing {}", addresses.join(", "))
+  };
+  if state.service_ready.swap(true, Ordering::SeqCst) {
+    systemd::notify(&status);
+  } else {
+    systemd::notify(&format!("READY=1\n{status}"));
+  }
+}
+
+async fn run_detection_cycle(state: &Arc<AppState>) -> Result<(), String> {
+  let settings = state.config.lock().settings.clone();
+  let auto_push_enabled = settings.auto_push;
+  let sync_policy = settings.sync_policy.clone();
+
+  let mut detection = network::collect_interfaces_and_ipv6(&settings);
+  reachability::verify_detection(&mut detection, &settings.reachability, settings.cloudflare.publish_limit()).await;
+  let current_addresses = detection.publish_ipv6.clone();
+  let current_prefix = current_addresses.first().and_then(|address| {
+    let prefix_len = prefix_history::delegated_prefix_len(*detection.prefix_lengths.get(address)?);
+    let addr = address.parse().ok()?;
+    Some((prefix_history::prefix_of(addr, prefix_len), prefix_len))
+  });
+  let network_changed = state.apply_detection(detection);
+  spawn_geoip_download_if_needed(state);
+
+  let now = Utc::now();
+  let (push_target, recheck_after) = {
+    let mut config = state.config.lock();
+    let mut scheduler = state.push_scheduler.lock();
+    // Requirement-driven behavior: we only compare local interface detection against local cache.
+    // We intentionally do not poll Cloudflare for current AAAA content in the scheduler.
+    let observation = scheduler.observe(&config.cache.committed_addresses(), &current_addresses, &sync_policy, now);
+    if observation.commit {
+      commit_addresses(state, &mut config, &current_addresses, now);
+      scheduler.queue_push(current_addresses.clone(), now);
+    }
+    let mut recheck_after = observation.recheck_after;
+    if auto_push_enabled {
+      let heartbeat_in = scheduler.queue_heartbeat_if_due(
+        &config.cache.committed_addresses(),
+        config.cache.last_success(),
+        &sync_policy,
+        now,
+      );
+      recheck_after = earliest(recheck_after, heartbeat_in);
+    } else {
+      scheduler.queue_push(Vec::new(), now);
+    }
+    match scheduler.poll_push(&sync_policy, now) {
+      PushDecision::Ready(addresses) => (Some(addresses), recheck_after),
+      PushDecision::Wait(delay) => (None, earliest(recheck_after, Some(delay))),
+      PushDecision::Idle => (None, recheck_after),
+    }
+  };
+
+  // Prefixes follow the committed (settled) address, so SLAAC flaps do not count as ISP rotations.
+  let prefix_change = {
+    let committed_primary = state.config.lock().cache.last_known_ipv6.clone();
+    match current_prefix {
+      Some((prefix, prefix_len)) if committed_primary.as_ref() == current_addresses.first() => state
+        .prefix_history
+        .lock()
+        .observe(&prefix, prefix_len, now)
+        .unwrap_or_else(|error| {
+          eprintln!("failed to record prefix history: {error}");
+          None
+        }),
+      _ => None,
+    }
+  };
+
+  if let Some(addresses) = push_target {
+    let _ = push_addresses_to_cloudflare(state, addresses).await;
+  }
+  schedule_detection_recheck(state, recheck_after);
+
+  emit_snapshot(state);
+  if network_changed {
+    state.events.network_changed(state);
+  }
+  if let Some(event) = prefix_change {
+    state.events.prefix_changed(&event);
+  }
+  Ok(())
+}
+
+fn earliest(a: Option<chrono::Duration>, b: Option<chrono::Duration>) -> Option<chrono::Duration> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a.min(b)),
+    (a, b) => a.or(b),
+  }
+}
+
+fn schedule_detection_recheck(state: &Arc<AppState>, delay: Option<chrono::Duration>) {
+  // Settle and hold-down windows expire without any OS network event, so wake the worker ourselves.
+  let task = delay.map(|delay| {
+    let delay = delay.to_std().unwrap_or_default() + Duration::from_millis(250);
+    let notify = state.notify.clone();
+    let shutting_down = state.shutting_down.clone();
+    rt::spawn(async move {
+      tokio::time::sleep(delay).await;
+      if !shutting_down.load(Ordering::SeqCst) {
+        notify.notify_one();
+      }
+    })
+  });
+  // Each cycle recomputes the deadline from current state, so it supersedes the previous one instead of
+  // stacking another sleeping task per poll.
+  if let Some(previous) = std::mem::replace(&mut *state.detection_recheck.lock(), task) {
+    previous.abort();
+  }
+}
+
+async fn push_addresses_to_cloudflare(state: &Arc<AppState>, addresses: Vec<String>) -> Result<(), String> {
+  let (record_mode, domain) = {
+    let config = state.config.lock();
+    (config.settings.cloudflare.record_mode, config.settings.cloudflare.domain.trim().to_string())
+  };
+  let started = Instant::now();
+  let result = match record_mode {
+    RecordMode::Single => match addresses.first() {
+      Some(ipv6) => push_ipv6_to_cloudflare(state, ipv6.clone()).await,
+      None => return Ok(()),
+    },
+    RecordMode::MultiValue => push_rrset_to_cloudflare(state, addresses.clone()).await,
+  };
+  let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+  let (status_kind, kind, error_code, message) = match &result {
+    Ok(message) => (SyncStatusKind::Success, HistoryEventKind::PushSucceeded, None, message.clone()),
+    Err(failure) => (
+      SyncStatusKind::Error,
+      HistoryEventKind::PushFailed,
+      Some(failure.code.clone()),
+      failure.message.clone(),
+    ),
+  };
+  update_sync_status(state, status_kind, Some(message.clone()));
+  record_history(
+    state,
+    HistoryEntry {
+      time: Utc::now().to_rfc3339(),
+      kind,
+      addresses,
+      previous_addresses: Vec::new(),
+      domain: (!domain.is_empty()).then_some(domain),
+      latency_ms: Some(latency_ms),
+      error_code,
+      message: Some(message),
+    },
+  );
+  result.map(|_| ()).map_err(|failure| failure.message)
+}
+
+// A failed push with the category recorded in the history log.
+struct PushFailure {
+  code: String,
+  message: String,
+}
+
+impl PushFailure {
+  fn new(code: &str, message: impl Into<String>) -> Self {
+    Self {
+      code: code.to_string(),
+      message: message.into(),
+    }
+  }
+
+  fn from_error(state: &Arc<AppState>, error: &anyhow::Error) -> Self {
+    Self {
+      code: cloudflare::error_code(error),
+      message: push_error_message(state, error),
+    }
+  }
+}
+
+fn record_history(state: &Arc<AppState>, entry: HistoryEntry) {
+  if let Err(error) = state.history.lock().append(&entry) {
+    eprintln!("failed to record history: {error:#}");
+  }
+}
+
+struct PushTarget {
+  zone_id: String,
+  domain: String,
+  record_id: String,
+  ttl: Option<u32>,
+  token: String,
+}
+
+fn read_push_target(state: &Arc<AppState>) -> Result<PushTarget, PushFailure> {
+  let (zone_id, domain, record_id, ttl) = {
+    let config = state.config.lock();
+    (
+      config.settings.cloudflare.zone_id.trim().to_string(),
+      config.settings.cloudflare.domain.trim().to_string(),
+      config.settings.cloudflare.record_id.trim().to_string(),
+      config.settings.cloudflare.ttl,
+    )
+  };
+
+  if zone_id.is_empty() || domain.is_empty() {
+    return Err(PushFailure::new(
+      "notConfigured",
+      "Cloudflare zone id and domain must be set before pushing updates",
+    ));
+  }
+
+  let token = state
+    .target_token()
+    .map_err(|error| PushFailure::new("noToken", format!("failed reading secure API token: {error}")))?
+    .ok_or_else(|| PushFailure::new("noToken", "API token is not set"))?;
+
+  Ok(PushTarget {
+    zone_id,
+    domain,
+    record_id,
+    ttl,
+    token,
+  })
+}
+
+// Returns the success message for the sync status.
+async fn push_ipv6_to_cloudflare(state: &Arc<AppState>, ipv6: String) -> Result<String, PushFailure> {
+  let _guard = state.sync_lock.lock().await;
+
+  let PushTarget {
+    zone_id,
+    domain,
+    mut record_id,
+    ttl,
+    token,
+  } = read_push_target(state)?;
+
+  if record_id.is_empty() {
+    record_id = cloudflare::find_aaaa_record_id(&zone_id, &domain, &token)
+      .await
+      .map_err(|error| PushFailure::new(&cloudflare::error_code(&error), error.to_string()))?;
+    {
+      let mut config = state.config.lock();
+      config.settings.cloudflare.record_id = record_id.clone();
+      if let Err(error) = config::save_config(&state.config_path, &config) {
+        eprintln!("failed to persist looked-up record id: {error:#}");
+      }
+    }
+  }
+
+  state
+    .push_scheduler
+    .lock()
+    .record_push(std::slice::from_ref(&ipv6), Utc::now());
+  let mut result = cloudflare::update_aaaa_record(&zone_id, &record_id, &token, &ipv6, ttl).await;
+  if result.as_ref().is_err_and(cloudflare::is_record_missing) {
+    // The record was deleted outside the app; recreate it (or adopt a replacement) under the same name.
+    result = restore_aaaa_record(state, &zone_id, &domain, &token, &ipv6, ttl).await;
+  }
+  match result {
+    Ok(()) => Ok(format!("Updated Cloudflare AAAA record to {}", ipv6)),
+    Err(error) => Err(PushFailure::from_error(state, &error)),
+  }
+}
+
+async fn push_rrset_to_cloudflare(state: &Arc<AppState>, addresses: Vec<String>) -> Result<String, PushFailure> {
+  let _guard = state.sync_lock.lock().await;
+
+  let target = read_push_target(state)?;
+  state.push_scheduler.lock().record_push(&addresses, Utc::now());
+  // The RRset must match the local set exactly, so extra records under the name are removed.
+  let result =
+    cloudflare::sync_aaaa_rrset(&target.zone_id, &target.domain, &target.token, &addresses, target.ttl).await;
+  match result {
+    Ok(summary) => Ok(format!(
+      "Synced Cloudflare AAAA records to {} (created {}, updated {}, deleted {})",
+      addresses.join(", "),
+      summary.created,
+      summary.updated,
+      summary.deleted
+    )),
+    Err(error) => Err(PushFailure::from_error(state, &error)),
+  }
+}
+
+// Auth failures usually mean the token expired; say so instead of only echoing Cloudflare's error.
+fn push_error_message(state: &Arc<AppState>, error: &anyhow::Error) -> String {
+  if !cloudflare::is_auth_failure(error) {
+    return error.to_string();
+  }
+  let known_bad = state
+    .token_status
+    .lock()
+    .as_ref()
+    .filter(|status| status.health != TokenHealth::ExpiringSoon)
+    .and_then(token_expiry::warning);
+  let state = state.clone();
+  rt::spawn(async move {
+    check_token_expiry(&state).await;
+  });
+  match known_bad {
+    Some(warning) => format!("{warning} ({error})"),
+    None => error.to_string(),
+  }
+}
+
+async fn restore_aaaa_record(
+  state: &Arc<AppState>,
+  zone_id: &str,
+  domain: &str,
+  token: &str,
+  ipv6: &str,
+  ttl: Option<u32>,
+) -> anyhow::Result<()> {
+  let record_id = match cloudflare::lookup_aaaa_record(zone_id, domain, token).await? {
+    Some(record) => {
+      cloudflare::update_aaaa_record(zone_id, &record.id, token, ipv6, ttl).await?;
+      record.id
+    }
+    None => cloudflare::create_aaaa_record(zone_id, domain, token, ipv6, ttl).await?,
+  };
+  let mut config = state.config.lock();
+  config.settings.cloudflare.record_id = record_id;
+  // Without the new id on disk, the next start would PATCH the deleted record and recreate it again.
+  if let Err(error) = config::save_config(&state.config_path, &config) {
+    eprintln!("failed to persist recreated record id: {error:#}");
+  }
+  Ok(())
+}
+
+fn update_sync_status(state: &Arc<AppState>, kind: SyncStatusKind, message: Option<String>) {
+  {
+    let mut config = state.config.lock();
+    let now = Utc::now().to_rfc3339();
+    if matches!(kind, SyncStatusKind::Success) {
+      config.cache.last_success_time = Some(now.clone());
+    }
+    config.cache.last_sync_time = Some(now);
+    config.cache.last_sync_status = SyncStatus { kind, message };
+    state.runtime_state.save_debounced(&config.cache);
+  }
+  emit_snapshot(state);
+}
+
+pub fn history_stats(state: &Arc<AppState>) -> HistoryStats {
+  let prefixes = state.prefix_history.lock().records().to_vec();
+  let entries = state.history.lock().entries();
+  stats::compute(&prefixes, &entries, Utc::now())
+}
+
+// Renders the retained history as a CSV of entries, or as one JSON document that also carries the statistics.
+// The document is written to the requested path, or returned when there is none.
+pub fn export_history(state: &Arc<AppState>, request: ExportHistoryRequest) -> Result<Option<String>, String> {
+  let prefixes = state.prefix_history.lock().records().to_vec();
+  let entries = state.history.lock().entries();
+  let contents = match request.format {
+    HistoryExportFormat::Csv => stats::export_csv(&entries),
+    HistoryExportFormat::Json => {
+      let summary = stats::compute(&prefixes, &entries, Utc::now());
+      stats::export_json(&summary, &prefixes, &entries).map_err(|error| format!("failed to export history: {error}"))?
+    }
+  };
+  let Some(path) = request.path.as_deref().map(str::trim).filter(|path| !path.is_empty()) else {
+    return Ok(Some(contents));
+  };
+  config::write_file_atomic(FsPath::new(path), contents.as_bytes())
+    .map_err(|error| format!("failed to write history export to {path}: {error}"))?;
+  Ok(None)
+}
+
+pub fn flush_runtime_state(state: &Arc<AppState>) {
+  if let Err(error) = state.runtime_state.flush() {
+    eprintln!("{error:#}");
+  }
+}
+
+pub fn emit_snapshot(state: &Arc<AppState>) {
+  state.events.snapshot_changed(state);
+}
+
+fn spawn_geoip_download_if_needed(state: &Arc<AppState>) {
+  let missing_db = state.geoip_lookup.read().missing_any_database();
+  if !missing_db {
+    return;
+  }
+  if state.geoip_download_inflight.swap(true, Ordering::SeqCst) {
+    return;
+  }
+
+  let state = state.clone();
+  let config_path = state.config_path.clone();
+  rt::spawn(async move {
+    let result = geoip::ensure_geoip_databases(&config_path).await;
+    if !result.errors.is_empty() {
+      eprintln!("geoip background download finished with errors: {}", result.errors.join(" | "));
+    }
+
+    // Reload from disk so new databases can be used immediately.
+    let reloaded = geoip::GeoIpLookup::new(&config_path, state.resource_dir.clone());
+    {
+      let mut guard = state.geoip_lookup.write();
+      *guard = reloaded;
+    }
+
+    if result.changed {
+      emit_snapshot(&state);
+    }
+    state.geoip_download_inflight.store(false, Ordering::SeqCst);
+  });
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HomepageApiSnapshot {
+  push_domain: Option<String>,
+  language_mode: LanguageMode,
+  preferred_host: String,
+  web_port: u16,
+  web_url: String,
+  services: Vec<ServiceRuntimeModel>,
+}
+
+// Axum docs ("Routing" and "Handlers"): map root path to an HTTP redirect target.
+async fn homepage_redirect() -> Redirect {
+  Redirect::temporary("/index.html")
+}
+
+// Axum docs ("extract::State"): shared server state is injected into handlers.
+async fn homepage_api_snapshot(State(server): State<LocalHomepageServerState>) -> Json<HomepageApiSnapshot> {
+  let config = server.state.config.lock().clone();
+  let preferred_host = preferred_share_host(&config, server.state.current_ipv6.lock().clone());
+  let services = build_service_runtime_models(&config.settings.local_homepage.services, &preferred_host);
+  let web_port = if server.state.homepage_running.load(Ordering::SeqCst) {
+    server.state.homepage_bound_port.load(Ordering::SeqCst)
+  } else {
+    config.settings.local_homepage.web_port
+  };
+  let push_domain = {
+    let value = config.settings.cloudflare.domain.trim();
+    if value.is_empty() {
+      None
+    } else {
+      Some(value.to_string())
+    }
+  };
+
+  Json(HomepageApiSnapshot {
+    push_domain,
+    language_mode: config.settings.language_mode,
+    preferred_host: preferred_host.clone(),
+    web_port,
+    web_url: format!("http://{}:{}/index.html", preferred_host, web_port),
+    services,
+  })
+}
+
+async fn homepage_html(State(server): State<LocalHomepageServerState>) -> Response {
+  serve_dist_file(&server.state.homepage_dist_dir, "homepage.html").await
+}
+
+// Axum docs ("Path extraction"): wildcard paths are supported via `{*path}` in route patterns.
+async fn homepage_assets(Path(path): Path<String>, State(server): State<LocalHomepageServerState>) -> Response {
+  let relative = format!("assets/{}", path.trim_start_matches('/'));
+  serve_dist_file(&server.state.homepage_dist_dir, &relative).await
+}
+
+async fn homepage_favicon(State(server): State<LocalHomepageServerState>) -> Response {
+  // Prefer serving the actual app icon so browser tab icon stays aligned with desktop icon.
+  if let Some(path) = server.state.app_icon_path.as_ref() {
+    return serve_any_file(path).await;
+  }
+  serve_dist_file(&server.state.homepage_dist_dir, "favicon.ico").await
+}
+
+async fn serve_dist_file(dist_dir: &PathBuf, relative: &str) -> Response {
+  let safe_relative = relative.trim_start_matches('/').replace('\\', "/");
+  let target = dist_dir.join(&safe_relative);
+  let canonical = match tokio::fs::canonicalize(&target).await {
+    Ok(path) => path,
+    Err(_) => {
+      if safe_relative == "homepage.html" {
+        return (
+          StatusCode::OK,
+          [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+          HOMEPAGE_FALLBACK_HTML,
+        )
+          .into_response();
+      }
+      return (StatusCode::NOT_FOUND, "Not found").into_response();
+    }
+  };
+  let canonical_dist = match tokio::fs::canonicalize(dist_dir).await {
+    Ok(path) => path,
+    Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Homepage dist path unavailable").into_response(),
+  };
+  if !canonical.starts_with(&canonical_dist) {
+    return (StatusCode::FORBIDDEN, "Forbidden").into_response();
+  }
+  let bytes = match tokio::fs::read(&canonical).await {
+    Ok(data) => data,
+    Err(_) => return (StatusCode::NOT_FOUND, "Not found").into_response(),
+  };
+  let mime = mime_guess::from_path(&canonical).first_or_octet_stream();
+  (
+    [(header::CONTENT_TYPE, mime.as_ref())],
+    bytes,
+  )
+    .into_response()
+}
+
+fn resolve_homepage_dist_dir(resource_dir: Option<&FsPath>) -> PathBuf {
+  if let Ok(override_path) = std::env::var("CF_DDNS_HOMEPAGE_DIST") {
+    let path = PathBuf::from(override_path);
+    if path.exists() {
+      return path;
+    }
+  }
+
+  let workspace_dist = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    .join("..")
+    .join("dist");
+  if workspace_dist.exists() {
+    return workspace_dist;
+  }
+
+  if let Some(resource_dir) = resource_dir {
+    let bundled_dist = resource_dir.join("dist");
+    if bundled_dist.exists() {
+      return bundled_dist;
+    }
+  }
+
+  workspace_dist
+}
+
+fn resolve_app_icon_path(resource_dir: Option<&FsPath>) -> Option<PathBuf> {
+  let dev_icon_ico = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("icons").join("icon.ico");
+  if dev_icon_ico.exists() {
+    return Some(dev_icon_ico);
+  }
+  let dev_icon_png = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("icons").join("icon.png");
+  if dev_icon_png.exists() {
+    return Some(dev_icon_png);
+  }
+
+  if let Some(resource_dir) = resource_dir {
+    let bundle_ico = resource_dir.join("icons").join("icon.ico");
+    if bundle_ico.exists() {
+      return Some(bundle_ico);
+    }
+    let bundle_png = resource_dir.join("icons").join("icon.png");
+    if bundle_png.exists() {
+      return Some(bundle_png);
+    }
+  }
+  None
+}
+
+async fn serve_any_file(path: &PathBuf) -> Response {
+  let bytes = match tokio::fs::read(path).await {
+    Ok(data) => data,
+    Err(_) => return (StatusCode::NOT_FOUND, "Not found").into_response(),
+  };
+  let mime = mime_guess::from_path(path).first_or_octet_stream();
+  (
+    [(header::CONTENT_TYPE, mime.as_ref())],
+    bytes,
+  )
+    .into_response()
+}
+
+fn spawn_local_homepage_server(state: Arc<AppState>) {
+  let state_for_server = state.clone();
+  rt::spawn(async move {
+    let configured_port = {
+      let config = state_for_server.config.lock();
+      let configured = config.settings.local_homepage.web_port;
+      if configured == 0 {
+        8089
+      } else {
+        configured
+      }
+    };
+
+    let primary_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), configured_port);
+    let listener = match tokio::net::TcpListener::bind(primary_address).await {
+      Ok(listener) => listener,
+      Err(primary_error) => {
+        state_for_server.homepage_running.store(false, Ordering::SeqCst);
+        state_for_server.homepage_bound_port.store(0, Ordering::SeqCst);
+        eprintln!("local homepage server failed to bind on {primary_address}: {primary_error}");
+        emit_snapshot(&state_for_server);
+        return;
+      }
+    };
+    let bound_port = configured_port;
+
+    state_for_server.homepage_running.store(true, Ordering::SeqCst);
+    state_for_server.homepage_bound_port.store(bound_port, Ordering::SeqCst);
+    emit_snapshot(&state_for_server);
+
+    let router = Router::new()
+      .route("/", get(homepage_redirect))
+      .route("/index.html", get(homepage_html))
+      .route("/favicon.ico", get(homepage_favicon))
+      .route("/assets/{*path}", get(homepage_assets))
+      .route("/api/homepage/snapshot", get(homepage_api_snapshot))
+      .with_state(LocalHomepageServerState {
+        state: state_for_server.clone(),
+      });
+
+    // Axum server docs: one `serve` call binds one listener. To support IPv4 + IPv6
+    // access simultaneously, we run one server per bound listener.
+    let mut server_tasks = Vec::new();
+    {
+      let shutting_down = state_for_server.shutting_down.clone();
+      let router_v4 = router.clone();
+      server_tasks.push(rt::spawn(async move {
+        let _ = axum::serve(listener, router_v4)
+          .with_graceful_shutdown(wait_for_shutdown(shutting_down))
+          .await;
+      }));
+    }
+
+    let ipv6_address = SocketAddr::new(IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED), bound_port);
+    match tokio::net::TcpListener::bind(ipv6_address).await {
+      Ok(listener_v6) => {
+        let shutting_down = state_for_server.shutting_down.clone();
+        let router_v6 = router.clone();
+        server_tasks.push(rt::spawn(async move {
+          let _ = axum::serve(listener_v6, router_v6)
+            .with_graceful_shutdown(wait_for_shutdown(shutting_down))
+            .await;
+        }));
+      }
+      Err(error) => {
+        eprintln!("local homepage ipv6 listener bind failed on {ipv6_address}: {error}");
+      }
+    }
+
+    for task in server_tasks {
+      let _ = task.await;
+    }
+
+    state_for_server.homepage_running.store(false, Ordering::SeqCst);
+    state_for_server.homepage_bound_port.store(0, Ordering::SeqCst);
+    emit_snapshot(&state_for_server);
+  });
+}
+
+pub async fn wait_for_shutdown(shutting_down: Arc<AtomicBool>) {
+  while !shutting_down.load(Ordering::SeqCst) {
+    tokio::time::sleep(Duration::from_millis(200)).await;
+  }
+}
+
+// Starts every background task of the engine; the host keeps the process alive until `shutting_down` is set.
+pub fn start(state: &Arc<AppState>) {
+  spawn_geoip_download_if_needed(state);
+  spawn_local_homepage_server(state.clone());
+  spawn_startup_refresh_task(state.clone());
+  spawn_config_file_watcher(state.clone());
+  spawn_ip_change_worker(state.clone());
+  control::spawn_server(state.clone());
+}
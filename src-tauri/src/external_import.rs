@@ -0,0 +1,603 @@
+use anyhow::{anyhow, Context, Result};
+use serde_yaml::Value as YamlValue;
+
+use crate::models::{AppSettings, ExternalConfigKind, InterfaceSelector};
+
+// Result of translating another DDNS client's config; nothing here is persisted yet.
+pub struct ExternalImport {
+  pub settings: AppSettings,
+  // Zone name from the source config; Cloudflare wants the zone ID, which is resolved via the API.
+  pub zone_name: Option<String>,
+  pub api_token: Option<String>,
+  // Human-readable notes for every option that has no equivalent here.
+  pub unsupported: Vec<String>,
+}
+
+pub fn parse(kind: ExternalConfigKind, text: &str, base: &AppSettings) -> Result<ExternalImport> {
+  match kind {
+    ExternalConfigKind::DdnsGo => parse_ddns_go(text, base),
+    ExternalConfigKind::Inadyn => parse_inadyn(text, base),
+  }
+}
+
+// ddns-go keeps everything in `.ddns_go_config.yaml`: a `dnsconf` list with one entry per provider account.
+fn parse_ddns_go(text: &str, base: &AppSettings) -> Result<ExternalImport> {
+  let root = serde_yaml::from_str::<YamlValue>(text).context("failed to parse ddns-go YAML")?;
+  let root = root.as_mapping().ok_or_else(|| anyhow!("ddns-go config is not a YAML mapping"))?;
+  let mut import = ExternalImport {
+    settings: base.clone(),
+    zone_name: None,
+    api_token: None,
+    unsupported: Vec::new(),
+  };
+
+  for (key, value) in root {
+    let key = key.as_str().unwrap_or_default();
+    match key {
+      "dnsconf" => {}
+      // Credentials and access rules of the ddns-go web UI have no meaning for a desktop app.
+      "user" | "notallowwanaccess" | "lang" => {}
+      "webhook" if yaml_str(value, "webhookurl").is_empty() => {}
+      _ => import.unsupported.push(format!("top-level option `{key}`")),
+    }
+  }
+
+  let entries = root
+    .get("dnsconf")
+    .and_then(YamlValue::as_sequence)
+    .ok_or_else(|| anyhow!("ddns-go config has no `dnsconf` entries"))?;
+  let mut cloudflare_entries = Vec::new();
+  for (index, entry) in entries.iter().enumerate() {
+    let provider = yaml_str(yaml_get(entry, "dns"), "name");
+    if provider.eq_ignore_ascii_case("cloudflare") {
+      cloudflare_entries.push(entry);
+    } else {
+      import
+        .unsupported
+        .push(format!("dnsconf[{index}]: provider `{provider}` (only Cloudflare is supported)"));
+    }
+  }
+  let Some(entry) = cloudflare_entries.first().copied() else {
+    return Err(anyhow!("ddns-go config has no Cloudflare entry"));
+  };
+  if cloudflare_entries.len() > 1 {
+    import
+      .unsupported
+      .push(format!("{} additional Cloudflare entries (only the first is imported)", cloudflare_entries.len() - 1));
+  }
+
+  let ipv4 = yaml_get(entry, "ipv4");
+  if yaml_bool(ipv4, "enable") {
+    import.unsupported.push("ipv4 (A record) updates".to_string());
+  }
+  let ipv6 = yaml_get(entry, "ipv6");
+  if !yaml_bool(ipv6, "enable") {
+    import.unsupported.push("ipv6 is disabled in ddns-go; it is always enabled here".to_string());
+  }
+  match yaml_str(ipv6, "gettype").as_str() {
+    "netInterface" => {
+      let interface = yaml_str(ipv6, "netinterface");
+      if !interface.is_empty() {
+        import.settings.interface_candidates = vec![interface_selector(&interface)];
+      }
+    }
+    "" => {}
+    other => import.unsupported.push(format!(
+      "ipv6 source `{other}` (addresses are always read from local interfaces)"
+    )),
+  }
+  let ipv6_reg = yaml_str(ipv6, "ipv6reg");
+  if !ipv6_reg.is_empty() {
+    import.unsupported.push(format!("ipv6reg `{ipv6_reg}` address filter"));
+  }
+
+  let dns = yaml_get(entry, "dns");
+  let secret = yaml_str(dns, "secret");
+  if !yaml_str(dns, "id").is_empty() {
+    // With an id, ddns-go authenticates with the Global API Key + email instead of an API token.
+    import
+      .unsupported
+      .push("Cloudflare Global API Key authentication (create a scoped API token instead)".to_string());
+  } else if !secret.is_empty() {
+    import.api_token = Some(secret);
+  }
+
+  let domains = yaml_get(ipv6, "domains")
+    .as_sequence()
+    .map(|items| {
+      items
+        .iter()
+        .filter_map(YamlValue::as_str)
+        .map(str::trim)
+        .filter(|domain| !domain.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
+  apply_domains(&mut import, &domains, parse_ddns_go_domain);
+
+  match yaml_str(entry, "ttl").trim() {
+    "" => {}
+    ttl if ttl.eq_ignore_ascii_case("auto") => import.settings.cloudflare.ttl = None,
+    ttl => match ttl.parse::<u32>() {
+      Ok(ttl) => import.settings.cloudflare.ttl = Some(ttl),
+      Err(_) => import.unsupported.push(format!("ttl `{ttl}`")),
+    },
+  }
+
+  Ok(import)
+}
+
+// ddns-go accepts `host.example.com`, `host:example.com` (explicit zone) and trailing `?key=value` parameters.
+fn parse_ddns_go_domain(raw: &str, unsupported: &mut Vec<String>) -> (String, Option<String>) {
+  let (name, params) = raw.split_once('?').unwrap_or((raw, ""));
+  if !params.is_empty() {
+    unsupported.push(format!("domain parameters `?{params}` on `{name}`"));
+  }
+  match name.split_once(':') {
+    Some((sub, zone)) if sub.is_empty() || sub == "@" => (zone.to_string(), Some(zone.to_string())),
+    Some((sub, zone)) => (format!("{sub}.{zone}"), Some(zone.to_string())),
+    None => (name.to_string(), None),
+  }
+}
+
+// inadyn reads a libconfuse file: `key = value` settings plus `provider NAME { ... }` sections.
+fn parse_inadyn(text: &str, base: &AppSettings) -> Result<ExternalImport> {
+  let entries = parse_confuse(text).context("failed to parse inadyn config")?;
+  let mut import = ExternalImport {
+    settings: base.clone(),
+    zone_name: None,
+    api_token: None,
+    unsupported: Vec::new(),
+  };
+
+  let mut cloudflare_sections = Vec::new();
+  for entry in &entries {
+    match entry {
+      ConfuseEntry::Setting(key, value) => match key.as_str() {
+        "iface" => {
+          if let Some(interface) = value.first() {
+            import.settings.interface_candidates = vec![interface_selector(interface)];
+          }
+        }
+        "period" => match value.first().and_then(|text| text.parse::<u64>().ok()) {
+          Some(seconds) => import.settings.sync_policy.poll_interval_seconds = seconds,
+          None => import.unsupported.push(format!("period `{}`", value.join(", "))),
+        },
+        "forced-update" => match value.first().and_then(|text| text.parse::<u64>().ok()) {
+          Some(seconds) => import.settings.sync_policy.heartbeat_interval_hours = seconds.div_ceil(3600),
+          None => import.unsupported.push(format!("forced-update `{}`", value.join(", "))),
+        },
+        "allow-ipv6" => {
+          if value.first().is_none_or(|flag| flag != "true") {
+            import.unsupported.push("allow-ipv6 = false (IPv6 is always used here)".to_string());
+          }
+        }
+        // Local runtime paths of the inadyn daemon.
+        "cache-dir" | "pidfile" => {}
+        _ => import.unsupported.push(format!("option `{key}`")),
+      },
+      ConfuseEntry::Section(kind, name, options) => {
+        if name.to_ascii_lowercase().contains("cloudflare.com") {
+          cloudflare_sections.push(options);
+        } else {
+          import
+            .unsupported
+            .push(format!("{kind} `{name}` (only Cloudflare is supported)"));
+        }
+      }
+    }
+  }
+
+  let Some(options) = cloudflare_sections.first().copied() else {
+    return Err(anyhow!("inadyn config has no Cloudflare provider section"));
+  };
+  if cloudflare_sections.len() > 1 {
+    import.unsupported.push(format!(
+      "{} additional Cloudflare provider sections (only the first is imported)",
+      cloudflare_sections.len() - 1
+    ));
+  }
+  let mut hostnames = Vec::new();
+  for (key, value) in options {
+    match key.as_str() {
+      // The Cloudflare plugin expects the zone name as username and an API token as password.
+      "username" => import.zone_name = value.first().cloned().filter(|zone| !zone.is_empty()),
+      "password" => import.api_token = value.first().cloned().filter(|token| !token.is_empty()),
+      "hostname" => hostnames.extend(value.iter().cloned()),
+      "ttl" => match value.first().and_then(|text| text.parse::<u32>().ok()) {
+        // Cloudflare treats TTL 1 as "automatic".
+        Some(1) => import.settings.cloudflare.ttl = None,
+        Some(ttl) => import.settings.cloudflare.ttl = Some(ttl),
+        None => import.unsupported.push(format!("ttl `{}`", value.join(", "))),
+      },
+      "proxied" if value.first().is_some_and(|flag| flag == "false") => {}
+      _ => import
+        .unsupported
+        .push(format!("provider option `{key} = {}`", value.join(", "))),
+    }
+  }
+  apply_domains(&mut import, &hostnames, |hostname, _| (hostname.to_string(), None));
+
+  Ok(import)
+}
+
+fn apply_domains(
+  import: &mut ExternalImport,
+  domains: &[String],
+  parse_domain: impl Fn(&str, &mut Vec<String>) -> (String, Option<String>),
+) {
+  let Some((first, rest)) = domains.split_first() else {
+    import.unsupported.push("no hostname to update was found".to_string());
+    return;
+  };
+  let (domain, zone_name) = parse_domain(first, &mut import.unsupported);
+  import.settings.cloudflare.domain = domain;
+  import.settings.cloudflare.record_id.clear();
+  if zone_name.is_some() {
+    import.zone_name = zone_name;
+  }
+  for extra in rest {
+    import
+      .unsupported
+      .push(format!("additional hostname `{extra}` (one domain per configuration)"));
+  }
+}
+
+// Zone names the domain may belong to, most specific first; stops before the bare TLD.
+pub fn zone_name_candidates(domain: &str) -> Vec<String> {
+  let labels = domain.trim_end_matches('.').split('.').collect::<Vec<_>>();
+  (0..labels.len().saturating_sub(1))
+    .map(|start| labels[start..].join("."))
+    .collect()
+}
+
+fn interface_selector(name: &str) -> InterfaceSelector {
+  // Both tools store the name users see, which on Windows is the friendly name; a wildcard-free glob
+  // matches it exactly against both the OS name and the friendly name.
+  InterfaceSelector::Glob(name.to_string())
+}
+
+fn yaml_get<'a>(value: &'a YamlValue, key: &str) -> &'a YamlValue {
+  value.get(key).unwrap_or(&YamlValue::Null)
+}
+
+fn yaml_str(value: &YamlValue, key: &str) -> String {
+  match yaml_get(value, key) {
+    YamlValue::String(text) => text.trim().to_string(),
+    YamlValue::Number(number) => number.to_string(),
+    YamlValue::Bool(flag) => flag.to_string(),
+    _ => String::new(),
+  }
+}
+
+fn yaml_bool(value: &YamlValue, key: &str) -> bool {
+  yaml_get(value, key).as_bool().unwrap_or(false)
+}
+
+enum ConfuseEntry {
+  Setting(String, Vec<String>),
+  // `provider` or `custom`, the section title and its options.
+  Section(String, String, Vec<(String, Vec<String>)>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+  Word(String),
+  Equals,
+  Open,
+  Close,
+  Comma,
+}
+
+fn parse_confuse(text: &str) -> Result<Vec<ConfuseEntry>> {
+  let tokens = tokenize_confuse(text)?;
+  let mut cursor = tokens.into_iter().peekable();
+  let mut entries = Vec::new();
+  while let Some(token) = cursor.next() {
+    let Token::Word(key) = token else {
+      return Err(anyhow!("unexpected {token:?} at top level"));
+    };
+    match cursor.next() {
+      Some(Token::Equals) => entries.push(ConfuseEntry::Setting(key, parse_confuse_value(&mut cursor)?)),
+      Some(Token::Word(title)) => {
+        if cursor.next() != Some(Token::Open) {
+          return Err(anyhow!("expected `{{` after `{key} {title}`"));
+        }
+        let mut options = Vec::new();
+        loop {
+          match cursor.next() {
+            Some(Token::Close) => break,
+            Some(Token::Word(option)) => {
+              if cursor.next() != Some(Token::Equals) {
+                return Err(anyhow!("expected `=` after `{option}` in `{key} {title}`"));
+              }
+              options.push((option, parse_confuse_value(&mut cursor)?));
+            }
+            other => return Err(anyhow!("unexpected {other:?} in `{key} {title}`")),
+          }
+        }
+        entries.push(ConfuseEntry::Section(key, title, options));
+      }
+      other => return Err(anyhow!("unexpected {other:?} after `{key}`")),
+    }
+  }
+  Ok(entries)
+}
+
+fn parse_confuse_value(cursor: &mut std::iter::Peekable<std::vec::IntoIter<Token>>) -> Result<Vec<String>> {
+  match cursor.next() {
+    Some(Token::Word(value)) => Ok(vec![value]),
+    Some(Token::Open) => {
+      let mut values = Vec::new();
+      loop {
+        match cursor.next() {
+          Some(Token::Word(value)) => values.push(value),
+          Some(Token::Comma) => {}
+          Some(Token::Close) => return Ok(values),
+          other => return Err(anyhow!("unexpected {other:?} in list")),
+        }
+      }
+    }
+    other => Err(anyhow!("expected a value, found {other:?}")),
+  }
+}
+
+fn tokenize_confuse(text: &str) -> Result<Vec<Token>> {
+  let mut tokens = Vec::new();
+  let mut chars = text.chars().peekable();
+  while let Some(ch) = chars.next() {
+    match ch {
+      ch if ch.is_whitespace() => {}
+      // libconfuse accepts shell, C++ and C style comments; inside a bare word (`http://...`) they are literal.
+      '#' => {
+        while chars.next_if(|next| *next != '\n').is_some() {}
+      }
+      '/' if chars.next_if_eq(&'/').is_some() => {
+        while chars.next_if(|next| *next != '\n').is_some() {}
+      }
+      '/' if chars.next_if_eq(&'*').is_some() => {
+        let mut previous = '\0';
+        loop {
+          match chars.next() {
+            Some('/') if previous == '*' => break,
+            Some(next) => previous = next,
+            None => return Err(anyhow!("unterminated comment")),
+          }
+        }
+      }
+      '=' => tokens.push(Token::Equals),
+      '{' => tokens.push(Token::Open),
+      '}' => tokens.push(Token::Close),
+      ',' => tokens.push(Token::Comma),
+      '"' | '\'' => {
+        let mut word = String::new();
+        loop {
+          match chars.next() {
+            Some('\\') => word.extend(chars.next()),
+            Some(next) if next == ch => break,
+            Some(next) => word.push(next),
+            None => return Err(anyhow!("unterminated string")),
+          }
+        }
+        tokens.push(Token::Word(word));
+      }
+      _ => {
+        let mut word = ch.to_string();
+        while let Some(next) = chars.next_if(|next| !next.is_whitespace() && !"={},#\"'".contains(*next)) {
+          word.push(next);
+        }
+        tokens.push(Token::Word(word));
+      }
+    }
+  }
+  Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // `.ddns_go_config.yaml` as written by the ddns-go v6 web UI.
+  const DDNS_GO_CONFIG: &str = r#"dnsconf:
+    - name: ""
+      ipv4:
+        enable: false
+        gettype: url
+        url: https://myip.ipip.net, https://ddns.oray.com/checkip, https://ip.3322.net, https://4.ipw.cn
+        netinterface: ""
+        cmd: ""
+        domains:
+            - ""
+      ipv6:
+        enable: true
+        gettype: netInterface
+        url: https://speed.neu6.edu.cn/getIP.php, https://v6.ident.me, https://6.ipw.cn
+        netinterface: eth0
+        cmd: ""
+        ipv6reg: ""
+        domains:
+            - nas:example.com
+            - media.example.com?proxied=true
+      dns:
+        name: cloudflare
+        id: ""
+        secret: 3f1c2a9be07d4e5f8a6b0c1d2e3f4a5b6c7d8e9f
+      ttl: "300"
+    - name: ""
+      ipv4:
+        enable: true
+        gettype: url
+        url: https://4.ipw.cn
+        netinterface: ""
+        cmd: ""
+        domains:
+            - office.example.cn
+      ipv6:
+        enable: false
+        gettype: url
+        url: ""
+        netinterface: ""
+        cmd: ""
+        ipv6reg: ""
+        domains:
+            - ""
+      dns:
+        name: alidns
+        id: LTAI5tExampleAccessKey
+        secret: ExampleAccessKeySecret
+      ttl: ""
+user:
+    username: admin
+    password: $2a$10$Q8Jz9l0aHc3C0m5y4u6i3e8W1r2t3y4u5i6o7p8a9s0d1f2g3h4j5
+webhook:
+    webhookurl: ""
+    webhookrequestbody: ""
+    webhookheaders: ""
+notallowwanaccess: true
+lang: zh
+"#;
+
+  // `/etc/inadyn.conf` for inadyn 2.x, mixing all three libconfuse comment styles.
+  const INADYN_CONFIG: &str = r#"# In-A-Dyn v2.0 configuration file format
+period          = 300
+forced-update   = 604800
+user-agent      = Mozilla/5.0
+allow-ipv6      = true
+iface           = enp3s0
+
+/*
+ * Cloudflare: the username is the zone name and the password a scoped API token.
+ */
+provider cloudflare.com {
+    username = example.com
+    password = "cf-token-0123456789abcdef"  // Zone.DNS edit on example.com only
+    hostname = { "home.example.com", "vpn.example.com" }
+    ttl      = 1
+    proxied  = false
+}
+
+provider default@freedns.afraid.org {
+    username = lower-case-username
+    password = case-sensitive-pwd
+    hostname = some.example.com
+}
+"#;
+
+  #[test]
+  fn ddns_go_config_imports_the_first_cloudflare_entry() {
+    let import = parse(ExternalConfigKind::DdnsGo, DDNS_GO_CONFIG, &AppSettings::default()).unwrap();
+    assert_eq!(import.settings.cloudflare.domain, "nas.example.com");
+    assert_eq!(import.zone_name.as_deref(), Some("example.com"));
+    assert_eq!(import.api_token.as_deref(), Some("3f1c2a9be07d4e5f8a6b0c1d2e3f4a5b6c7d8e9f"));
+    assert_eq!(import.settings.cloudflare.ttl, Some(300));
+    assert_eq!(import.settings.interface_candidates, vec![InterfaceSelector::Glob("eth0".to_string())]);
+    assert_eq!(
+      import.unsupported,
+      vec![
+        "dnsconf[1]: provider `alidns` (only Cloudflare is supported)".to_string(),
+        "additional hostname `media.example.com?proxied=true` (one domain per configuration)".to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn ddns_go_reports_options_without_an_equivalent() {
+    let config = DDNS_GO_CONFIG
+      .replacen("enable: false", "enable: true", 1)
+      .replacen("gettype: netInterface", "gettype: url", 1)
+      .replacen("ipv6reg: \"\"", "ipv6reg: \"@2\"", 1)
+      .replacen("id: \"\"", "id: admin@example.com", 1)
+      .replacen("ttl: \"300\"", "ttl: AUTO", 1)
+      .replace("webhookurl: \"\"", "webhookurl: https://hooks.example.com/ddns");
+    let mut base = AppSettings::default();
+    base.cloudflare.ttl = Some(120);
+    let import = parse(ExternalConfigKind::DdnsGo, &config, &base).unwrap();
+
+    // With an `id`, the secret is a Global API Key and must not be stored as a token.
+    assert_eq!(import.api_token, None);
+    assert_eq!(import.settings.cloudflare.ttl, None);
+    assert!(import.settings.interface_candidates.is_empty());
+    for expected in [
+      "top-level option `webhook`",
+      "ipv4 (A record) updates",
+      "ipv6 source `url` (addresses are always read from local interfaces)",
+      "ipv6reg `@2` address filter",
+      "Cloudflare Global API Key authentication (create a scoped API token instead)",
+    ] {
+      assert!(import.unsupported.iter().any(|note| note == expected), "missing `{expected}`");
+    }
+  }
+
+  #[test]
+  fn ddns_go_without_cloudflare_is_rejected() {
+    let config = DDNS_GO_CONFIG.replace("name: cloudflare", "name: dnspod");
+    assert!(parse(ExternalConfigKind::DdnsGo, &config, &AppSettings::default()).is_err());
+  }
+
+  #[test]
+  fn ddns_go_domains_split_into_name_and_zone() {
+    let cases = [
+      ("home.example.com", "home.example.com", None, false),
+      ("nas:example.com", "nas.example.com", Some("example.com"), false),
+      ("a.b:example.co.uk", "a.b.example.co.uk", Some("example.co.uk"), false),
+      (":example.com", "example.com", Some("example.com"), false),
+      ("@:example.com", "example.com", Some("example.com"), false),
+      ("www.example.com?proxied=true", "www.example.com", None, true),
+      ("www:example.com?ttl=60&proxied=true", "www.example.com", Some("example.com"), true),
+    ];
+    for (raw, domain, zone, has_params) in cases {
+      let mut unsupported = Vec::new();
+      let (parsed_domain, parsed_zone) = parse_ddns_go_domain(raw, &mut unsupported);
+      assert_eq!(parsed_domain, domain, "{raw}");
+      assert_eq!(parsed_zone.as_deref(), zone, "{raw}");
+      assert_eq!(!unsupported.is_empty(), has_params, "{raw}");
+    }
+  }
+
+  #[test]
+  fn inadyn_config_imports_the_cloudflare_provider() {
+    let import = parse(ExternalConfigKind::Inadyn, INADYN_CONFIG, &AppSettings::default()).unwrap();
+    assert_eq!(import.settings.cloudflare.domain, "home.example.com");
+    assert_eq!(import.zone_name.as_deref(), Some("example.com"));
+    assert_eq!(import.api_token.as_deref(), Some("cf-token-0123456789abcdef"));
+    assert_eq!(import.settings.cloudflare.ttl, None);
+    assert_eq!(import.settings.sync_policy.poll_interval_seconds, 300);
+    assert_eq!(import.settings.sync_policy.heartbeat_interval_hours, 168);
+    assert_eq!(import.settings.interface_candidates, vec![InterfaceSelector::Glob("enp3s0".to_string())]);
+    assert_eq!(
+      import.unsupported,
+      vec![
+        "option `user-agent`".to_string(),
+        "provider `default@freedns.afraid.org` (only Cloudflare is supported)".to_string(),
+        "additional hostname `vpn.example.com` (one domain per configuration)".to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn inadyn_reports_unknown_provider_options() {
+    let config = INADYN_CONFIG.replace("proxied  = false", "proxied  = true\n    ssl = false");
+    let import = parse(ExternalConfigKind::Inadyn, &config, &AppSettings::default()).unwrap();
+    assert!(import.unsupported.contains(&"provider option `proxied = true`".to_string()));
+    assert!(import.unsupported.contains(&"provider option `ssl = false`".to_string()));
+  }
+
+  #[test]
+  fn confuse_comments_are_skipped_but_slashes_in_words_are_kept() {
+    let tokens = tokenize_confuse(
+      "a = b // trailing\n/* block\n * with } and = inside */ c = http://x/y # hash\nd = \"// quoted\"",
+    )
+    .unwrap();
+    let words = tokens
+      .into_iter()
+      .filter_map(|token| match token {
+        Token::Word(word) => Some(word),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(words, ["a", "b", "c", "http://x/y", "d", "// quoted"]);
+    assert!(tokenize_confuse("a = b /* never closed").is_err());
+    assert!(tokenize_confuse("a = b /*/").is_err());
+  }
+}
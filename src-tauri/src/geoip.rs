@@ -150,13 +150,9 @@ fn find_database_path(
     }
   }
 
-  for candidate in build_candidates(file_name, config_dir, resource_dir) {
-    if candidate.exists() {
-      return Some(candidate);
-    }
-  }
-
-  None
+  build_candidates(file_name, config_dir, resource_dir)
+    .into_iter()
+    .find(|candidate| candidate.exists())
 }
 
 fn build_candidates(file_name: &str, config_dir: Option<&Path>, resource_dir: Option<&Path>) -> Vec<PathBuf> {
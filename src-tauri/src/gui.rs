@@ -0,0 +1,468 @@
+use std::sync::{atomic::Ordering, Arc};
+
+use tauri::{
+  menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder, PredefinedMenuItem},
+  async_runtime::JoinHandle,
+  AppHandle, Emitter, Manager, RunEvent, Runtime, WebviewWindowBuilder, WindowEvent,
+};
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::engine::{self, AppState, EngineEvents, EngineOptions};
+use crate::models::{
+  AppSettings, AppSnapshot, ExportHistoryRequest, ExportSettingsRequest, ExternalImportReport, ExternalImportRequest,
+  FieldError, HistoryPage, HistoryQuery, HistoryStats, ImportSettingsRequest, LookupRecordIdRequest, PrefixChangedEvent,
+  PrefixRecord, RotateTokenRequest, SaveCredentialRequest, SaveSettingsRequest, SettingsImportPreview, TokenRotationReport,
+};
+use crate::{config, platform, token_expiry};
+
+const SNAPSHOT_EVENT: &str = "ddns://snapshot";
+const NETWORK_CHANGED_EVENT: &str = "ddns://network-changed";
+const PREFIX_CHANGED_EVENT: &str = "ddns://prefix-changed";
+const AUTOSTART_ARG: &str = "--autostart";
+
+#[derive(Clone)]
+struct SharedState(Arc<AppState>);
+
+// Pending switch into lightweight mode; activating the window cancels it.
+#[derive(Clone, Default)]
+struct LightweightEntry(Arc<AsyncMutex<Option<JoinHandle<()>>>>);
+
+// Forwards engine notifications to the webview, the tray and the OS notification center.
+struct GuiEvents {
+  app: AppHandle,
+}
+
+impl EngineEvents for GuiEvents {
+  fn snapshot_changed(&self, state: &Arc<AppState>) {
+    refresh_tray_menu(&self.app, state);
+    let _ = self.app.emit(SNAPSHOT_EVENT, state.snapshot());
+  }
+
+  fn network_changed(&self, _state: &Arc<AppState>) {
+    let _ = self.app.emit(NETWORK_CHANGED_EVENT, ());
+  }
+
+  fn prefix_changed(&self, event: &PrefixChangedEvent) {
+    let _ = self.app.emit(PREFIX_CHANGED_EVENT, event);
+  }
+
+  fn alert(&self, message: &str) {
+    let _ = self
+      .app
+      .notification()
+      .builder()
+      .title("Cloudflare IPv6 DDNS")
+      .body(message)
+      .show();
+  }
+
+  fn settings_applied(&self, state: &Arc<AppState>) -> Result<(), String> {
+    let launch_on_startup = state.config.lock().settings.launch_on_startup;
+    apply_autostart(&self.app, launch_on_startup)
+  }
+}
+
+#[tauri::command]
+fn get_snapshot(state: tauri::State<'_, SharedState>) -> AppSnapshot {
+  state.inner().0.snapshot()
+}
+
+#[tauri::command]
+async fn save_settings(
+  state: tauri::State<'_, SharedState>,
+  request: SaveSettingsRequest,
+) -> Result<AppSnapshot, String> {
+  engine::save_settings(&state.inner().0, request)?;
+  Ok(state.inner().0.snapshot())
+}
+
+#[tauri::command]
+fn validate_settings(state: tauri::State<'_, SharedState>, settings: AppSettings) -> Vec<FieldError> {
+  state.inner().0.validate(&settings)
+}
+
+#[tauri::command]
+async fn manual_push_now(state: tauri::State<'_, SharedState>) -> Result<AppSnapshot, String> {
+  engine::run_manual_push(&state.inner().0).await?;
+  Ok(state.inner().0.snapshot())
+}
+
+#[tauri::command]
+async fn import_external_config(
+  state: tauri::State<'_, SharedState>,
+  request: ExternalImportRequest,
+) -> Result<ExternalImportReport, String> {
+  Ok(engine::import_external_config(&state.inner().0, request).await?)
+}
+
+#[tauri::command]
+async fn lookup_record_id(
+  state: tauri::State<'_, SharedState>,
+  request: LookupRecordIdRequest,
+) -> Result<AppSnapshot, String> {
+  engine::lookup_record_id(&state.inner().0, request).await?;
+  Ok(state.inner().0.snapshot())
+}
+
+#[tauri::command]
+fn export_settings_bundle(state: tauri::State<'_, SharedState>, request: ExportSettingsRequest) -> Result<(), String> {
+  engine::export_settings_bundle(&state.inner().0, request)
+}
+
+#[tauri::command]
+fn preview_settings_import(
+  state: tauri::State<'_, SharedState>,
+  request: ImportSettingsRequest,
+) -> Result<SettingsImportPreview, String> {
+  engine::preview_settings_import(&state.inner().0, &request)
+}
+
+#[tauri::command]
+fn import_settings_bundle(
+  state: tauri::State<'_, SharedState>,
+  request: ImportSettingsRequest,
+) -> Result<AppSnapshot, String> {
+  engine::import_settings_bundle(&state.inner().0, &request)?;
+  Ok(state.inner().0.snapshot())
+}
+
+#[tauri::command]
+fn save_credential(
+  state: tauri::State<'_, SharedState>,
+  request: SaveCredentialRequest,
+) -> Result<AppSnapshot, String> {
+  engine::save_credential(&state.inner().0, request)?;
+  Ok(state.inner().0.snapshot())
+}
+
+#[tauri::command]
+fn delete_credential(state: tauri::State<'_, SharedState>, id: String) -> Result<AppSnapshot, String> {
+  engine::delete_credential(&state.inner().0, &id)?;
+  Ok(state.inner().0.snapshot())
+}
+
+#[tauri::command]
+async fn rotate_token(
+  state: tauri::State<'_, SharedState>,
+  request: RotateTokenRequest,
+) -> Result<TokenRotationReport, String> {
+  engine::rotate_token(&state.inner().0, request).await
+}
+
+#[tauri::command]
+fn get_prefix_history(state: tauri::State<'_, SharedState>) -> Vec<PrefixRecord> {
+  state.inner().0.prefix_history.lock().records().to_vec()
+}
+
+#[tauri::command]
+fn get_history(state: tauri::State<'_, SharedState>, request: HistoryQuery) -> HistoryPage {
+  state.inner().0.history.lock().page(&request)
+}
+
+#[tauri::command]
+fn get_history_stats(state: tauri::State<'_, SharedState>) -> HistoryStats {
+  engine::history_stats(&state.inner().0)
+}
+
+#[tauri::command]
+fn export_history(state: tauri::State<'_, SharedState>, request: ExportHistoryRequest) -> Result<Option<String>, String> {
+  engine::export_history(&state.inner().0, request)
+}
+
+#[tauri::command]
+fn enter_lightweight_mode(app: AppHandle, state: tauri::State<'_, SharedState>) -> Result<AppSnapshot, String> {
+  set_lightweight_mode(&state.inner().0, true);
+  destroy_main_window(&app);
+  engine::emit_snapshot(&state.inner().0);
+  Ok(state.inner().0.snapshot())
+}
+
+#[tauri::command]
+fn restart_app(app: AppHandle, state: tauri::State<'_, SharedState>) {
+  engine::flush_runtime_state(&state.inner().0);
+  app.restart();
+}
+
+fn build_tray_menu<R: Runtime>(app: &AppHandle<R>, state: &Arc<AppState>) -> tauri::Result<tauri::menu::Menu<R>> {
+  let config = state.config.lock().clone();
+  let last_sync = config
+    .cache
+    .last_sync_time
+    .unwrap_or_else(|| "never".to_string());
+
+  let show_item = MenuItemBuilder::with_id("tray_show_main", "Show main window").build(app)?;
+  let last_item = MenuItemBuilder::with_id("tray_last_sync", format!("Last update: {last_sync}"))
+    .enabled(false)
+    .build(app)?;
+  let token_warning = state.token_status.lock().as_ref().and_then(token_expiry::warning);
+  let token_item = match token_warning {
+    Some(warning) => Some(MenuItemBuilder::with_id("tray_token_warning", warning).enabled(false).build(app)?),
+    None => None,
+  };
+  let auto_item = CheckMenuItemBuilder::with_id("tray_auto_update", "Auto update enabled")
+    .checked(config.settings.auto_push)
+    .build(app)?;
+  let manual_item = MenuItemBuilder::with_id("tray_manual_update", "Manual update").build(app)?;
+  let separator = PredefinedMenuItem::separator(app)?;
+  let quit_item = MenuItemBuilder::with_id("tray_quit", "Quit").build(app)?;
+
+  let mut menu = MenuBuilder::new(app).item(&show_item).item(&last_item);
+  if let Some(token_item) = token_item.as_ref() {
+    menu = menu.item(token_item);
+  }
+  menu
+    .item(&separator)
+    .item(&auto_item)
+    .item(&manual_item)
+    .item(&separator)
+    .item(&quit_item)
+    .build()
+}
+
+fn refresh_tray_menu(app: &AppHandle, state: &Arc<AppState>) {
+  if let Some(tray) = app.tray_by_id("main-tray") {
+    if let Ok(menu) = build_tray_menu(app, state) {
+      let _ = tray.set_menu(Some(menu));
+    }
+  }
+}
+
+fn show_main_window(app: &AppHandle) {
+  if let Some(window) = ensure_main_window(app) {
+    let _ = window.center();
+    let _ = window.show();
+    let _ = window.unminimize();
+    let _ = window.set_focus();
+  }
+}
+
+fn set_lightweight_mode(state: &Arc<AppState>, enabled: bool) {
+  // Window visibility is runtime-only; startup re-derives it from the autostart flag, so it is not persisted.
+  state.config.lock().settings.lightweight_mode = enabled;
+}
+
+fn activate_main_window(app: &AppHandle, state: &Arc<AppState>) {
+  show_main_window(app);
+  set_lightweight_mode(state, false);
+  if let Some(entry) = app.try_state::<LightweightEntry>() {
+    let entry = entry.inner().clone();
+    tauri::async_runtime::spawn(async move {
+      if let Some(task) = entry.0.lock().await.take() {
+        task.abort();
+      }
+    });
+  }
+  engine::emit_snapshot(state);
+}
+
+fn graceful_quit(app: &AppHandle, state: &Arc<AppState>) {
+  state.shutting_down.store(true, Ordering::SeqCst);
+  engine::flush_runtime_state(state);
+  if let Some(entry) = app.try_state::<LightweightEntry>() {
+    if let Ok(mut task_guard) = entry.0.try_lock() {
+      if let Some(task) = task_guard.take() {
+        task.abort();
+      }
+    }
+  }
+  // Prefer a normal close sequence on quit; avoid force-destroy teardown when exiting process.
+  if let Some(window) = app.get_webview_window("main") {
+    let _ = window.close();
+  }
+  app.exit(0);
+}
+
+fn destroy_main_window(app: &AppHandle) {
+  if let Some(window) = app.get_webview_window("main") {
+    // Tauri webview docs: `destroy` force-closes the window and does not emit close events.
+    let _ = window.destroy();
+  }
+}
+
+fn ensure_main_window(app: &AppHandle) -> Option<tauri::WebviewWindow> {
+  if let Some(window) = app.get_webview_window("main") {
+    return Some(window);
+  }
+
+  // Tauri webview docs: `WebviewWindowBuilder::from_config` recreates windows from tauri.conf config.
+  let config = app
+    .config()
+    .app
+    .windows
+    .iter()
+    .find(|window| window.label == "main")
+    .cloned()?;
+  WebviewWindowBuilder::from_config(app, &config)
+    .ok()?
+    .build()
+    .ok()
+}
+
+fn apply_autostart(app: &AppHandle, enabled: bool) -> Result<(), String> {
+  // Official plugin docs: tauri-plugin-autostart "Usage" uses ManagerExt::autolaunch()
+  // with `enable`, `disable`, and `is_enabled`.
+  let launcher = app.autolaunch();
+  if enabled {
+    launcher
+      .enable()
+      .map_err(|error| format!("failed enabling autostart: {error}"))?;
+  } else {
+    // Some platforms return "file not found" when disabling an entry that was never created.
+    // We first check current state and only call `disable` when it is currently enabled.
+    let already_enabled = launcher
+      .is_enabled()
+      .map_err(|error| format!("failed reading autostart state: {error}"))?;
+    if already_enabled {
+      if let Err(error) = launcher.disable() {
+        let text = error.to_string();
+        // Windows may surface os error 2 for missing startup artifacts; treat as already disabled.
+        if !(text.contains("os error 2") || text.contains("cannot find the file")) {
+          return Err(format!("failed disabling autostart: {error}"));
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+pub fn run() {
+  let app = tauri::Builder::default()
+    .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+      if let Some(state) = app.try_state::<SharedState>() {
+        activate_main_window(app, &state.inner().0);
+      } else {
+        show_main_window(app);
+      }
+    }))
+    .plugin(tauri_plugin_autostart::init(
+      tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+      Some(vec![AUTOSTART_ARG]),
+    ))
+    .plugin(tauri_plugin_notification::init())
+    .setup(|app| {
+      let is_autostart_launch = std::env::args().any(|arg| arg == AUTOSTART_ARG);
+      let (mut loaded_config, config_path, config_recovery) =
+        config::load_or_default(app.handle()).map_err(|error| anyhow::anyhow!("{error}"))?;
+
+      let _ = apply_autostart(app.handle(), loaded_config.settings.launch_on_startup);
+      if let Ok(enabled) = app.autolaunch().is_enabled() {
+        if loaded_config.settings.launch_on_startup != enabled {
+          loaded_config.settings.launch_on_startup = enabled;
+          let _ = config::save_config(&config_path, &loaded_config);
+        }
+      }
+      // Autostart boots in lightweight mode; direct launches boot with visible GUI.
+      loaded_config.settings.lightweight_mode = is_autostart_launch;
+
+      let state = SharedState(AppState::new(
+        EngineOptions {
+          config: loaded_config,
+          config_path,
+          config_recovery,
+          resource_dir: app.path().resource_dir().ok(),
+          watch_network: true,
+        },
+        Arc::new(GuiEvents {
+          app: app.handle().clone(),
+        }),
+      ));
+      app.manage(state.clone());
+      app.manage(LightweightEntry::default());
+
+      let tray_menu = build_tray_menu(app.handle(), &state.0).map_err(|error| anyhow::anyhow!("{error}"))?;
+      let tray_state = state.clone();
+      platform::install_tray(
+        app.handle(),
+        tray_menu,
+        platform::TrayHandlers {
+          on_show_main: Arc::new({
+            let state = state.clone();
+            move |app| activate_main_window(app, &state.0)
+          }),
+          on_manual_update: Arc::new(move |_: &AppHandle| {
+            let state = tray_state.clone();
+            tauri::async_runtime::spawn(async move {
+              let _ = engine::run_manual_push(&state.0).await;
+            });
+          }),
+          on_toggle_auto_update: Arc::new({
+            let tray_state = state.clone();
+            move |_: &AppHandle| {
+              let mut config = tray_state.0.config.lock();
+              config.settings.auto_push = !config.settings.auto_push;
+              let _ = config::save_config(&tray_state.0.config_path, &config);
+              drop(config);
+              tray_state.0.notify.notify_one();
+              engine::emit_snapshot(&tray_state.0);
+            }
+          }),
+          on_quit: Arc::new({
+            let state = state.clone();
+            move |app| {
+              graceful_quit(app, &state.0);
+            }
+          }),
+        },
+      )
+      .map_err(anyhow::Error::msg)?;
+
+      if is_autostart_launch {
+        // Autostart should run without a resident WebView/WebView2 process.
+        destroy_main_window(app.handle());
+      } else {
+        show_main_window(app.handle());
+      }
+
+      engine::emit_snapshot(&state.0);
+      engine::start(&state.0);
+      Ok(())
+    })
+    .invoke_handler(tauri::generate_handler![
+      get_snapshot,
+      save_settings,
+      manual_push_now,
+      lookup_record_id,
+      get_prefix_history,
+      get_history,
+      get_history_stats,
+      export_history,
+      export_settings_bundle,
+      preview_settings_import,
+      import_settings_bundle,
+      import_external_config,
+      validate_settings,
+      save_credential,
+      delete_credential,
+      rotate_token,
+      enter_lightweight_mode,
+      restart_app
+    ])
+    .on_window_event(|window, event| {
+      if let WindowEvent::CloseRequested { api, .. } = event {
+        let app = window.app_handle();
+        let state = app.state::<SharedState>();
+        if state.inner().0.shutting_down.load(Ordering::SeqCst) {
+          return;
+        }
+        // Enter lightweight mode immediately when users close the main window.
+        api.prevent_close();
+        set_lightweight_mode(&state.inner().0, true);
+        destroy_main_window(&app);
+        engine::emit_snapshot(&state.inner().0);
+      }
+    })
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application");
+
+  app.run(|app_handle, event| {
+    if let RunEvent::ExitRequested { api, .. } = event {
+      let state = app_handle.state::<SharedState>();
+      if !state.inner().0.shutting_down.load(Ordering::SeqCst) {
+        // Keep backend scheduler + tray alive even when all windows are destroyed in lightweight mode.
+        api.prevent_exit();
+      }
+    }
+  });
+}
\ No newline at end of file
@@ -0,0 +1,224 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::models::{HistoryEntry, HistoryPage, HistoryQuery};
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+// The active file is rotated to `history.1.jsonl` once it grows past this size; older files shift up by one.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_ROTATED_FILES: usize = 5;
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+
+// Append-only log of address changes and push attempts, one JSON object per line.
+pub struct HistoryStore {
+  dir: PathBuf,
+}
+
+impl HistoryStore {
+  pub fn new(config_path: &Path) -> Self {
+    Self {
+      dir: config_path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    }
+  }
+
+  pub fn append(&self, entry: &HistoryEntry) -> Result<()> {
+    let path = self.file_path(0);
+    let size = std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+    if size >= MAX_FILE_BYTES {
+      self.rotate()?;
+    }
+
+    let mut line = serde_json::to_string(entry).context("failed to serialize history entry")?;
+    line.push('\n');
+    // A crash mid-write leaves a torn last line without its newline; terminate it so this entry starts on its own line.
+    if ends_mid_line(&path) {
+      line.insert(0, '\n');
+    }
+    let mut file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&path)
+      .with_context(|| format!("failed to open {}", path.display()))?;
+    // Each entry is written in one call, so a crash leaves at most one torn line, which reads skip.
+    file
+      .write_all(line.as_bytes())
+      .with_context(|| format!("failed to append to {}", path.display()))
+  }
+
+  // Every retained entry, oldest first.
+  pub fn entries(&self) -> Vec<HistoryEntry> {
+    (0..=MAX_ROTATED_FILES)
+      .rev()
+      .filter_map(|index| std::fs::read_to_string(self.file_path(index)).ok())
+      .flat_map(|text| {
+        text
+          .lines()
+          .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+          .collect::<Vec<_>>()
+      })
+      .collect()
+  }
+
+  // Reads the newest files first and stops once the page and one entry past it are found, so the first pages never
+  // touch the rotated files.
+  pub fn page(&self, query: &HistoryQuery) -> HistoryPage {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let wanted = query.offset + limit + 1;
+    let mut matching = Vec::new();
+    for index in 0..=MAX_ROTATED_FILES {
+      if matching.len() >= wanted {
+        break;
+      }
+      let Ok(text) = std::fs::read_to_string(self.file_path(index)) else {
+        continue;
+      };
+      let remaining = wanted - matching.len();
+      matching.extend(
+        text
+          .lines()
+          .rev()
+          .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+          .filter(|entry| query.kind.is_none_or(|kind| entry.kind == kind))
+          .take(remaining),
+      );
+    }
+    // Only a scan that ran out of entries before filling the page has seen every match.
+    let has_more = matching.len() == wanted;
+    let total = (!has_more).then_some(matching.len());
+    let entries = matching.into_iter().skip(query.offset).take(limit).collect::<Vec<_>>();
+    HistoryPage {
+      entries,
+      total,
+      offset: query.offset,
+      has_more,
+    }
+  }
+
+  fn rotate(&self) -> Result<()> {
+    let _ = std::fs::remove_file(self.file_path(MAX_ROTATED_FILES));
+    for index in (0..MAX_ROTATED_FILES).rev() {
+      let from = self.file_path(index);
+      if from.exists() {
+        let to = self.file_path(index + 1);
+        std::fs::rename(&from, &to).with_context(|| format!("failed to rotate {}", from.display()))?;
+      }
+    }
+    Ok(())
+  }
+
+  fn file_path(&self, index: usize) -> PathBuf {
+    match index {
+      0 => self.dir.join(HISTORY_FILE_NAME),
+      index => self.dir.join(format!("history.{index}.jsonl")),
+    }
+  }
+}
+
+fn ends_mid_line(path: &Path) -> bool {
+  let Ok(mut file) = File::open(path) else {
+    return false;
+  };
+  // Seeking before the start fails for an empty file, which has no line to finish.
+  if file.seek(SeekFrom::End(-1)).is_err() {
+    return false;
+  }
+  let mut last = [0u8; 1];
+  file.read_exact(&mut last).is_ok() && last[0] != b'\n'
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::models::HistoryEventKind;
+
+  fn entry(minute: u32, kind: HistoryEventKind) -> HistoryEntry {
+    HistoryEntry {
+      time: format!("2026-01-01T00:{minute:02}:00+00:00"),
+      kind,
+      addresses: vec!["2001:db8::1".to_string()],
+      previous_addresses: Vec::new(),
+      domain: None,
+      latency_ms: None,
+      error_code: None,
+      message: None,
+    }
+  }
+
+  fn temp_store(name: &str) -> (HistoryStore, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("cf-ddns-history-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    (HistoryStore::new(&dir.join("settings.json")), dir)
+  }
+
+  #[test]
+  fn append_terminates_a_torn_last_line() {
+    let (store, dir) = temp_store("torn");
+    store.append(&entry(0, HistoryEventKind::AddressChanged)).unwrap();
+    let mut file = OpenOptions::new().append(true).open(store.file_path(0)).unwrap();
+    file.write_all(br#"{"time":"2026-01-01T00:01:00+00:00","kind":"pu"#).unwrap();
+    drop(file);
+
+    store.append(&entry(2, HistoryEventKind::PushSucceeded)).unwrap();
+    let times = store.entries().into_iter().map(|entry| entry.time).collect::<Vec<_>>();
+    assert_eq!(times, ["2026-01-01T00:00:00+00:00", "2026-01-01T00:02:00+00:00"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn pages_run_newest_first_across_rotated_files() {
+    let (store, dir) = temp_store("page");
+    for minute in 0..6 {
+      let kind = if minute % 2 == 0 {
+        HistoryEventKind::AddressChanged
+      } else {
+        HistoryEventKind::PushSucceeded
+      };
+      store.append(&entry(minute, kind)).unwrap();
+      if minute == 2 {
+        store.rotate().unwrap();
+      }
+    }
+    let minutes = |page: &HistoryPage| {
+      page
+        .entries
+        .iter()
+        .map(|entry| entry.time[14..16].to_string())
+        .collect::<Vec<_>>()
+    };
+
+    let first = store.page(&HistoryQuery {
+      offset: 0,
+      limit: Some(2),
+      kind: None,
+    });
+    assert_eq!(minutes(&first), ["05", "04"]);
+    assert!(first.has_more);
+    assert_eq!(first.total, None);
+
+    let last = store.page(&HistoryQuery {
+      offset: 4,
+      limit: Some(2),
+      kind: None,
+    });
+    assert_eq!(minutes(&last), ["01", "00"]);
+    assert!(!last.has_more);
+    assert_eq!(last.total, Some(6));
+
+    let pushes = store.page(&HistoryQuery {
+      offset: 0,
+      limit: Some(3),
+      kind: Some(HistoryEventKind::PushSucceeded),
+    });
+    assert_eq!(minutes(&pushes), ["05", "03", "01"]);
+    assert!(!pushes.has_more);
+    assert_eq!(pushes.total, Some(3));
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,233 @@
+use netdev::{interface::InterfaceType, Interface};
+
+use crate::models::InterfaceKind;
+
+// Docker's user-defined networks are `br-<first 12 hex digits of the network id>` and matched separately, since
+// `br-` alone also covers OpenWrt's `br-lan`, the LAN bridge that carries a router's global address.
+const CONTAINER_NAME_PREFIXES: &[&str] = &[
+  "docker", "veth", "cni", "flannel", "cali", "weave", "podman", "lxcbr", "lxdbr", "kube",
+];
+const VPN_NAME_PREFIXES: &[&str] = &["wg", "tailscale", "tun", "tap", "utun", "zt", "nordlynx", "ipsec"];
+// 6in4/GRE tunnels, e.g. a tunnel broker's `he-ipv6`; often the host's only global IPv6.
+const TUNNEL_NAME_PREFIXES: &[&str] = &["sit", "he-ipv6", "6in4", "6to4", "ip6tnl", "ip6gre", "gre"];
+const VM_NAME_PREFIXES: &[&str] = &["vmnet", "vboxnet", "virbr", "vnet", "vethernet"];
+// Teredo and IP-HTTPS relay addresses are not a routed prefix the host can be reached on, so they stay excluded.
+const VPN_DESCRIPTION_KEYWORDS: &[&str] = &[
+  "wireguard", "tailscale", "wintun", "tap-windows", "openvpn", "zerotier", "vpn", "teredo", "ip-https",
+];
+const TUNNEL_DESCRIPTION_KEYWORDS: &[&str] = &["6in4", "6to4", "isatap", "tunnel"];
+// Host-side adapters only; guest NICs such as "Microsoft Hyper-V Network Adapter" stay physical.
+const VM_DESCRIPTION_KEYWORDS: &[&str] = &[
+  "hyper-v virtual ethernet",
+  "vmware virtual ethernet",
+  "virtualbox host-only",
+  "parallels host",
+];
+const CONTAINER_DESCRIPTION_KEYWORDS: &[&str] = &["docker", "wsl", "container"];
+
+pub struct InterfaceClassification {
+  pub kind: InterfaceKind,
+  pub driver: Option<String>,
+}
+
+pub fn classify(iface: &Interface) -> InterfaceClassification {
+  let driver = read_driver_name(&iface.name);
+  InterfaceClassification {
+    kind: classify_kind(iface, driver.as_deref()),
+    driver,
+  }
+}
+
+fn classify_kind(iface: &Interface, driver: Option<&str>) -> InterfaceKind {
+  let labels = [iface.friendly_name.as_deref(), iface.description.as_deref(), driver]
+    .into_iter()
+    .flatten()
+    .map(str::to_lowercase)
+    .collect::<Vec<_>>();
+  kind_of(&InterfaceTraits {
+    name: iface.name.to_lowercase(),
+    if_type: &iface.if_type,
+    loopback: iface.is_loopback(),
+    labels,
+    backing_device: has_backing_device(&iface.name),
+  })
+}
+
+// What classification looks at, gathered up front so the rules can be tested without real interfaces.
+struct InterfaceTraits<'a> {
+  // Lowercase OS name.
+  name: String,
+  if_type: &'a InterfaceType,
+  loopback: bool,
+  // Lowercase friendly name, description and driver, whichever are known.
+  labels: Vec<String>,
+  // Whether a bus device backs the interface; `None` where the OS does not say.
+  backing_device: Option<bool>,
+}
+
+fn kind_of(traits: &InterfaceTraits) -> InterfaceKind {
+  if traits.loopback || matches!(traits.if_type, InterfaceType::Loopback) {
+    return InterfaceKind::Loopback;
+  }
+
+  let name = traits.name.as_str();
+  let label_contains = |keywords: &[&str]| {
+    traits
+      .labels
+      .iter()
+      .any(|label| keywords.iter().any(|keyword| label.contains(keyword)))
+  };
+  let name_starts_with = |prefixes: &[&str]| prefixes.iter().any(|prefix| name.starts_with(prefix));
+
+  // Order matters: Docker Desktop and WSL adapters also advertise themselves as Hyper-V adapters.
+  if name_starts_with(CONTAINER_NAME_PREFIXES)
+    || is_docker_bridge(name)
+    || label_contains(CONTAINER_DESCRIPTION_KEYWORDS)
+  {
+    return InterfaceKind::Container;
+  }
+  if name_starts_with(VPN_NAME_PREFIXES) || label_contains(VPN_DESCRIPTION_KEYWORDS) {
+    return InterfaceKind::Vpn;
+  }
+  if matches!(traits.if_type, InterfaceType::Tunnel)
+    || name_starts_with(TUNNEL_NAME_PREFIXES)
+    || label_contains(TUNNEL_DESCRIPTION_KEYWORDS)
+  {
+    return InterfaceKind::Tunnel;
+  }
+  if name_starts_with(VM_NAME_PREFIXES) || label_contains(VM_DESCRIPTION_KEYWORDS) {
+    return InterfaceKind::VirtualMachine;
+  }
+
+  match traits.backing_device {
+    Some(true) => InterfaceKind::Physical,
+    // Bridges, bonds, VLANs and PPP links without a bus device; kept selectable by default.
+    Some(false) => InterfaceKind::Virtual,
+    None => match traits.if_type {
+      InterfaceType::Ethernet
+      | InterfaceType::GigabitEthernet
+      | InterfaceType::FastEthernetT
+      | InterfaceType::FastEthernetFx
+      | InterfaceType::Wireless80211
+      | InterfaceType::Ppp => InterfaceKind::Physical,
+      _ => InterfaceKind::Unknown,
+    },
+  }
+}
+
+fn is_docker_bridge(name: &str) -> bool {
+  name
+    .strip_prefix("br-")
+    .is_some_and(|id| id.len() == 12 && id.chars().all(|ch| ch.is_ascii_hexdigit()))
+}
+
+#[cfg(target_os = "linux")]
+fn has_backing_device(name: &str) -> Option<bool> {
+  // Linux sysfs: only interfaces bound to a bus device expose `/sys/class/net/<name>/device`.
+  let path = std::path::Path::new("/sys/class/net").join(name);
+  if !path.exists() {
+    return None;
+  }
+  Some(path.join("device").exists())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn has_backing_device(_name: &str) -> Option<bool> {
+  None
+}
+
+#[cfg(target_os = "linux")]
+fn read_driver_name(name: &str) -> Option<String> {
+  let driver_link = std::path::Path::new("/sys/class/net")
+    .join(name)
+    .join("device")
+    .join("driver");
+  let target = std::fs::read_link(driver_link).ok()?;
+  target
+    .file_name()
+    .map(|value| value.to_string_lossy().into_owned())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_driver_name(_name: &str) -> Option<String> {
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Name, type, labels, backing device and the expected kind.
+  type Case = (&'static str, InterfaceType, &'static [&'static str], Option<bool>, InterfaceKind);
+
+  fn kind(name: &str, if_type: InterfaceType, labels: &[&str], backing_device: Option<bool>) -> InterfaceKind {
+    kind_of(&InterfaceTraits {
+      name: name.to_lowercase(),
+      if_type: &if_type,
+      loopback: false,
+      labels: labels.iter().map(|label| label.to_lowercase()).collect(),
+      backing_device,
+    })
+  }
+
+  #[test]
+  fn docker_bridges_are_told_apart_from_other_bridges() {
+    assert!(is_docker_bridge("br-3f2a9c1d7e04"));
+    assert!(!is_docker_bridge("br-lan"));
+    assert!(!is_docker_bridge("br-wan"));
+    assert!(!is_docker_bridge("br-3f2a9c1d7e0"));
+    assert!(!is_docker_bridge("br-3f2a9c1d7e04x"));
+    assert!(!is_docker_bridge("br0"));
+  }
+
+  #[test]
+  fn loopback_wins_over_every_name_rule() {
+    let traits = InterfaceTraits {
+      name: "lo".to_string(),
+      if_type: &InterfaceType::Ethernet,
+      loopback: true,
+      labels: Vec::new(),
+      backing_device: None,
+    };
+    assert_eq!(kind_of(&traits), InterfaceKind::Loopback);
+    assert_eq!(kind("lo0", InterfaceType::Loopback, &[], None), InterfaceKind::Loopback);
+  }
+
+  #[test]
+  fn interfaces_are_classified_by_name_label_and_type() {
+    use InterfaceType::{Ethernet, Ppp, Tunnel, Unknown, Wireless80211};
+    let cases: &[Case] = &[
+      // Containers, including Docker's per-network bridges.
+      ("docker0", Unknown, &[], Some(false), InterfaceKind::Container),
+      ("veth1a2b3c", Unknown, &[], Some(false), InterfaceKind::Container),
+      ("br-3f2a9c1d7e04", Unknown, &[], Some(false), InterfaceKind::Container),
+      ("eth1", Ethernet, &["vEthernet (WSL)", "Hyper-V Virtual Ethernet Adapter"], None, InterfaceKind::Container),
+      // VPNs by name and by adapter description.
+      ("wg0", Unknown, &[], Some(false), InterfaceKind::Vpn),
+      ("tailscale0", Unknown, &[], Some(false), InterfaceKind::Vpn),
+      ("utun3", Tunnel, &[], None, InterfaceKind::Vpn),
+      ("Ethernet 3", Ethernet, &["OpenVPN Data Channel Offload"], None, InterfaceKind::Vpn),
+      ("{8a4e6b1c-2d3f}", Tunnel, &["Teredo Tunneling Pseudo-Interface"], None, InterfaceKind::Vpn),
+      // Point-to-point IPv6 tunnels are their own, selectable kind.
+      ("he-ipv6", Unknown, &[], Some(false), InterfaceKind::Tunnel),
+      ("sit1", Unknown, &[], Some(false), InterfaceKind::Tunnel),
+      ("6in4-wan6", Unknown, &[], Some(false), InterfaceKind::Tunnel),
+      ("gif0", Tunnel, &[], None, InterfaceKind::Tunnel),
+      ("isatap.{4F2B}", Unknown, &["Microsoft ISATAP Adapter"], None, InterfaceKind::Tunnel),
+      // Host-side VM adapters.
+      ("virbr0", Unknown, &[], Some(false), InterfaceKind::VirtualMachine),
+      ("Ethernet 2", Ethernet, &["VirtualBox Host-Only Ethernet Adapter"], None, InterfaceKind::VirtualMachine),
+      // Bridges other than Docker's and physical NICs.
+      ("br-lan", Unknown, &[], Some(false), InterfaceKind::Virtual),
+      ("br0", Unknown, &[], Some(false), InterfaceKind::Virtual),
+      ("eth0", Ethernet, &["e1000e"], Some(true), InterfaceKind::Physical),
+      ("en0", Wireless80211, &[], None, InterfaceKind::Physical),
+      ("pppoe-wan", Ppp, &[], None, InterfaceKind::Physical),
+      ("Ethernet", Ethernet, &["Microsoft Hyper-V Network Adapter"], None, InterfaceKind::Physical),
+      ("mystery0", Unknown, &[], None, InterfaceKind::Unknown),
+    ];
+    for (name, if_type, labels, backing_device, expected) in cases {
+      assert_eq!(kind(name, *if_type, labels, *backing_device), *expected, "{name}");
+    }
+  }
+}
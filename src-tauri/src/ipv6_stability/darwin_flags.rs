@@ -0,0 +1,84 @@
+use super::StabilityRank;
+
+// XNU `netinet6/in6_var.h` address flags returned by SIOCGIFAFLAG_IN6.
+pub(super) const IN6_IFF_TENTATIVE: u32 = 0x0002;
+pub(super) const IN6_IFF_DUPLICATED: u32 = 0x0004;
+pub(super) const IN6_IFF_DETACHED: u32 = 0x0008;
+pub(super) const IN6_IFF_DEPRECATED: u32 = 0x0010;
+pub(super) const IN6_IFF_AUTOCONF: u32 = 0x0040;
+pub(super) const IN6_IFF_TEMPORARY: u32 = 0x0080;
+pub(super) const IN6_IFF_DYNAMIC: u32 = 0x0100;
+pub(super) const IN6_IFF_SECURED: u32 = 0x0400;
+
+// Kept free of FFI so recorded flag fixtures can be classified on any host.
+pub(super) fn classify_darwin_rank(flags: u32, prefix_len: u8) -> StabilityRank {
+  if flags & IN6_IFF_TEMPORARY != 0 {
+    return StabilityRank::Temporary;
+  }
+  if flags & (IN6_IFF_TENTATIVE | IN6_IFF_DUPLICATED | IN6_IFF_DETACHED | IN6_IFF_DEPRECATED) != 0 {
+    return StabilityRank::Fallback;
+  }
+  // SECURED marks RFC 7217 stable-privacy SLAAC addresses (default since macOS 10.12).
+  if flags & IN6_IFF_SECURED != 0 {
+    return StabilityRank::PreferredStable;
+  }
+  if flags & IN6_IFF_DYNAMIC != 0 {
+    return StabilityRank::Stable;
+  }
+  if flags & IN6_IFF_AUTOCONF == 0 {
+    // Neither SLAAC nor DHCPv6: manually configured, same as Linux IFA_F_PERMANENT.
+    return StabilityRank::PreferredStable;
+  }
+  if prefix_len == 64 {
+    return StabilityRank::Stable;
+  }
+  StabilityRank::Fallback
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Flag words recorded with SIOCGIFAFLAG_IN6 on macOS, alongside the `ifconfig` keywords for the same address.
+  #[test]
+  fn classifies_recorded_flag_sets() {
+    let cases = [
+      // `autoconf secured`: RFC 7217 stable-privacy SLAAC address.
+      (0x0440, 64, StabilityRank::PreferredStable),
+      // `autoconf temporary`: RFC 8981 privacy address.
+      (0x00c0, 64, StabilityRank::Temporary),
+      // `deprecated autoconf temporary`: an expired privacy address is still temporary.
+      (0x00d0, 64, StabilityRank::Temporary),
+      // `deprecated autoconf secured`: the prefix is being withdrawn.
+      (0x0450, 64, StabilityRank::Fallback),
+      // `tentative autoconf secured`: DAD has not finished.
+      (0x0442, 64, StabilityRank::Fallback),
+      // `duplicated autoconf secured`
+      (0x0444, 64, StabilityRank::Fallback),
+      // `detached autoconf secured`: the router went away.
+      (0x0448, 64, StabilityRank::Fallback),
+      // `dynamic`: DHCPv6 lease, reported as /128.
+      (0x0100, 128, StabilityRank::Stable),
+      // `autoconf`: EUI-64 SLAAC without stable privacy, on-link /64.
+      (0x0040, 64, StabilityRank::Stable),
+      // `autoconf` on a /128 is not a regular SLAAC prefix.
+      (0x0040, 128, StabilityRank::Fallback),
+      // No flags: configured by hand, either as /64 or as a single /128.
+      (0x0000, 64, StabilityRank::PreferredStable),
+      (0x0000, 128, StabilityRank::PreferredStable),
+    ];
+    for (flags, prefix_len, expected) in cases {
+      assert_eq!(
+        classify_darwin_rank(flags, prefix_len),
+        expected,
+        "flags {flags:#06x} /{prefix_len}"
+      );
+    }
+  }
+
+  #[test]
+  fn temporary_wins_over_other_flags() {
+    let flags = IN6_IFF_AUTOCONF | IN6_IFF_TEMPORARY | IN6_IFF_SECURED | IN6_IFF_DYNAMIC;
+    assert_eq!(classify_darwin_rank(flags, 64), StabilityRank::Temporary);
+  }
+}
@@ -1,10 +1,92 @@
 use std::collections::HashMap;
+use std::ffi::CStr;
 use std::net::Ipv6Addr;
 
-use super::StabilityRank;
+use super::darwin_flags::classify_darwin_rank;
+use super::{StabilityIndex, StabilityRank};
+
+// `_IOWR('i', 73, struct in6_ifreq)` from XNU `netinet6/in6_var.h`; `struct in6_ifreq` is 288 bytes.
+const SIOCGIFAFLAG_IN6: libc::c_ulong = 0xc120_6949;
+const IN6_IFREQ_UNION_SIZE: usize = 288 - libc::IFNAMSIZ;
+
+#[repr(C)]
+union In6IfreqUnion {
+  addr: libc::sockaddr_in6,
+  flags6: libc::c_int,
+  // Pads the union to the kernel layout, which also holds address lifetimes and ICMPv6 stats.
+  _raw: [u64; IN6_IFREQ_UNION_SIZE / 8],
+}
+
+#[repr(C)]
+struct In6Ifreq {
+  ifr_name: [libc::c_char; libc::IFNAMSIZ],
+  ifr_ifru: In6IfreqUnion,
+}
 
 pub(super) fn collect_stability_ranks() -> HashMap<(String, Ipv6Addr), StabilityRank> {
-  // macOS does not expose Windows-like PrefixOrigin/SuffixOrigin metadata via getifaddrs.
-  // We return an empty map and rely on the shared /64 fallback heuristic in `mod.rs`.
-  HashMap::new()
+  let mut ranks = HashMap::new();
+  let socket = unsafe { libc::socket(libc::AF_INET6, libc::SOCK_DGRAM, 0) };
+  if socket < 0 {
+    return ranks;
+  }
+
+  let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+  // getifaddrs(3) enumerates per-address entries; per-address IN6_IFF_* flags need SIOCGIFAFLAG_IN6.
+  if unsafe { libc::getifaddrs(&mut head) } != 0 {
+    unsafe { libc::close(socket) };
+    return ranks;
+  }
+
+  let mut cursor = head;
+  while let Some(entry) = unsafe { cursor.as_ref() } {
+    cursor = entry.ifa_next;
+    let Some(sockaddr) = (unsafe { as_sockaddr_in6(entry.ifa_addr) }) else {
+      continue;
+    };
+    let addr = Ipv6Addr::from(sockaddr.sin6_addr.s6_addr);
+    let prefix_len = unsafe { as_sockaddr_in6(entry.ifa_netmask) }
+      .map(|mask| mask.sin6_addr.s6_addr.iter().map(|byte| byte.count_ones() as u8).sum())
+      .unwrap_or(128);
+    let Some(flags) = (unsafe { query_address_flags(socket, entry.ifa_name, sockaddr) }) else {
+      continue;
+    };
+    let iface_name = unsafe { CStr::from_ptr(entry.ifa_name) }
+      .to_string_lossy()
+      .into_owned();
+    StabilityIndex::upsert_rank(&mut ranks, iface_name, addr, classify_darwin_rank(flags, prefix_len));
+  }
+
+  unsafe {
+    libc::freeifaddrs(head);
+    libc::close(socket);
+  }
+  ranks
+}
+
+unsafe fn as_sockaddr_in6<'a>(addr: *const libc::sockaddr) -> Option<&'a libc::sockaddr_in6> {
+  let sockaddr = addr.as_ref()?;
+  if sockaddr.sa_family as libc::c_int != libc::AF_INET6 {
+    return None;
+  }
+  addr.cast::<libc::sockaddr_in6>().as_ref()
+}
+
+unsafe fn query_address_flags(
+  socket: libc::c_int,
+  ifa_name: *const libc::c_char,
+  sockaddr: &libc::sockaddr_in6,
+) -> Option<u32> {
+  let mut request: In6Ifreq = std::mem::zeroed();
+  let name = CStr::from_ptr(ifa_name).to_bytes();
+  if name.len() >= libc::IFNAMSIZ {
+    return None;
+  }
+  for (slot, byte) in request.ifr_name.iter_mut().zip(name) {
+    *slot = *byte as libc::c_char;
+  }
+  request.ifr_ifru.addr = *sockaddr;
+  if libc::ioctl(socket, SIOCGIFAFLAG_IN6, &mut request as *mut In6Ifreq) < 0 {
+    return None;
+  }
+  Some(request.ifr_ifru.flags6 as u32)
 }
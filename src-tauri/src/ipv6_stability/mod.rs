@@ -9,6 +9,10 @@ mod windows;
 mod linux;
 #[cfg(any(target_os = "macos", target_os = "ios"))]
 mod macos;
+// Pure flag classification; also built for tests so recorded macOS fixtures can run on any host.
+#[cfg(any(target_os = "macos", target_os = "ios", test))]
+#[cfg_attr(not(any(target_os = "macos", target_os = "ios")), allow(dead_code))]
+mod darwin_flags;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub(crate) enum StabilityRank {
@@ -27,21 +31,21 @@ impl StabilityIndex {
   pub(crate) fn collect() -> Self {
     #[cfg(target_os = "windows")]
     {
-      return Self {
+      Self {
         ranks: windows::collect_stability_ranks(),
-      };
+      }
     }
     #[cfg(target_os = "linux")]
     {
-      return Self {
+      Self {
         ranks: linux::collect_stability_ranks(),
-      };
+      }
     }
     #[cfg(any(target_os = "macos", target_os = "ios"))]
     {
-      return Self {
+      Self {
         ranks: macos::collect_stability_ranks(),
-      };
+      }
     }
     #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos", target_os = "ios")))]
     {
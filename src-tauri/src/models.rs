@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -25,6 +27,20 @@ fn default_local_homepage_port() -> u16 {
   8089
 }
 
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordMode {
+  // One AAAA record (`record_id`) always holds the best address.
+  #[default]
+  Single,
+  // The AAAA RRset under `domain` is reconciled to the top `max_records` eligible addresses.
+  MultiValue,
+}
+
+fn default_max_records() -> u32 {
+  2
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CloudflareSettings {
@@ -34,6 +50,23 @@ pub struct CloudflareSettings {
   #[serde(default)]
   pub record_id: String,
   pub ttl: Option<u32>,
+  #[serde(default)]
+  pub record_mode: RecordMode,
+  #[serde(default = "default_max_records")]
+  pub max_records: u32,
+  // Named credential used for this record; `None` uses the default token and token sources.
+  #[serde(default)]
+  pub credential_id: Option<String>,
+}
+
+impl CloudflareSettings {
+  // Number of addresses to publish for the current record mode.
+  pub fn publish_limit(&self) -> usize {
+    match self.record_mode {
+      RecordMode::Single => 1,
+      RecordMode::MultiValue => self.max_records.max(1) as usize,
+    }
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,10 +98,197 @@ impl Default for LocalHomepageSettings {
   }
 }
 
+fn default_settle_seconds() -> u64 {
+  10
+}
+
+fn default_min_push_interval_seconds() -> u64 {
+  30
+}
+
+fn default_poll_interval_seconds() -> u64 {
+  300
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPolicySettings {
+  // A changed address must stay observed for this long before it is committed and pushed.
+  #[serde(default = "default_settle_seconds")]
+  pub settle_seconds: u64,
+  // Automatic pushes closer together than this are deferred until the window elapses.
+  #[serde(default = "default_min_push_interval_seconds")]
+  pub min_push_interval_seconds: u64,
+  // Safety-net detection interval for missed OS network events. Zero disables polling.
+  #[serde(default = "default_poll_interval_seconds")]
+  pub poll_interval_seconds: u64,
+  // Forces a re-push of the unchanged address after this many hours. Zero disables the heartbeat.
+  #[serde(default)]
+  pub heartbeat_interval_hours: u64,
+}
+
+impl SyncPolicySettings {
+  pub fn settle_window(&self) -> chrono::Duration {
+    seconds_to_duration(self.settle_seconds)
+  }
+
+  pub fn min_push_interval(&self) -> chrono::Duration {
+    seconds_to_duration(self.min_push_interval_seconds)
+  }
+
+  pub fn poll_interval(&self) -> Option<std::time::Duration> {
+    (self.poll_interval_seconds > 0).then(|| std::time::Duration::from_secs(self.poll_interval_seconds))
+  }
+
+  pub fn heartbeat_interval(&self) -> Option<chrono::Duration> {
+    (self.heartbeat_interval_hours > 0).then(|| seconds_to_duration(self.heartbeat_interval_hours.saturating_mul(3600)))
+  }
+}
+
+fn seconds_to_duration(seconds: u64) -> chrono::Duration {
+  i64::try_from(seconds)
+    .ok()
+    .and_then(chrono::Duration::try_seconds)
+    .unwrap_or(chrono::Duration::MAX)
+}
+
+impl Default for SyncPolicySettings {
+  fn default() -> Self {
+    Self {
+      settle_seconds: default_settle_seconds(),
+      min_push_interval_seconds: default_min_push_interval_seconds(),
+      poll_interval_seconds: default_poll_interval_seconds(),
+      heartbeat_interval_hours: 0,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum InterfaceSelector {
+  // Exact OS interface name (adapter GUID on Windows).
+  Name(String),
+  // MAC address in any common notation (`aa:bb:..`, `AA-BB-..`).
+  Mac(String),
+  // Case-insensitive `*`/`?` pattern matched against the name and friendly name.
+  Glob(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InterfaceKind {
+  Physical,
+  Virtual,
+  Loopback,
+  Vpn,
+  // 6in4, GRE and similar point-to-point IPv6 tunnels; selectable by default.
+  Tunnel,
+  Container,
+  VirtualMachine,
+  Unknown,
+}
+
+fn default_excluded_interface_kinds() -> Vec<InterfaceKind> {
+  vec![
+    InterfaceKind::Loopback,
+    InterfaceKind::Vpn,
+    InterfaceKind::Container,
+    InterfaceKind::VirtualMachine,
+  ]
+}
+
+// Only applies when no interface is explicitly selected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceFilterSettings {
+  #[serde(default = "default_excluded_interface_kinds")]
+  pub excluded_kinds: Vec<InterfaceKind>,
+  // Glob patterns that are always considered, even when their kind is excluded.
+  #[serde(default)]
+  pub always_include: Vec<String>,
+  // Glob patterns that are never considered.
+  #[serde(default)]
+  pub always_exclude: Vec<String>,
+}
+
+impl Default for InterfaceFilterSettings {
+  fn default() -> Self {
+    Self {
+      excluded_kinds: default_excluded_interface_kinds(),
+      always_include: Vec::new(),
+      always_exclude: Vec::new(),
+    }
+  }
+}
+
+fn default_token_warn_days() -> u32 {
+  14
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenExpirySettings {
+  // Warn this many days before the API token expires. Zero only reports tokens that already expired.
+  #[serde(default = "default_token_warn_days")]
+  pub warn_days: u32,
+  // Show a desktop notification in addition to the snapshot and tray warning.
+  #[serde(default = "default_true")]
+  pub notify: bool,
+}
+
+impl Default for TokenExpirySettings {
+  fn default() -> Self {
+    Self {
+      warn_days: default_token_warn_days(),
+      notify: true,
+    }
+  }
+}
+
+fn default_true() -> bool {
+  true
+}
+
+fn default_probe_endpoint() -> String {
+  "[2606:4700:4700::1111]:443".to_string()
+}
+
+fn default_probe_timeout_ms() -> u64 {
+  3000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachabilitySettings {
+  #[serde(default)]
+  pub enabled: bool,
+  // `host:port` or `[ipv6]:port`; only IPv6 resolutions are probed.
+  #[serde(default = "default_probe_endpoint")]
+  pub probe_endpoint: String,
+  #[serde(default = "default_probe_timeout_ms")]
+  pub timeout_ms: u64,
+}
+
+impl Default for ReachabilitySettings {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      probe_endpoint: default_probe_endpoint(),
+      timeout_ms: default_probe_timeout_ms(),
+    }
+  }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettings {
   pub selected_interface: Option<String>,
+  #[serde(default)]
+  pub interface_candidates: Vec<InterfaceSelector>,
+  #[serde(default)]
+  pub interface_filter: InterfaceFilterSettings,
+  #[serde(default)]
+  pub reachability: ReachabilitySettings,
   pub auto_push: bool,
   pub launch_on_startup: bool,
   pub lightweight_mode: bool,
@@ -79,12 +299,22 @@ pub struct AppSettings {
   pub cloudflare: CloudflareSettings,
   #[serde(default)]
   pub local_homepage: LocalHomepageSettings,
+  #[serde(default)]
+  pub sync_policy: SyncPolicySettings,
+  // Tried in order before the keyring; the keyring (or encrypted file) is always the last resort.
+  #[serde(default)]
+  pub token_sources: Vec<TokenSource>,
+  #[serde(default)]
+  pub token_expiry: TokenExpirySettings,
 }
 
 impl Default for AppSettings {
   fn default() -> Self {
     Self {
       selected_interface: None,
+      interface_candidates: Vec::new(),
+      interface_filter: InterfaceFilterSettings::default(),
+      reachability: ReachabilitySettings::default(),
       auto_push: true,
       launch_on_startup: false,
       lightweight_mode: false,
@@ -96,8 +326,14 @@ impl Default for AppSettings {
         domain: String::new(),
         record_id: String::new(),
         ttl: None,
+        record_mode: RecordMode::Single,
+        max_records: default_max_records(),
+        credential_id: None,
       },
       local_homepage: LocalHomepageSettings::default(),
+      sync_policy: SyncPolicySettings::default(),
+      token_sources: Vec::new(),
+      token_expiry: TokenExpirySettings::default(),
     }
   }
 }
@@ -130,18 +366,68 @@ impl Default for SyncStatus {
 #[serde(rename_all = "camelCase")]
 pub struct RuntimeCache {
   pub last_known_ipv6: Option<String>,
+  // Full committed address set in rank order; `last_known_ipv6` mirrors its first entry.
+  #[serde(default)]
+  pub last_known_ipv6_set: Vec<String>,
   pub last_ipv6_change_time: Option<String>,
+  // Last push attempt, successful or not.
   pub last_sync_time: Option<String>,
   pub last_sync_status: SyncStatus,
+  // Last successful push; the heartbeat counts from here so failed pushes do not postpone it.
+  #[serde(default)]
+  pub last_success_time: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+impl RuntimeCache {
+  pub fn committed_addresses(&self) -> Vec<String> {
+    // Caches written before multi-value support only carry `last_known_ipv6`.
+    if self.last_known_ipv6_set.is_empty() {
+      return self.last_known_ipv6.iter().cloned().collect();
+    }
+    self.last_known_ipv6_set.clone()
+  }
+
+  // Caches written before `last_success_time` existed only know the last attempt, which counts if it succeeded.
+  pub fn last_success(&self) -> Option<&str> {
+    match (&self.last_success_time, &self.last_sync_status.kind) {
+      (Some(time), _) => Some(time),
+      (None, SyncStatusKind::Success) => self.last_sync_time.as_deref(),
+      (None, _) => None,
+    }
+  }
+
+  pub fn commit_addresses(&mut self, addresses: &[String], time: String) {
+    self.last_known_ipv6 = addresses.first().cloned();
+    self.last_known_ipv6_set = addresses.to_vec();
+    self.last_ipv6_change_time = Some(time);
+  }
+}
+
+// Bump together with a new migration step in `config.rs` whenever the stored shape changes.
+pub const CONFIG_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfig {
+  // Files written before versioning have no `version` and are treated as version 0.
+  #[serde(default)]
+  pub version: u32,
   pub settings: AppSettings,
+  // Stored in runtime_state.json; only read here to carry over caches from version 1 and older files.
+  #[serde(default, skip_serializing)]
   pub cache: RuntimeCache,
 }
 
+impl Default for AppConfig {
+  fn default() -> Self {
+    Self {
+      version: CONFIG_VERSION,
+      settings: AppSettings::default(),
+      cache: RuntimeCache::default(),
+    }
+  }
+}
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct InterfaceInfo {
@@ -150,6 +436,17 @@ pub struct InterfaceInfo {
   pub mac_address: Option<String>,
   pub ipv6_addresses: Vec<String>,
   pub link_speed_mbps: Option<u64>,
+  pub kind: InterfaceKind,
+  pub driver: Option<String>,
+  // True when automatic selection skips this interface because of `InterfaceFilterSettings`.
+  pub excluded: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceSelectionStatus {
+  pub resolved_interface: Option<String>,
+  pub warning: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq)]
@@ -161,6 +458,294 @@ pub struct IpGeoInfo {
   pub country_iso_code: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachabilityResult {
+  pub address: String,
+  pub reachable: bool,
+  pub latency_ms: Option<u64>,
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReachabilityReport {
+  pub probe_endpoint: String,
+  pub checked_time: String,
+  // Candidates in probe order; probing stops once enough reachable addresses were found.
+  pub results: Vec<ReachabilityResult>,
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixRecord {
+  // Network prefix of the published address, e.g. `2001:db8:1:2::/64`.
+  pub prefix: String,
+  // Estimated delegated prefix length: the on-link length, capped at /64 because a DHCPv6 /128 says nothing about
+  // the delegation. A /56 delegation split into /64 subnets is therefore recorded as /64.
+  pub prefix_len: u8,
+  pub first_seen_time: String,
+  pub last_seen_time: String,
+  // Set when a different prefix replaced this one; `None` for the active prefix.
+  pub ended_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryEventKind {
+  // The committed (settled) address set changed.
+  AddressChanged,
+  PushSucceeded,
+  PushFailed,
+}
+
+// One line of `history.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+  pub time: String,
+  pub kind: HistoryEventKind,
+  // The new address set for changes, the pushed set for push attempts.
+  pub addresses: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub previous_addresses: Vec<String>,
+  // Record name a push targeted.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub domain: Option<String>,
+  // Wall time of the push, including record lookups and retries.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub latency_ms: Option<u64>,
+  // Failure category: `http401`, `http404`, ... for Cloudflare responses, `network`, `notConfigured`, `noToken`, `other`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error_code: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+  // Entries to skip, counted from the newest.
+  #[serde(default)]
+  pub offset: usize,
+  pub limit: Option<usize>,
+  pub kind: Option<HistoryEventKind>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+  // Newest first.
+  pub entries: Vec<HistoryEntry>,
+  // Matching entries across all retained files; `None` when the page was served without reading every file.
+  pub total: Option<usize>,
+  pub offset: usize,
+  pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryStats {
+  pub generated_time: String,
+  // Time of the oldest retained history entry; statistics cover this point up to `generatedTime`.
+  pub period_start: Option<String>,
+  pub prefix_lifetimes: LifetimeDistribution,
+  pub pushes_per_day: Vec<DailyPushCount>,
+  pub push_attempts: usize,
+  pub push_failures: usize,
+  // `pushFailures / pushAttempts`, absent before the first push.
+  pub failure_rate: Option<f64>,
+  pub failures_by_code: BTreeMap<String, usize>,
+  pub mean_push_latency_ms: Option<f64>,
+  // Mean delay from an address change to the successful push publishing it.
+  pub mean_time_to_update_seconds: Option<f64>,
+  pub updates_measured: usize,
+  pub address_uptime: Vec<AddressUptime>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifetimeDistribution {
+  // Prefixes that have been replaced; the active one is reported as `currentAgeSeconds`.
+  pub completed: usize,
+  pub min_seconds: Option<i64>,
+  pub median_seconds: Option<i64>,
+  pub mean_seconds: Option<f64>,
+  pub max_seconds: Option<i64>,
+  pub current_age_seconds: Option<i64>,
+  pub buckets: Vec<LifetimeBucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifetimeBucket {
+  pub label: String,
+  // Exclusive upper bound; absent for the last bucket.
+  pub up_to_seconds: Option<i64>,
+  pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyPushCount {
+  // UTC date, `YYYY-MM-DD`.
+  pub date: String,
+  pub succeeded: usize,
+  pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressUptime {
+  pub address: String,
+  pub first_seen_time: String,
+  // When the address last left the committed set; absent while it is still published.
+  pub last_seen_time: Option<String>,
+  // Total time spent in the committed set, across every stint.
+  pub uptime_seconds: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryExportFormat {
+  // One row per history entry.
+  #[default]
+  Csv,
+  // Statistics, prefix records and history entries in one document.
+  Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportHistoryRequest {
+  // Where to write the export; without a path the rendered document is returned instead.
+  #[serde(default)]
+  pub path: Option<String>,
+  #[serde(default)]
+  pub format: HistoryExportFormat,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixChangedEvent {
+  pub previous_prefix: String,
+  pub prefix: String,
+  pub prefix_len: u8,
+  pub changed_time: String,
+  // How long the previous prefix was observed.
+  pub lifetime_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TokenSource {
+  // Environment variable holding the token.
+  Env { variable: String },
+  // File whose first line is the token; `${NAME}` expands environment variables, e.g. `${CREDENTIALS_DIRECTORY}`.
+  File { path: String },
+  // Credential helper (`pass`, `op`, `bw`, ...) that prints the token on stdout.
+  Command {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+  },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenBackendKind {
+  // OS credential store (Windows Credential Manager, macOS Keychain, Secret Service).
+  Keyring,
+  // `api_token.enc` in the config dir, used when no OS keyring is reachable. Encrypted only when
+  // `CF_DDNS_TOKEN_PASSPHRASE` is set; the machine-id key used otherwise on Linux is obfuscation.
+  EncryptedFile,
+}
+
+// Index entry for a named credential; the token itself only lives in the keyring or encrypted file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialSummary {
+  pub id: String,
+  pub name: String,
+  pub created_time: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveCredentialRequest {
+  // Replaces an existing credential when set; otherwise an ID is derived from the name.
+  pub id: Option<String>,
+  pub name: String,
+  pub token: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenHealth {
+  Active,
+  // Active, but expires within `tokenExpiry.warnDays`.
+  ExpiringSoon,
+  Expired,
+  // Disabled in the Cloudflare dashboard, or not valid before `notBefore` yet.
+  Disabled,
+  // Cloudflare rejected the token outright (revoked, deleted or mistyped).
+  Invalid,
+  // The verify request itself failed, e.g. no network; the token may still be fine.
+  Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenStatus {
+  pub health: TokenHealth,
+  pub expires_on: Option<String>,
+  pub not_before: Option<String>,
+  // Days left until `expiresOn`, rounded up (23 hours left is 1); zero or negative once expired.
+  pub days_remaining: Option<i64>,
+  pub message: Option<String>,
+  pub checked_time: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateTokenRequest {
+  pub token: String,
+  // False only checks the new token; true also replaces the stored one when the checks pass.
+  pub apply: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRotationReport {
+  // Status of the new token as reported by Cloudflare.
+  pub status: TokenStatus,
+  // True when the new token could list the AAAA records of the configured zone and domain.
+  pub zone_access_verified: bool,
+  pub applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRecovery {
+  // Backup file the settings were restored from.
+  pub recovered_from: String,
+  // Why the main config file could not be loaded.
+  pub reason: String,
+  // Where the unreadable file was moved, if that succeeded.
+  pub corrupt_copy: Option<String>,
+  pub recovered_time: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReloadStatus {
+  // False when an external edit was rejected and the previous settings stayed active.
+  pub applied: bool,
+  pub message: String,
+  pub time: String,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ServiceRuntimeModel {
@@ -184,6 +769,24 @@ pub struct LocalHomepageRuntime {
   pub services: Vec<ServiceRuntimeModel>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PendingPushReason {
+  Settling,
+  RateLimited,
+  Heartbeat,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingPushInfo {
+  pub ipv6: Option<String>,
+  pub addresses: Vec<String>,
+  pub reason: PendingPushReason,
+  pub first_seen_time: String,
+  pub due_time: String,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSnapshot {
@@ -191,9 +794,26 @@ pub struct AppSnapshot {
   pub settings: AppSettings,
   pub cache: RuntimeCache,
   pub current_ipv6: Option<String>,
+  // Addresses that would be published right now (one entry in single-record mode).
+  pub current_ipv6_set: Vec<String>,
   pub current_ipv6_geo: Option<IpGeoInfo>,
+  pub current_prefix: Option<PrefixRecord>,
+  pub reachability: Option<ReachabilityReport>,
+  pub pending_push: Option<PendingPushInfo>,
   pub interfaces: Vec<InterfaceInfo>,
+  pub interface_selection: InterfaceSelectionStatus,
   pub has_token: bool,
+  pub token_backend: TokenBackendKind,
+  // Which source supplied the token (`env:NAME`, `file:PATH`, `command:PROGRAM`, `keyring`, `encryptedFile`).
+  pub token_source: Option<String>,
+  // Named credentials, names only.
+  pub credentials: Vec<CredentialSummary>,
+  // Result of the last token verification against Cloudflare; `None` until checked or when no token is set.
+  pub token_status: Option<TokenStatus>,
+  // Set when settings.json was corrupt at startup and a backup was loaded instead.
+  pub config_recovery: Option<ConfigRecovery>,
+  // Result of the last reload triggered by an external edit of settings.json.
+  pub config_reload: Option<ConfigReloadStatus>,
   pub linux_theme_hint: Option<ThemeMode>,
   pub local_homepage: LocalHomepageRuntime,
 }
@@ -206,9 +826,139 @@ pub struct SaveSettingsRequest {
   pub clear_token: bool,
 }
 
+// Stable identifiers the frontend maps to localized messages; keep in sync with `src/i18n.ts`.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ValidationCode {
+  DomainInvalid,
+  ZoneIdInvalid,
+  RecordIdInvalid,
+  TtlOutOfRange,
+  MaxRecordsOutOfRange,
+  SettleOutOfRange,
+  MinPushIntervalOutOfRange,
+  PollIntervalOutOfRange,
+  HeartbeatIntervalOutOfRange,
+  InterfaceSelectorEmpty,
+  TokenSourceEmpty,
+  CredentialUnknown,
+  ServiceIdRequired,
+  ServiceIdDuplicate,
+  ServicePortInvalid,
+  ServicePortDuplicate,
+  ServicePortConflictsWithHomepage,
+}
+
+impl ValidationCode {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Self::DomainInvalid => "domainInvalid",
+      Self::ZoneIdInvalid => "zoneIdInvalid",
+      Self::RecordIdInvalid => "recordIdInvalid",
+      Self::TtlOutOfRange => "ttlOutOfRange",
+      Self::MaxRecordsOutOfRange => "maxRecordsOutOfRange",
+      Self::SettleOutOfRange => "settleOutOfRange",
+      Self::MinPushIntervalOutOfRange => "minPushIntervalOutOfRange",
+      Self::PollIntervalOutOfRange => "pollIntervalOutOfRange",
+      Self::HeartbeatIntervalOutOfRange => "heartbeatIntervalOutOfRange",
+      Self::InterfaceSelectorEmpty => "interfaceSelectorEmpty",
+      Self::TokenSourceEmpty => "tokenSourceEmpty",
+      Self::CredentialUnknown => "credentialUnknown",
+      Self::ServiceIdRequired => "serviceIdRequired",
+      Self::ServiceIdDuplicate => "serviceIdDuplicate",
+      Self::ServicePortInvalid => "servicePortInvalid",
+      Self::ServicePortDuplicate => "servicePortDuplicate",
+      Self::ServicePortConflictsWithHomepage => "servicePortConflictsWithHomepage",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+  // Dotted camelCase settings path, e.g. `localHomepage.services[2].port`.
+  pub field: String,
+  pub code: ValidationCode,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSettingsRequest {
+  pub path: String,
+  // When set, the API token is included, encrypted with this passphrase.
+  pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSettingsRequest {
+  pub path: String,
+  // Needed to import an encrypted API token; settings are imported without it otherwise.
+  pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChange {
+  // Dotted settings path, e.g. `cloudflare.zoneId`.
+  pub path: String,
+  pub from: serde_json::Value,
+  pub to: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsImportPreview {
+  pub exported_time: String,
+  pub app_version: String,
+  pub changes: Vec<SettingsChange>,
+  pub includes_token: bool,
+  // True when the bundle has a token and the given passphrase decrypts it.
+  pub token_will_import: bool,
+  pub warnings: Vec<String>,
+  // Import is refused while this is non-empty.
+  pub validation_errors: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExternalConfigKind {
+  // ddns-go `.ddns_go_config.yaml`.
+  DdnsGo,
+  // inadyn `inadyn.conf`.
+  Inadyn,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalImportRequest {
+  pub kind: ExternalConfigKind,
+  pub path: String,
+  // False only previews the mapped changes.
+  pub apply: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalImportReport {
+  pub changes: Vec<SettingsChange>,
+  // Options of the source config that were not imported.
+  pub unsupported: Vec<String>,
+  pub zone_name: Option<String>,
+  // False when the zone ID could not be looked up and has to be entered manually.
+  pub zone_id_resolved: bool,
+  pub token_found: bool,
+  // Import is refused while this is non-empty.
+  pub validation_errors: Vec<FieldError>,
+  pub applied: bool,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LookupRecordIdRequest {
-  pub zone_id: String,
-  pub domain: String,
+  // Both fall back to the saved target, so the record ID can be refreshed on its own.
+  #[serde(default)]
+  pub zone_id: Option<String>,
+  #[serde(default)]
+  pub domain: Option<String>,
 }
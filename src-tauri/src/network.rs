@@ -1,32 +1,214 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 
 use netdev::{get_interfaces, Interface};
 
+use crate::interface_kind;
 use crate::ipv6_stability::{collect_ranks_from_interfaces, StabilityIndex, StabilityRank};
-use crate::models::InterfaceInfo;
+use crate::models::{
+  AppSettings, InterfaceFilterSettings, InterfaceInfo, InterfaceKind, InterfaceSelectionStatus, InterfaceSelector,
+  ReachabilityReport, RecordMode,
+};
 
-pub fn collect_interfaces_and_ipv6(selected_interface: Option<&str>) -> (Vec<InterfaceInfo>, Option<String>) {
+pub struct NetworkDetection {
+  pub interfaces: Vec<InterfaceInfo>,
+  pub current_ipv6: Option<String>,
+  // Every address eligible for publishing, best first.
+  pub ranked_ipv6: Vec<String>,
+  // Accepted addresses to publish, best first; `current_ipv6` is its first entry.
+  pub publish_ipv6: Vec<String>,
+  pub selection: InterfaceSelectionStatus,
+  pub reachability: Option<ReachabilityReport>,
+  // On-link prefix length of every global address, used for prefix tracking.
+  pub prefix_lengths: HashMap<String, u8>,
+}
+
+pub fn collect_interfaces_and_ipv6(settings: &AppSettings) -> NetworkDetection {
   let mut interfaces = get_interfaces();
   interfaces.sort_by(|a, b| a.name.cmp(&b.name));
   let outbound_source_ipv6 = detect_outbound_source_ipv6();
   let stability_index = collect_ranks_from_interfaces(&interfaces);
 
-  let infos = interfaces.iter().map(to_interface_info).collect::<Vec<_>>();
+  let infos = interfaces
+    .iter()
+    .map(|iface| to_interface_info(iface, &settings.interface_filter))
+    .collect::<Vec<_>>();
 
-  let current_ipv6 = if let Some(name) = selected_interface {
-    interfaces
+  let selectors = effective_selectors(settings);
+  let (ranked_ipv6, selection) = if selectors.is_empty() {
+    let eligible = interfaces
       .iter()
-      .find(|iface| iface.name == name)
-      .and_then(|iface| select_ipv6_from_interface(iface, &stability_index, outbound_source_ipv6))
+      .zip(&infos)
+      .filter(|(_, info)| !info.excluded)
+      .map(|(iface, _)| iface);
+    (
+      select_ipv6_from_interfaces(eligible, &stability_index, outbound_source_ipv6),
+      InterfaceSelectionStatus::default(),
+    )
   } else {
-    select_ipv6_from_interfaces(&interfaces, &stability_index, outbound_source_ipv6)
+    match resolve_selected_interface(&interfaces, &selectors) {
+      Some(iface) => (
+        select_ipv6_from_interface(iface, &stability_index, outbound_source_ipv6),
+        InterfaceSelectionStatus {
+          resolved_interface: Some(iface.name.clone()),
+          warning: None,
+        },
+      ),
+      None => (
+        Vec::new(),
+        InterfaceSelectionStatus {
+          resolved_interface: None,
+          warning: Some(format!(
+            "selected interface is not present: {}",
+            selectors.iter().map(describe_selector).collect::<Vec<_>>().join(", ")
+          )),
+        },
+      ),
+    }
+  };
+
+  let ranked_ipv6 = publishable(ranked_ipv6, settings.cloudflare.record_mode)
+    .iter()
+    .map(ToString::to_string)
+    .collect::<Vec<_>>();
+  let publish_ipv6 = ranked_ipv6
+    .iter()
+    .take(settings.cloudflare.publish_limit())
+    .cloned()
+    .collect::<Vec<_>>();
+  NetworkDetection {
+    interfaces: infos,
+    current_ipv6: publish_ipv6.first().cloned(),
+    ranked_ipv6,
+    publish_ipv6,
+    selection,
+    reachability: None,
+    prefix_lengths: collect_prefix_lengths(&interfaces),
+  }
+}
+
+// Privacy addresses rotate every few hours, so publishing them would churn the record. They are only used as the
+// single record's last resort when the host has nothing else.
+fn publishable(ranked: Vec<(Ipv6Addr, StabilityRank)>, record_mode: RecordMode) -> Vec<Ipv6Addr> {
+  let (temporary, stable): (Vec<_>, Vec<_>) = ranked
+    .into_iter()
+    .partition(|(_, rank)| *rank == StabilityRank::Temporary);
+  let candidates = match record_mode {
+    RecordMode::Single if stable.is_empty() => temporary,
+    _ => stable,
   };
+  candidates.into_iter().map(|(addr, _)| addr).collect()
+}
+
+fn collect_prefix_lengths(interfaces: &[Interface]) -> HashMap<String, u8> {
+  let mut lengths = HashMap::new();
+  for network in interfaces.iter().flat_map(|iface| iface.ipv6.iter()) {
+    if is_global_candidate(&network.addr()) {
+      lengths
+        .entry(network.addr().to_string())
+        .or_insert(network.prefix_len());
+    }
+  }
+  lengths
+}
+
+fn effective_selectors(settings: &AppSettings) -> Vec<InterfaceSelector> {
+  // Candidate lists take precedence over the single exact-name selection kept for older configs.
+  if !settings.interface_candidates.is_empty() {
+    return settings.interface_candidates.clone();
+  }
+  settings
+    .selected_interface
+    .iter()
+    .map(|name| InterfaceSelector::Name(name.clone()))
+    .collect()
+}
 
-  (infos, current_ipv6.map(|addr| addr.to_string()))
+fn resolve_selected_interface<'a>(interfaces: &'a [Interface], selectors: &[InterfaceSelector]) -> Option<&'a Interface> {
+  // Candidates are tried in priority order; the first one present on this host wins.
+  selectors
+    .iter()
+    .find_map(|selector| interfaces.iter().find(|iface| selector_matches(selector, iface)))
 }
 
-fn to_interface_info(iface: &Interface) -> InterfaceInfo {
+fn selector_matches(selector: &InterfaceSelector, iface: &Interface) -> bool {
+  match selector {
+    InterfaceSelector::Name(name) => iface.name == *name,
+    InterfaceSelector::Mac(mac) => iface
+      .mac_addr
+      .as_ref()
+      .is_some_and(|address| normalize_mac(&address.to_string()) == normalize_mac(mac)),
+    InterfaceSelector::Glob(pattern) => {
+      glob_matches(pattern, &iface.name)
+        || iface
+          .friendly_name
+          .as_deref()
+          .is_some_and(|friendly| glob_matches(pattern, friendly))
+    }
+  }
+}
+
+fn describe_selector(selector: &InterfaceSelector) -> String {
+  match selector {
+    InterfaceSelector::Name(name) => format!("name {name}"),
+    InterfaceSelector::Mac(mac) => format!("MAC {mac}"),
+    InterfaceSelector::Glob(pattern) => format!("pattern {pattern}"),
+  }
+}
+
+fn normalize_mac(value: &str) -> String {
+  value
+    .chars()
+    .filter(char::is_ascii_hexdigit)
+    .map(|ch| ch.to_ascii_lowercase())
+    .collect()
+}
+
+// Case-insensitive shell-style glob supporting `*` and `?`.
+fn glob_matches(pattern: &str, value: &str) -> bool {
+  let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+  let value = value.to_lowercase().chars().collect::<Vec<_>>();
+  let (mut p, mut v) = (0, 0);
+  let mut backtrack: Option<(usize, usize)> = None;
+  while v < value.len() {
+    if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+      p += 1;
+      v += 1;
+    } else if p < pattern.len() && pattern[p] == '*' {
+      backtrack = Some((p, v));
+      p += 1;
+    } else if let Some((star_p, star_v)) = backtrack {
+      p = star_p + 1;
+      v = star_v + 1;
+      backtrack = Some((star_p, star_v + 1));
+    } else {
+      return false;
+    }
+  }
+  pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+fn is_excluded_by_filter(iface: &Interface, kind: InterfaceKind, filter: &InterfaceFilterSettings) -> bool {
+  let matches_any = |patterns: &[String]| {
+    patterns.iter().any(|pattern| {
+      glob_matches(pattern, &iface.name)
+        || iface
+          .friendly_name
+          .as_deref()
+          .is_some_and(|friendly| glob_matches(pattern, friendly))
+    })
+  };
+  if matches_any(&filter.always_exclude) {
+    return true;
+  }
+  if matches_any(&filter.always_include) {
+    return false;
+  }
+  filter.excluded_kinds.contains(&kind)
+}
+
+fn to_interface_info(iface: &Interface, filter: &InterfaceFilterSettings) -> InterfaceInfo {
   let mut ipv6 = iface
     .ipv6
     .iter()
@@ -58,12 +240,17 @@ fn to_interface_info(iface: &Interface) -> InterfaceInfo {
     .or(iface.receive_speed)
     .map(|bits_per_second| bits_per_second / 1_000_000);
 
+  let classification = interface_kind::classify(iface);
+
   InterfaceInfo {
     id: iface.name.clone(),
     label,
     mac_address: mac,
     ipv6_addresses: ipv6,
     link_speed_mbps,
+    kind: classification.kind,
+    excluded: is_excluded_by_filter(iface, classification.kind, filter),
+    driver: classification.driver,
   }
 }
 
@@ -71,22 +258,21 @@ fn select_ipv6_from_interface(
   iface: &Interface,
   stability_index: &StabilityIndex,
   outbound_source_ipv6: Option<Ipv6Addr>,
-) -> Option<Ipv6Addr> {
+) -> Vec<(Ipv6Addr, StabilityRank)> {
   let candidates = iface
     .ipv6
     .iter()
     .filter_map(|network| to_candidate(iface, network.addr(), network.prefix_len(), stability_index, outbound_source_ipv6))
     .collect::<Vec<_>>();
-  pick_best_candidate(candidates)
+  rank_candidates(candidates)
 }
 
-fn select_ipv6_from_interfaces(
-  interfaces: &[Interface],
+fn select_ipv6_from_interfaces<'a>(
+  interfaces: impl Iterator<Item = &'a Interface>,
   stability_index: &StabilityIndex,
   outbound_source_ipv6: Option<Ipv6Addr>,
-) -> Option<Ipv6Addr> {
+) -> Vec<(Ipv6Addr, StabilityRank)> {
   let candidates = interfaces
-    .iter()
     .flat_map(|iface| {
       iface
         .ipv6
@@ -94,7 +280,7 @@ fn select_ipv6_from_interfaces(
         .filter_map(|network| to_candidate(iface, network.addr(), network.prefix_len(), stability_index, outbound_source_ipv6))
     })
     .collect::<Vec<_>>();
-  pick_best_candidate(candidates)
+  rank_candidates(candidates)
 }
 
 fn to_candidate(
@@ -114,12 +300,16 @@ fn to_candidate(
   })
 }
 
-fn pick_best_candidate(mut candidates: Vec<Ipv6Candidate>) -> Option<Ipv6Addr> {
-  if candidates.is_empty() {
-    return None;
-  }
+fn rank_candidates(mut candidates: Vec<Ipv6Candidate>) -> Vec<(Ipv6Addr, StabilityRank)> {
   candidates.sort_by(order_ipv6_candidates_with_policy);
-  candidates.into_iter().next().map(|candidate| candidate.addr)
+  let mut ranked = Vec::<(Ipv6Addr, StabilityRank)>::with_capacity(candidates.len());
+  for candidate in candidates {
+    // The same address can be reported by several interfaces; keep its best-ranked position.
+    if !ranked.iter().any(|(addr, _)| *addr == candidate.addr) {
+      ranked.push((candidate.addr, candidate.stability_rank));
+    }
+  }
+  ranked
 }
 
 fn order_ipv6_candidates_with_policy(a: &Ipv6Candidate, b: &Ipv6Candidate) -> Ordering {
@@ -174,3 +364,70 @@ struct Ipv6Candidate {
   stability_rank: StabilityRank,
   outbound_selected: bool,
 }
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv6Addr;
+
+  use super::{glob_matches, normalize_mac, publishable, RecordMode, StabilityRank};
+
+  #[test]
+  fn glob_matches_shell_patterns() {
+    let cases = [
+      ("eth0", "eth0", true),
+      ("eth*", "eth0", true),
+      ("eth*", "eth", true),
+      ("eth*", "wlan0", false),
+      ("*", "", true),
+      ("*", "enp3s0", true),
+      ("en?s0", "enp3s0", false),
+      ("enp?s0", "enp3s0", true),
+      ("enp?s0", "enps0", false),
+      ("*s0", "enp3s0", true),
+      ("*s0", "enp3s1", false),
+      ("e*p*s*", "enp3s0", true),
+      ("*-ipv6", "he-ipv6", true),
+      ("veth*b", "veth12ab", true),
+      ("veth*b", "veth12ab0", false),
+      ("ETH*", "eth0", true),
+      ("Ethernet ?", "ethernet 2", true),
+      ("", "eth0", false),
+    ];
+    for (pattern, value, expected) in cases {
+      assert_eq!(glob_matches(pattern, value), expected, "{pattern:?} vs {value:?}");
+    }
+  }
+
+  #[test]
+  fn normalize_mac_ignores_separators_and_case() {
+    let expected = "001a2b3c4d5e";
+    for mac in ["00:1A:2B:3C:4D:5E", "00-1a-2b-3c-4d-5e", "001a.2b3c.4d5e", "001A2B3C4D5E"] {
+      assert_eq!(normalize_mac(mac), expected, "{mac}");
+    }
+    assert_ne!(normalize_mac("00:1a:2b:3c:4d:5f"), expected);
+  }
+
+  #[test]
+  fn privacy_addresses_are_only_a_single_record_fallback() {
+    let stable = "2001:db8:1::10".parse::<Ipv6Addr>().unwrap();
+    let temporary_a = "2001:db8:1::a1".parse::<Ipv6Addr>().unwrap();
+    let temporary_b = "2001:db8:1::b2".parse::<Ipv6Addr>().unwrap();
+    let mixed = vec![
+      (stable, StabilityRank::Stable),
+      (temporary_a, StabilityRank::Temporary),
+      (temporary_b, StabilityRank::Temporary),
+    ];
+    let only_temporary = vec![
+      (temporary_a, StabilityRank::Temporary),
+      (temporary_b, StabilityRank::Temporary),
+    ];
+
+    assert_eq!(publishable(mixed.clone(), RecordMode::MultiValue), vec![stable]);
+    assert_eq!(publishable(mixed, RecordMode::Single), vec![stable]);
+    assert_eq!(
+      publishable(only_temporary.clone(), RecordMode::Single),
+      vec![temporary_a, temporary_b]
+    );
+    assert!(publishable(only_temporary, RecordMode::MultiValue).is_empty());
+  }
+}
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use futures_util::StreamExt;
 use rtnetlink::new_connection;
+#[cfg(feature = "gui")]
 use tauri::{
   tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
   AppHandle, Manager,
@@ -10,6 +11,7 @@ use tokio::sync::Notify;
 
 use crate::models::ThemeMode;
 
+#[cfg(feature = "gui")]
 use super::TrayHandlers;
 
 pub struct PlatformWatcher {
@@ -32,10 +34,10 @@ pub fn detect_theme_hint() -> Option<ThemeMode> {
 pub fn start_network_watcher(notify: Arc<Notify>) -> Result<Option<PlatformWatcher>, String> {
   let (connection, _handle, mut messages) =
     new_connection().map_err(|error| format!("failed to open rtnetlink connection: {error}"))?;
-  tauri::async_runtime::spawn(connection);
+  crate::rt::spawn(connection);
 
   let notify_for_task = notify.clone();
-  tauri::async_runtime::spawn(async move {
+  crate::rt::spawn(async move {
     while messages.next().await.is_some() {
       notify_for_task.notify_one();
     }
@@ -44,6 +46,7 @@ pub fn start_network_watcher(notify: Arc<Notify>) -> Result<Option<PlatformWatch
   Ok(Some(PlatformWatcher { _notify: notify }))
 }
 
+#[cfg(feature = "gui")]
 pub fn install_tray(
   app: &AppHandle,
   menu: tauri::menu::Menu<tauri::Wry>,
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use system_configuration::dynamic_store::{SCDynamicStore, SCDynamicStoreBuilder};
+#[cfg(feature = "gui")]
 use tauri::{
   tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
   AppHandle, Manager,
@@ -9,6 +10,7 @@ use tokio::sync::Notify;
 
 use crate::models::ThemeMode;
 
+#[cfg(feature = "gui")]
 use super::TrayHandlers;
 
 pub struct PlatformWatcher {
@@ -39,6 +41,7 @@ pub fn start_network_watcher(notify: Arc<Notify>) -> Result<Option<PlatformWatch
   }))
 }
 
+#[cfg(feature = "gui")]
 pub fn install_tray(
   app: &AppHandle,
   menu: tauri::menu::Menu<tauri::Wry>,
@@ -4,13 +4,16 @@ use std::{
     atomic::{AtomicBool, Ordering},
     Arc,
   },
+  time::{Duration, Instant},
 };
 
+#[cfg(feature = "gui")]
 use tauri::AppHandle;
 use tokio::sync::Notify;
 
 use crate::models::ThemeMode;
 
+#[cfg(feature = "gui")]
 #[derive(Clone)]
 pub struct TrayHandlers {
   pub on_show_main: Arc<dyn Fn(&AppHandle) + Send + Sync>,
@@ -43,6 +46,7 @@ pub fn start_network_watcher(notify: Arc<Notify>) -> Result<Option<PlatformWatch
   current::start_network_watcher(notify)
 }
 
+#[cfg(feature = "gui")]
 pub fn install_tray(
   app: &AppHandle,
   menu: tauri::menu::Menu<tauri::Wry>,
@@ -51,25 +55,62 @@ pub fn install_tray(
   current::install_tray(app, menu, handlers)
 }
 
-pub fn spawn_background_worker<F, Fut>(
+// `heartbeat` is called after every tick and at least once per its interval while the loop is idle, so a
+// supervisor such as the systemd watchdog can tell the worker is still alive.
+pub fn spawn_background_worker<F, Fut, P, H>(
   notify: Arc<Notify>,
   shutting_down: Arc<AtomicBool>,
+  poll_interval: P,
+  heartbeat: Option<(Duration, H)>,
   mut on_tick: F,
 ) where
   F: FnMut() -> Fut + Send + 'static,
   Fut: Future<Output = ()> + Send + 'static,
+  P: Fn() -> Option<Duration> + Send + 'static,
+  H: Fn() + Send + Sync + 'static,
 {
-  tauri::async_runtime::spawn(async move {
+  crate::rt::spawn(async move {
+    let beat = || {
+      if let Some((_, heartbeat)) = heartbeat.as_ref() {
+        heartbeat();
+      }
+    };
+    let heartbeat_interval = heartbeat.as_ref().map(|(interval, _)| *interval);
+
     on_tick().await;
+    beat();
     loop {
       if shutting_down.load(Ordering::SeqCst) {
         break;
       }
-      notify.notified().await;
+      // Polling is a safety net for watcher events that were missed or never delivered.
+      let deadline = poll_interval().map(|interval| Instant::now() + interval);
+      loop {
+        let until_poll = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        let wait = match (until_poll, heartbeat_interval) {
+          (Some(poll), Some(heartbeat)) => Some(poll.min(heartbeat)),
+          (poll, heartbeat) => poll.or(heartbeat),
+        };
+        let notified = match wait {
+          Some(wait) => tokio::time::timeout(wait, notify.notified()).await.is_ok(),
+          None => {
+            notify.notified().await;
+            true
+          }
+        };
+        if notified || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+          break;
+        }
+        if shutting_down.load(Ordering::SeqCst) {
+          break;
+        }
+        beat();
+      }
       if shutting_down.load(Ordering::SeqCst) {
         break;
       }
       on_tick().await;
+      beat();
     }
   });
 }
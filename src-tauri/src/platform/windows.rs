@@ -1,5 +1,6 @@
 use std::{ffi::c_void, sync::Arc};
 
+#[cfg(feature = "gui")]
 use tauri::{
   tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
   AppHandle,
@@ -15,6 +16,7 @@ use windows_sys::Win32::{
 
 use crate::models::ThemeMode;
 
+#[cfg(feature = "gui")]
 use super::TrayHandlers;
 
 pub struct PlatformWatcher {
@@ -65,6 +67,7 @@ pub fn start_network_watcher(notify: Arc<Notify>) -> Result<Option<PlatformWatch
   }))
 }
 
+#[cfg(feature = "gui")]
 pub fn install_tray(
   app: &AppHandle,
   menu: tauri::menu::Menu<tauri::Wry>,
@@ -0,0 +1,169 @@
+use std::net::Ipv6Addr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::{PrefixChangedEvent, PrefixRecord};
+
+const PREFIX_HISTORY_FILE_NAME: &str = "prefix_history.json";
+const MAX_PREFIX_RECORDS: usize = 500;
+// `last_seen` is only a liveness hint, so it is flushed to disk at most this often.
+const LAST_SEEN_PERSIST_INTERVAL_MINUTES: i64 = 10;
+// Longest prefix an ISP delegates to a site (RFC 6177); longer on-link lengths are host routes, not delegations.
+const MAX_DELEGATED_PREFIX_LEN: u8 = 64;
+
+pub struct PrefixHistory {
+  path: PathBuf,
+  records: Vec<PrefixRecord>,
+  last_persisted: Option<DateTime<Utc>>,
+}
+
+impl PrefixHistory {
+  pub fn load(config_path: &Path) -> Self {
+    let path = config_path
+      .parent()
+      .map(|dir| dir.join(PREFIX_HISTORY_FILE_NAME))
+      .unwrap_or_else(|| PathBuf::from(PREFIX_HISTORY_FILE_NAME));
+    let records = std::fs::read_to_string(&path)
+      .ok()
+      .and_then(|text| serde_json::from_str::<Vec<PrefixRecord>>(&text).ok())
+      .unwrap_or_default();
+    Self {
+      path,
+      records,
+      last_persisted: None,
+    }
+  }
+
+  pub fn records(&self) -> &[PrefixRecord] {
+    &self.records
+  }
+
+  pub fn current(&self) -> Option<&PrefixRecord> {
+    self.records.last().filter(|record| record.ended_time.is_none())
+  }
+
+  // Records that `prefix` is active. Returns the change event when it replaced a different prefix; the first prefix
+  // ever seen only opens a record, since there is nothing it changed from.
+  pub fn observe(&mut self, prefix: &str, prefix_len: u8, now: DateTime<Utc>) -> Result<Option<PrefixChangedEvent>> {
+    let now_text = now.to_rfc3339();
+    if let Some(current) = self.records.last_mut().filter(|record| record.ended_time.is_none()) {
+      if current.prefix == prefix {
+        current.last_seen_time = now_text;
+        let stale = self
+          .last_persisted
+          .is_none_or(|last| now - last >= Duration::minutes(LAST_SEEN_PERSIST_INTERVAL_MINUTES));
+        if stale {
+          self.persist(now)?;
+        }
+        return Ok(None);
+      }
+    }
+
+    let previous = self.records.last_mut().filter(|record| record.ended_time.is_none()).map(|record| {
+      record.ended_time = Some(now_text.clone());
+      record.clone()
+    });
+    self.records.push(PrefixRecord {
+      prefix: prefix.to_string(),
+      prefix_len,
+      first_seen_time: now_text.clone(),
+      last_seen_time: now_text.clone(),
+      ended_time: None,
+    });
+    if self.records.len() > MAX_PREFIX_RECORDS {
+      let overflow = self.records.len() - MAX_PREFIX_RECORDS;
+      self.records.drain(..overflow);
+    }
+    self.persist(now)?;
+
+    let Some(previous) = previous else {
+      return Ok(None);
+    };
+    Ok(Some(PrefixChangedEvent {
+      lifetime_seconds: lifetime_seconds(&previous),
+      previous_prefix: previous.prefix,
+      prefix: prefix.to_string(),
+      prefix_len,
+      changed_time: now_text,
+    }))
+  }
+
+  fn persist(&mut self, now: DateTime<Utc>) -> Result<()> {
+    let json = serde_json::to_string_pretty(&self.records).context("failed to serialize prefix history")?;
+    crate::config::write_file_atomic(&self.path, json.as_bytes())
+      .with_context(|| format!("failed to write prefix history to {}", self.path.display()))?;
+    self.last_persisted = Some(now);
+    Ok(())
+  }
+}
+
+pub fn lifetime_seconds(record: &PrefixRecord) -> Option<i64> {
+  let start = DateTime::parse_from_rfc3339(&record.first_seen_time).ok()?;
+  let end = DateTime::parse_from_rfc3339(record.ended_time.as_deref().unwrap_or(&record.last_seen_time)).ok()?;
+  Some((end - start).num_seconds())
+}
+
+// The on-link length says nothing about the delegation for DHCPv6 /128 (or /127 point-to-point) addresses, which
+// would make every new address look like a new prefix; those are tracked as the /64 they sit in.
+pub fn delegated_prefix_len(on_link_len: u8) -> u8 {
+  on_link_len.min(MAX_DELEGATED_PREFIX_LEN)
+}
+
+// Masks the address down to its network prefix, e.g. `2001:db8:1:2::/64`.
+pub fn prefix_of(addr: Ipv6Addr, prefix_len: u8) -> String {
+  let prefix_len = prefix_len.min(128);
+  let mask = if prefix_len == 0 {
+    0
+  } else {
+    u128::MAX << (128 - u32::from(prefix_len))
+  };
+  format!("{}/{}", Ipv6Addr::from(u128::from(addr) & mask), prefix_len)
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{Duration, TimeZone, Utc};
+
+  use super::*;
+
+  #[test]
+  fn host_routes_are_tracked_as_their_slash_64() {
+    let dhcpv6 = "2001:db8:1:2::1234".parse::<Ipv6Addr>().unwrap();
+    let next_lease = "2001:db8:1:2::5678".parse::<Ipv6Addr>().unwrap();
+    assert_eq!(delegated_prefix_len(128), 64);
+    assert_eq!(delegated_prefix_len(64), 64);
+    assert_eq!(delegated_prefix_len(56), 56);
+    assert_eq!(prefix_of(dhcpv6, delegated_prefix_len(128)), "2001:db8:1:2::/64");
+    assert_eq!(
+      prefix_of(dhcpv6, delegated_prefix_len(128)),
+      prefix_of(next_lease, delegated_prefix_len(128))
+    );
+    assert_eq!(prefix_of(dhcpv6, 56), "2001:db8:1::/56");
+  }
+
+  #[test]
+  fn only_a_replaced_prefix_emits_an_event() {
+    let dir = std::env::temp_dir().join(format!("cf-ddns-prefix-history-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut history = PrefixHistory::load(&dir.join("settings.json"));
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+
+    assert!(history.observe("2001:db8:1::/64", 64, start).unwrap().is_none());
+    assert!(history
+      .observe("2001:db8:1::/64", 64, start + Duration::hours(1))
+      .unwrap()
+      .is_none());
+    let event = history
+      .observe("2001:db8:2::/64", 64, start + Duration::days(2))
+      .unwrap()
+      .expect("replacing the prefix emits an event");
+    assert_eq!(event.previous_prefix, "2001:db8:1::/64");
+    assert_eq!(event.prefix, "2001:db8:2::/64");
+    assert_eq!(event.lifetime_seconds, Some(2 * 86_400));
+    assert_eq!(history.records().len(), 2);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
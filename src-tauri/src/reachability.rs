@@ -0,0 +1,96 @@
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::time::Instant;
+
+use chrono::Utc;
+use tokio::net::TcpSocket;
+use tokio::time::Duration;
+
+use crate::models::{ReachabilityReport, ReachabilityResult, ReachabilitySettings};
+use crate::network::NetworkDetection;
+
+// Probes ranked candidates in order and accepts the first `wanted` ones that can reach the configured endpoint.
+pub async fn verify_detection(detection: &mut NetworkDetection, settings: &ReachabilitySettings, wanted: usize) {
+  if !settings.enabled || detection.ranked_ipv6.is_empty() {
+    detection.reachability = None;
+    return;
+  }
+
+  let probe_endpoint = settings.probe_endpoint.trim().to_string();
+  let mut report = ReachabilityReport {
+    probe_endpoint: probe_endpoint.clone(),
+    checked_time: Utc::now().to_rfc3339(),
+    results: Vec::new(),
+    error: None,
+  };
+
+  let target = match resolve_probe_endpoint(&probe_endpoint).await {
+    Ok(target) => target,
+    Err(error) => {
+      // Without a usable probe target we cannot judge candidates, so keep the ranking as-is.
+      report.error = Some(error);
+      detection.reachability = Some(report);
+      return;
+    }
+  };
+
+  let timeout = Duration::from_millis(settings.timeout_ms.max(1));
+  let mut accepted = Vec::new();
+  for candidate in &detection.ranked_ipv6 {
+    let Ok(source) = candidate.parse::<Ipv6Addr>() else {
+      continue;
+    };
+    let result = match probe(source, target, timeout).await {
+      Ok(latency) => ReachabilityResult {
+        address: candidate.clone(),
+        reachable: true,
+        latency_ms: Some(latency.as_millis() as u64),
+        error: None,
+      },
+      Err(error) => ReachabilityResult {
+        address: candidate.clone(),
+        reachable: false,
+        latency_ms: None,
+        error: Some(error),
+      },
+    };
+    let reachable = result.reachable;
+    report.results.push(result);
+    if reachable {
+      accepted.push(candidate.clone());
+      if accepted.len() >= wanted {
+        break;
+      }
+    }
+  }
+
+  // No reachable candidate means no usable address, so nothing gets pushed until one recovers.
+  detection.current_ipv6 = accepted.first().cloned();
+  detection.publish_ipv6 = accepted;
+  detection.reachability = Some(report);
+}
+
+async fn resolve_probe_endpoint(endpoint: &str) -> Result<SocketAddr, String> {
+  if endpoint.is_empty() {
+    return Err("probe endpoint is empty".to_string());
+  }
+  let mut addresses = tokio::net::lookup_host(endpoint)
+    .await
+    .map_err(|error| format!("failed resolving probe endpoint {endpoint}: {error}"))?;
+  addresses
+    .find(SocketAddr::is_ipv6)
+    .ok_or_else(|| format!("probe endpoint {endpoint} has no IPv6 address"))
+}
+
+async fn probe(source: Ipv6Addr, target: SocketAddr, timeout: Duration) -> Result<Duration, String> {
+  // Binding the socket to the candidate forces the kernel to use it as source address for the probe.
+  let socket = TcpSocket::new_v6().map_err(|error| format!("failed creating probe socket: {error}"))?;
+  socket
+    .bind(SocketAddr::new(IpAddr::V6(source), 0))
+    .map_err(|error| format!("failed binding probe socket: {error}"))?;
+  let started = Instant::now();
+  match tokio::time::timeout(timeout, socket.connect(target)).await {
+    Ok(Ok(_stream)) => Ok(started.elapsed()),
+    Ok(Err(error)) => Err(format!("connect failed: {error}")),
+    Err(_) => Err(format!("connect timed out after {} ms", timeout.as_millis())),
+  }
+}
@@ -0,0 +1,16 @@
+// Background tasks run on Tauri's runtime in the GUI build and on the daemon's own Tokio runtime otherwise,
+// so the engine never has to know which host it is running under.
+#[cfg(feature = "gui")]
+pub use tauri::async_runtime::{block_on, spawn, spawn_blocking, JoinHandle};
+
+#[cfg(not(feature = "gui"))]
+pub use tokio::task::{spawn, spawn_blocking, JoinHandle};
+
+#[cfg(not(feature = "gui"))]
+pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+  tokio::runtime::Builder::new_multi_thread()
+    .enable_all()
+    .build()
+    .expect("failed to start Tokio runtime")
+    .block_on(future)
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use tokio::time::Duration;
+
+use crate::models::RuntimeCache;
+
+const RUNTIME_STATE_FILE_NAME: &str = "runtime_state.json";
+// Status updates often arrive in bursts (detect, push, result), so they are written once per window.
+const RUNTIME_STATE_WRITE_DELAY: Duration = Duration::from_secs(2);
+
+// Volatile runtime cache kept apart from settings.json so user settings are only written on user changes.
+pub struct RuntimeStateStore {
+  path: PathBuf,
+  pending: Arc<Mutex<Option<RuntimeCache>>>,
+  flush_scheduled: Arc<AtomicBool>,
+}
+
+impl RuntimeStateStore {
+  pub fn new(config_path: &Path) -> Self {
+    let path = config_path
+      .parent()
+      .map(|dir| dir.join(RUNTIME_STATE_FILE_NAME))
+      .unwrap_or_else(|| PathBuf::from(RUNTIME_STATE_FILE_NAME));
+    Self {
+      path,
+      pending: Arc::new(Mutex::new(None)),
+      flush_scheduled: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  // Returns `None` when no state file exists yet, e.g. right after upgrading from a combined settings.json.
+  pub fn load(&self) -> Option<RuntimeCache> {
+    let text = std::fs::read_to_string(&self.path).ok()?;
+    match serde_json::from_str::<RuntimeCache>(&text) {
+      Ok(cache) => Some(cache),
+      Err(error) => {
+        // The cache is rebuilt by the next detection cycle, so a damaged file is not worth failing over.
+        eprintln!("ignoring unreadable runtime state {}: {error}", self.path.display());
+        None
+      }
+    }
+  }
+
+  // Writes the cache read from a pre-v2 settings.json, unless a state file already exists. Runs before the migrated
+  // settings.json is saved without its `cache` block, so the cache is on disk in one of the two files at all times.
+  pub fn carry_over_legacy(&self, cache: &RuntimeCache) -> Result<()> {
+    if self.path.exists() {
+      return Ok(());
+    }
+    write_state(&self.path, cache)
+  }
+
+  // Queues `cache` for writing; calls within the write window are coalesced into one write of the latest value.
+  pub fn save_debounced(&self, cache: &RuntimeCache) {
+    *self.pending.lock() = Some(cache.clone());
+    if self.flush_scheduled.swap(true, Ordering::SeqCst) {
+      return;
+    }
+
+    let path = self.path.clone();
+    let pending = self.pending.clone();
+    let flush_scheduled = self.flush_scheduled.clone();
+    crate::rt::spawn(async move {
+      tokio::time::sleep(RUNTIME_STATE_WRITE_DELAY).await;
+      // Clear the flag before taking the value so a concurrent save schedules its own flush.
+      flush_scheduled.store(false, Ordering::SeqCst);
+      let cache = pending.lock().take();
+      if let Some(cache) = cache {
+        if let Err(error) = write_state(&path, &cache) {
+          eprintln!("{error:#}");
+        }
+      }
+    });
+  }
+
+  // Writes any queued cache immediately; used before the process exits.
+  pub fn flush(&self) -> Result<()> {
+    let cache = self.pending.lock().take();
+    match cache {
+      Some(cache) => write_state(&self.path, &cache),
+      None => Ok(()),
+    }
+  }
+}
+
+fn write_state(path: &Path, cache: &RuntimeCache) -> Result<()> {
+  let json = serde_json::to_string_pretty(cache).context("failed to serialize runtime state")?;
+  crate::config::write_file_atomic(path, json.as_bytes())
+    .with_context(|| format!("failed to write runtime state to {}", path.display()))
+}
@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+const KDF_ARGON2ID: &str = "argon2id";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+// Passphrase-encrypted secret: Argon2id derives the key, ChaCha20-Poly1305 encrypts and authenticates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedSecret {
+  pub kdf: String,
+  pub salt: String,
+  pub nonce: String,
+  pub ciphertext: String,
+}
+
+pub fn seal(passphrase: &str, plaintext: &[u8]) -> Result<SealedSecret> {
+  let mut salt = [0u8; SALT_LEN];
+  OsRng.fill_bytes(&mut salt);
+  let cipher = cipher_for(passphrase, &salt)?;
+  let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+  let ciphertext = cipher
+    .encrypt(&nonce, plaintext)
+    .map_err(|error| anyhow!("failed to encrypt secret: {error}"))?;
+  Ok(SealedSecret {
+    kdf: KDF_ARGON2ID.to_string(),
+    salt: STANDARD.encode(salt),
+    nonce: STANDARD.encode(nonce),
+    ciphertext: STANDARD.encode(ciphertext),
+  })
+}
+
+pub fn open(passphrase: &str, sealed: &SealedSecret) -> Result<Vec<u8>> {
+  if sealed.kdf != KDF_ARGON2ID {
+    return Err(anyhow!("unsupported key derivation `{}`", sealed.kdf));
+  }
+  let salt = STANDARD.decode(&sealed.salt).context("invalid secret salt")?;
+  let nonce = STANDARD.decode(&sealed.nonce).context("invalid secret nonce")?;
+  if nonce.len() != NONCE_LEN {
+    return Err(anyhow!("invalid secret nonce length"));
+  }
+  let ciphertext = STANDARD.decode(&sealed.ciphertext).context("invalid secret ciphertext")?;
+  let cipher = cipher_for(passphrase, &salt)?;
+  // AEAD tag verification fails for both a wrong passphrase and a tampered file.
+  cipher
+    .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+    .map_err(|_| anyhow!("wrong passphrase or corrupted secret"))
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+  let mut key = [0u8; 32];
+  // argon2 crate docs: `Argon2::default()` is Argon2id v19 with the OWASP-recommended parameters.
+  Argon2::default()
+    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|error| anyhow!("failed to derive key from passphrase: {error}"))?;
+  Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sealed_secret_round_trips_with_a_fresh_salt_and_nonce() {
+    let first = seal("correct horse", b"api-token").unwrap();
+    let second = seal("correct horse", b"api-token").unwrap();
+    assert_eq!(open("correct horse", &first).unwrap(), b"api-token");
+    assert_eq!(open("correct horse", &second).unwrap(), b"api-token");
+    assert_ne!(first.salt, second.salt);
+    assert_ne!(first.ciphertext, second.ciphertext);
+  }
+
+  #[test]
+  fn wrong_passphrase_is_rejected() {
+    let sealed = seal("correct horse", b"api-token").unwrap();
+    let error = open("battery staple", &sealed).unwrap_err();
+    assert!(error.to_string().contains("wrong passphrase"), "{error:#}");
+  }
+
+  #[test]
+  fn truncated_or_tampered_secrets_are_rejected() {
+    let sealed = seal("correct horse", b"api-token").unwrap();
+    let ciphertext = STANDARD.decode(&sealed.ciphertext).unwrap();
+
+    let mut flipped = ciphertext.clone();
+    flipped[0] ^= 0x01;
+    let mut truncated = ciphertext.clone();
+    truncated.truncate(ciphertext.len() - 1);
+    let mut other_salt = STANDARD.decode(&sealed.salt).unwrap();
+    other_salt[0] ^= 0x01;
+
+    let cases = [
+      SealedSecret {
+        ciphertext: STANDARD.encode(&flipped),
+        ..sealed.clone()
+      },
+      SealedSecret {
+        ciphertext: STANDARD.encode(&truncated),
+        ..sealed.clone()
+      },
+      SealedSecret {
+        ciphertext: String::new(),
+        ..sealed.clone()
+      },
+      SealedSecret {
+        salt: STANDARD.encode(&other_salt),
+        ..sealed.clone()
+      },
+      SealedSecret {
+        nonce: STANDARD.encode([0u8; NONCE_LEN - 1]),
+        ..sealed.clone()
+      },
+      SealedSecret {
+        ciphertext: "not base64!".to_string(),
+        ..sealed.clone()
+      },
+      SealedSecret {
+        kdf: "scrypt".to_string(),
+        ..sealed.clone()
+      },
+    ];
+    for (index, case) in cases.iter().enumerate() {
+      assert!(open("correct horse", case).is_err(), "case {index} opened");
+    }
+  }
+}
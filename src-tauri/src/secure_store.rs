@@ -1,52 +1,362 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
 use anyhow::{Context, Result};
+use chrono::Utc;
 use keyring::Entry;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{CredentialSummary, TokenBackendKind, TokenSource};
+use crate::secret_box::{self, SealedSecret};
+use crate::token_sources;
+
+const DEFAULT_ACCOUNT: &str = "cloudflare_api_token";
+const TOKEN_FILE_NAME: &str = "api_token.enc";
+const CREDENTIAL_INDEX_FILE_NAME: &str = "credentials.json";
+// Lets headless installs pick their own key instead of the machine-bound default.
+const TOKEN_PASSPHRASE_ENV: &str = "CF_DDNS_TOKEN_PASSPHRASE";
+// Credential helpers can be slow or prompt for unlock, so their answer is reused for a while.
+const COMMAND_TOKEN_CACHE_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenFile {
+  // Which key material sealed the token, so a later mismatch gives a useful error.
+  key_source: KeySource,
+  secret: SealedSecret,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum KeySource {
+  Passphrase,
+  MachineId,
+}
 
 pub struct SecureTokenStore {
   service: String,
-  account: String,
+  config_dir: PathBuf,
+  backend: Mutex<TokenBackendKind>,
+  // External sources tried before the store, in priority order.
+  sources: Mutex<Vec<TokenSource>>,
+  command_cache: Mutex<Option<(TokenSource, Instant, String)>>,
+  // Label of the source that supplied the last token, never the token itself.
+  active_source: Mutex<Option<String>>,
+  // Keyrings cannot be enumerated, so names of the named credentials are kept in an index file.
+  credentials: Mutex<Vec<CredentialSummary>>,
 }
 
 impl SecureTokenStore {
-  pub fn new(app_identifier: &str) -> Self {
+  pub fn new(app_identifier: &str, config_path: &Path) -> Self {
+    let config_dir = config_path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let credentials = std::fs::read_to_string(config_dir.join(CREDENTIAL_INDEX_FILE_NAME))
+      .ok()
+      .and_then(|text| serde_json::from_str::<Vec<CredentialSummary>>(&text).ok())
+      .unwrap_or_default();
     Self {
       service: app_identifier.to_string(),
-      account: "cloudflare_api_token".to_string(),
+      config_dir,
+      backend: Mutex::new(TokenBackendKind::Keyring),
+      sources: Mutex::new(Vec::new()),
+      command_cache: Mutex::new(None),
+      active_source: Mutex::new(None),
+      credentials: Mutex::new(credentials),
+    }
+  }
+
+  pub fn set_sources(&self, sources: Vec<TokenSource>) {
+    let mut current = self.sources.lock();
+    if *current != sources {
+      *current = sources;
+      *self.command_cache.lock() = None;
     }
   }
 
-  fn entry(&self) -> Result<Entry> {
+  pub fn active_source(&self) -> Option<String> {
+    self.active_source.lock().clone()
+  }
+
+  pub fn backend(&self) -> TokenBackendKind {
+    *self.backend.lock()
+  }
+
+  pub fn credentials(&self) -> Vec<CredentialSummary> {
+    self.credentials.lock().clone()
+  }
+
+  // Probes the OS keyring once at startup; blocking, so callers run it off the async runtime.
+  pub fn detect_backend(&self) -> TokenBackendKind {
+    let backend = if self.token_file(None).exists() {
+      // A token already written to the fallback file stays authoritative until it is cleared.
+      TokenBackendKind::EncryptedFile
+    } else {
+      match self.entry(None).and_then(|entry| probe_keyring(&entry)) {
+        Ok(()) => TokenBackendKind::Keyring,
+        Err(error) => {
+          eprintln!("OS keyring unavailable, using the {TOKEN_FILE_NAME} file store: {error:#}");
+          TokenBackendKind::EncryptedFile
+        }
+      }
+    };
+    *self.backend.lock() = backend;
+    backend
+  }
+
+  // `None` is the default credential, stored under the account name used before named credentials existed.
+  fn entry(&self, credential_id: Option<&str>) -> Result<Entry> {
+    let account = match credential_id {
+      Some(id) => format!("{DEFAULT_ACCOUNT}:{id}"),
+      None => DEFAULT_ACCOUNT.to_string(),
+    };
     // keyring crate docs: platform-native backends use Windows Credential Manager, macOS Keychain,
     // and Linux Secret Service when the related cargo features are enabled.
-    Entry::new(&self.service, &self.account).context("failed to create keyring entry")
+    Entry::new(&self.service, &account).context("failed to create keyring entry")
+  }
+
+  fn token_file(&self, credential_id: Option<&str>) -> PathBuf {
+    match credential_id {
+      Some(id) => self.config_dir.join(format!("api_token.{id}.enc")),
+      None => self.config_dir.join(TOKEN_FILE_NAME),
+    }
   }
 
   pub fn set_token(&self, token: &str) -> Result<()> {
-    self
-      .entry()?
-      .set_password(token)
-      .context("failed to store API token in secure keyring")?;
-    Ok(())
+    self.store_token(None, token)
   }
 
+  // Tries the external sources in order and falls back to the keyring or encrypted file.
   pub fn get_token(&self) -> Result<Option<String>> {
-    let entry = self.entry()?;
-    match entry.get_password() {
-      Ok(token) if token.trim().is_empty() => Ok(None),
-      Ok(token) => Ok(Some(token)),
-      Err(error) => {
-        let text = error.to_string();
-        if text.contains("NoEntry") || text.contains("not found") {
-          Ok(None)
-        } else {
-          Err(anyhow::anyhow!("failed to read API token from secure keyring: {text}"))
+    let sources = self.sources.lock().clone();
+    let mut first_error = None;
+    for source in &sources {
+      match self.read_source(source) {
+        Ok(Some(token)) => {
+          *self.active_source.lock() = Some(token_sources::describe(source));
+          return Ok(Some(token));
+        }
+        Ok(None) => {}
+        Err(error) => {
+          eprintln!("token source {} failed: {error:#}", token_sources::describe(source));
+          first_error.get_or_insert(error);
         }
       }
     }
+
+    let stored = self.load_token(None)?;
+    *self.active_source.lock() = stored.as_ref().map(|_| self.backend_label());
+    match (stored, first_error) {
+      (Some(token), _) => Ok(Some(token)),
+      // Surface why a configured source failed instead of a bare "API token is not set".
+      (None, Some(error)) => Err(error),
+      (None, None) => Ok(None),
+    }
   }
 
   pub fn clear_token(&self) -> Result<()> {
-    let entry = self.entry()?;
-    let _ = entry.delete_credential();
+    self.remove_token(None)
+  }
+
+  // Token for a record target: its named credential when set, the default chain otherwise.
+  pub fn token_for(&self, credential_id: Option<&str>) -> Result<Option<String>> {
+    let Some(id) = credential_id.map(str::trim).filter(|id| !id.is_empty()) else {
+      return self.get_token();
+    };
+    if !self.credentials.lock().iter().any(|credential| credential.id == id) {
+      return Err(anyhow::anyhow!("credential `{id}` does not exist"));
+    }
+    let token = self.load_token(Some(id))?;
+    if token.is_some() {
+      *self.active_source.lock() = Some(format!("{}:{id}", self.backend_label()));
+    }
+    Ok(token)
+  }
+
+  // Creates or replaces a named credential; `id` must already be a valid slug.
+  pub fn set_named_credential(&self, id: &str, name: &str, token: &str) -> Result<()> {
+    self.store_token(Some(id), token)?;
+    let mut credentials = self.credentials.lock();
+    match credentials.iter_mut().find(|credential| credential.id == id) {
+      Some(credential) => credential.name = name.to_string(),
+      None => credentials.push(CredentialSummary {
+        id: id.to_string(),
+        name: name.to_string(),
+        created_time: Utc::now().to_rfc3339(),
+      }),
+    }
+    self.persist_credential_index(&credentials)
+  }
+
+  pub fn delete_named_credential(&self, id: &str) -> Result<()> {
+    self.remove_token(Some(id))?;
+    let mut credentials = self.credentials.lock();
+    credentials.retain(|credential| credential.id != id);
+    self.persist_credential_index(&credentials)
+  }
+
+  fn persist_credential_index(&self, credentials: &[CredentialSummary]) -> Result<()> {
+    let path = self.config_dir.join(CREDENTIAL_INDEX_FILE_NAME);
+    let json = serde_json::to_string_pretty(credentials).context("failed to serialize credential index")?;
+    crate::config::write_file_atomic(&path, json.as_bytes())
+      .with_context(|| format!("failed to write {}", path.display()))
+  }
+
+  fn backend_label(&self) -> String {
+    match self.backend() {
+      TokenBackendKind::Keyring => "keyring".to_string(),
+      TokenBackendKind::EncryptedFile => "encryptedFile".to_string(),
+    }
+  }
+
+  fn read_source(&self, source: &TokenSource) -> Result<Option<String>> {
+    if !matches!(source, TokenSource::Command { .. }) {
+      return token_sources::read_token(source);
+    }
+    if let Some((cached_source, fetched, token)) = self.command_cache.lock().as_ref() {
+      if cached_source == source && fetched.elapsed() < COMMAND_TOKEN_CACHE_TTL {
+        return Ok(Some(token.clone()));
+      }
+    }
+    let token = token_sources::read_token(source)?;
+    *self.command_cache.lock() = token.clone().map(|token| (source.clone(), Instant::now(), token));
+    Ok(token)
+  }
+
+  fn store_token(&self, credential_id: Option<&str>, token: &str) -> Result<()> {
+    if self.backend() == TokenBackendKind::Keyring {
+      match self.entry(credential_id)?.set_password(token) {
+        Ok(()) => return Ok(()),
+        Err(error) if is_keyring_unavailable(&error) => {
+          eprintln!("OS keyring rejected the token, falling back to the {TOKEN_FILE_NAME} file store: {error}");
+          *self.backend.lock() = TokenBackendKind::EncryptedFile;
+        }
+        Err(error) => return Err(error).context("failed to store API token in secure keyring"),
+      }
+    }
+    self.write_token_file(credential_id, token)
+  }
+
+  fn load_token(&self, credential_id: Option<&str>) -> Result<Option<String>> {
+    if self.backend() == TokenBackendKind::EncryptedFile {
+      return self.read_token_file(credential_id);
+    }
+    let entry = self.entry(credential_id)?;
+    match entry.get_password() {
+      Ok(token) if token.trim().is_empty() => Ok(None),
+      Ok(token) => Ok(Some(token)),
+      Err(keyring::Error::NoEntry) => Ok(None),
+      Err(error) => Err(error).context("failed to read API token from secure keyring"),
+    }
+  }
+
+  fn remove_token(&self, credential_id: Option<&str>) -> Result<()> {
+    let token_file = self.token_file(credential_id);
+    if token_file.exists() {
+      std::fs::remove_file(&token_file).with_context(|| format!("failed to remove {}", token_file.display()))?;
+    }
+    if let Ok(entry) = self.entry(credential_id) {
+      let _ = entry.delete_credential();
+    }
     Ok(())
   }
+
+  fn write_token_file(&self, credential_id: Option<&str>, token: &str) -> Result<()> {
+    let (key_source, key) = token_file_key()?;
+    let file = TokenFile {
+      key_source,
+      secret: secret_box::seal(&key, token.as_bytes())?,
+    };
+    let token_file = self.token_file(credential_id);
+    let json = serde_json::to_string_pretty(&file).context("failed to serialize token file")?;
+    crate::config::write_private_file_atomic(&token_file, json.as_bytes())
+      .with_context(|| format!("failed to write {}", token_file.display()))
+  }
+
+  fn read_token_file(&self, credential_id: Option<&str>) -> Result<Option<String>> {
+    let token_file = self.token_file(credential_id);
+    let text = match std::fs::read_to_string(&token_file) {
+      Ok(text) => text,
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+      Err(error) => return Err(error).with_context(|| format!("failed to read {}", token_file.display())),
+    };
+    let file = serde_json::from_str::<TokenFile>(&text).context("failed to parse encrypted token file")?;
+    let (key_source, key) = token_file_key()?;
+    if key_source != file.key_source {
+      return Err(anyhow::anyhow!(
+        "encrypted token file was sealed with {:?} key material but {:?} is active; set or unset {TOKEN_PASSPHRASE_ENV}",
+        file.key_source,
+        key_source
+      ));
+    }
+    let token = secret_box::open(&key, &file.secret).context("failed to decrypt API token file")?;
+    let token = String::from_utf8(token).context("decrypted API token is not valid UTF-8")?;
+    Ok(Some(token).filter(|token| !token.trim().is_empty()))
+  }
+}
+
+// Credential IDs end up in keyring account names and file names, so only a conservative slug is accepted.
+pub fn is_valid_credential_id(id: &str) -> bool {
+  !id.is_empty()
+    && id.len() <= 64
+    && id
+      .chars()
+      .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_')
+}
+
+// Derives an ID such as `home-zone` from a display name; empty when the name has no ASCII letters or digits.
+pub fn credential_id_from_name(name: &str) -> String {
+  let mut id = String::new();
+  for ch in name.trim().chars() {
+    if ch.is_ascii_alphanumeric() {
+      id.push(ch.to_ascii_lowercase());
+    } else if !id.is_empty() && !id.ends_with('-') {
+      id.push('-');
+    }
+  }
+  let id = id.trim_end_matches('-');
+  id[..id.len().min(64)].trim_end_matches('-').to_string()
+}
+
+fn probe_keyring(entry: &Entry) -> Result<()> {
+  match entry.get_password() {
+    Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+    Err(error) => Err(error).context("keyring probe failed"),
+  }
+}
+
+fn is_keyring_unavailable(error: &keyring::Error) -> bool {
+  matches!(error, keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_))
+}
+
+// Only the passphrase is a secret. The machine-id key is obfuscation: /etc/machine-id is world-readable, so without
+// a passphrase the token is protected by nothing more than the file's 0600 mode.
+fn token_file_key() -> Result<(KeySource, String)> {
+  if let Ok(passphrase) = std::env::var(TOKEN_PASSPHRASE_ENV) {
+    if !passphrase.is_empty() {
+      return Ok((KeySource::Passphrase, passphrase));
+    }
+  }
+  let machine_id = read_machine_id()?;
+  Ok((KeySource::MachineId, format!("cf-ddns-ipv6:{machine_id}")))
+}
+
+#[cfg(target_os = "linux")]
+fn read_machine_id() -> Result<String> {
+  // machine-id(5): stable per installation; the D-Bus path is the legacy location.
+  ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+    .iter()
+    .filter_map(|path| std::fs::read_to_string(path).ok())
+    .map(|text| text.trim().to_string())
+    .find(|id| !id.is_empty())
+    .with_context(|| format!("no machine id available to protect the token file; set {TOKEN_PASSPHRASE_ENV}"))
+}
+
+// macOS and Windows always have a native keyring, so reaching the file store there means it is locked or broken;
+// a passphrase is required rather than inventing a machine-bound key.
+#[cfg(not(target_os = "linux"))]
+fn read_machine_id() -> Result<String> {
+  Err(anyhow::anyhow!(
+    "the system keyring is unavailable and the token file store on this platform needs {TOKEN_PASSPHRASE_ENV}; \
+     set it or unlock the keyring"
+  ))
 }
@@ -0,0 +1,236 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config;
+use crate::models::{AppSettings, SettingsChange, CONFIG_VERSION};
+use crate::secret_box::{self, SealedSecret};
+
+const BUNDLE_FORMAT: &str = "cf-ddns-ipv6-settings";
+
+// Portable settings file for moving a setup between machines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBundle {
+  pub format: String,
+  // Config schema version of `settings`, so older bundles go through the same migrations as settings.json.
+  pub config_version: u32,
+  pub app_version: String,
+  pub exported_time: String,
+  pub settings: Value,
+  #[serde(default)]
+  pub api_token: Option<SealedSecret>,
+}
+
+pub struct ImportedBundle {
+  pub settings: AppSettings,
+  pub exported_time: String,
+  pub app_version: String,
+  pub sealed_token: Option<SealedSecret>,
+}
+
+pub fn export_bundle(path: &Path, settings: &AppSettings, token: Option<(&str, &str)>) -> Result<()> {
+  let api_token = match token {
+    Some((passphrase, token)) => Some(secret_box::seal(passphrase, token.as_bytes())?),
+    None => None,
+  };
+  let bundle = SettingsBundle {
+    format: BUNDLE_FORMAT.to_string(),
+    config_version: CONFIG_VERSION,
+    app_version: env!("CARGO_PKG_VERSION").to_string(),
+    exported_time: Utc::now().to_rfc3339(),
+    settings: serde_json::to_value(settings).context("failed to serialize settings")?,
+    api_token,
+  };
+  let json = serde_json::to_string_pretty(&bundle).context("failed to serialize settings bundle")?;
+  config::write_file_atomic(path, json.as_bytes())
+    .with_context(|| format!("failed to write settings bundle to {}", path.display()))
+}
+
+pub fn read_bundle(path: &Path) -> Result<ImportedBundle> {
+  let text = std::fs::read_to_string(path)
+    .with_context(|| format!("failed to read settings bundle {}", path.display()))?;
+  let bundle = serde_json::from_str::<SettingsBundle>(&text).context("file is not a settings bundle")?;
+  if bundle.format != BUNDLE_FORMAT {
+    return Err(anyhow!("unexpected bundle format `{}`", bundle.format));
+  }
+
+  let document = serde_json::json!({
+    "version": bundle.config_version,
+    "settings": bundle.settings,
+  });
+  let (mut config, _) = config::parse_config_document(document).context("bundle settings are invalid")?;
+  config::normalize(&mut config);
+
+  Ok(ImportedBundle {
+    settings: config.settings,
+    exported_time: bundle.exported_time,
+    app_version: bundle.app_version,
+    sealed_token: bundle.api_token,
+  })
+}
+
+pub fn decrypt_token(sealed: &SealedSecret, passphrase: &str) -> Result<String> {
+  let bytes = secret_box::open(passphrase, sealed)?;
+  String::from_utf8(bytes).context("decrypted API token is not valid UTF-8")
+}
+
+// Flattens both settings to dotted JSON paths and lists every leaf that differs.
+pub fn diff_settings(current: &AppSettings, incoming: &AppSettings) -> Vec<SettingsChange> {
+  let mut current_leaves = Vec::new();
+  let mut incoming_leaves = Vec::new();
+  flatten("", &serde_json::to_value(current).unwrap_or_default(), &mut current_leaves);
+  flatten("", &serde_json::to_value(incoming).unwrap_or_default(), &mut incoming_leaves);
+
+  let mut paths = current_leaves
+    .iter()
+    .chain(incoming_leaves.iter())
+    .map(|(path, _)| path.clone())
+    .collect::<Vec<_>>();
+  paths.sort();
+  paths.dedup();
+
+  let lookup = |leaves: &[(String, Value)], path: &str| {
+    leaves
+      .iter()
+      .find(|(leaf_path, _)| leaf_path == path)
+      .map(|(_, value)| value.clone())
+      .unwrap_or(Value::Null)
+  };
+  paths
+    .into_iter()
+    .filter_map(|path| {
+      let from = lookup(&current_leaves, &path);
+      let to = lookup(&incoming_leaves, &path);
+      (from != to).then_some(SettingsChange { path, from, to })
+    })
+    .collect()
+}
+
+fn flatten(prefix: &str, value: &Value, leaves: &mut Vec<(String, Value)>) {
+  match value {
+    Value::Object(map) if !map.is_empty() => {
+      for (key, child) in map {
+        let path = if prefix.is_empty() {
+          key.clone()
+        } else {
+          format!("{prefix}.{key}")
+        };
+        flatten(&path, child, leaves);
+      }
+    }
+    // Lists such as services are compared as a whole; element-wise paths would shift on every insert.
+    _ => leaves.push((prefix.to_string(), value.clone())),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::models::RecordMode;
+
+  fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("cf-ddns-settings-bundle-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn sample_settings() -> AppSettings {
+    let mut settings = AppSettings {
+      selected_interface: Some("eth0".to_string()),
+      ..Default::default()
+    };
+    settings.cloudflare.zone_id = "023e105f4ecef8ad9ca31a8372d0c353".to_string();
+    settings.cloudflare.domain = "home.example.com".to_string();
+    settings.cloudflare.ttl = Some(120);
+    settings
+  }
+
+  #[test]
+  fn exported_settings_import_unchanged_without_a_token() {
+    let dir = temp_dir("plain");
+    let path = dir.join("bundle.json");
+    let settings = sample_settings();
+    export_bundle(&path, &settings, None).unwrap();
+
+    let imported = read_bundle(&path).unwrap();
+    assert_eq!(
+      serde_json::to_value(&imported.settings).unwrap(),
+      serde_json::to_value(&settings).unwrap()
+    );
+    assert!(diff_settings(&settings, &imported.settings).is_empty());
+    assert_eq!(imported.app_version, env!("CARGO_PKG_VERSION"));
+    assert!(imported.sealed_token.is_none());
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn bundled_token_needs_the_export_passphrase() {
+    let dir = temp_dir("token");
+    let path = dir.join("bundle.json");
+    export_bundle(&path, &sample_settings(), Some(("correct horse", "api-token"))).unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(!text.contains("api-token"), "token must not be stored in clear text");
+
+    let sealed = read_bundle(&path).unwrap().sealed_token.expect("sealed token");
+    assert_eq!(decrypt_token(&sealed, "correct horse").unwrap(), "api-token");
+    assert!(decrypt_token(&sealed, "battery staple").is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn foreign_files_are_rejected() {
+    let dir = temp_dir("foreign");
+    let path = dir.join("bundle.json");
+    let mut bundle = serde_json::to_value(SettingsBundle {
+      format: BUNDLE_FORMAT.to_string(),
+      config_version: CONFIG_VERSION,
+      app_version: "0.0.0".to_string(),
+      exported_time: Utc::now().to_rfc3339(),
+      settings: serde_json::to_value(sample_settings()).unwrap(),
+      api_token: None,
+    })
+    .unwrap();
+    bundle["format"] = json!("something-else");
+    std::fs::write(&path, bundle.to_string()).unwrap();
+    assert!(read_bundle(&path).is_err());
+
+    std::fs::write(&path, r#"{"settings": {}}"#).unwrap();
+    assert!(read_bundle(&path).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn preview_lists_each_changed_leaf() {
+    let current = sample_settings();
+    let mut incoming = current.clone();
+    incoming.cloudflare.domain = "nas.example.com".to_string();
+    incoming.cloudflare.ttl = None;
+    incoming.cloudflare.record_mode = RecordMode::MultiValue;
+    incoming.selected_interface = None;
+
+    let changes = diff_settings(&current, &incoming)
+      .into_iter()
+      .map(|change| (change.path, change.from, change.to))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      changes,
+      vec![
+        (
+          "cloudflare.domain".to_string(),
+          json!("home.example.com"),
+          json!("nas.example.com")
+        ),
+        ("cloudflare.recordMode".to_string(), json!("single"), json!("multiValue")),
+        ("cloudflare.ttl".to_string(), json!(120), Value::Null),
+        ("selectedInterface".to_string(), json!("eth0"), Value::Null),
+      ]
+    );
+  }
+}
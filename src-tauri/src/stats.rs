@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::models::{
+  AddressUptime, DailyPushCount, HistoryEntry, HistoryEventKind, HistoryStats, LifetimeBucket, LifetimeDistribution,
+  PrefixRecord,
+};
+use crate::prefix_history;
+
+// Upper bounds of the prefix lifetime histogram; ISPs typically rotate daily, weekly or on reconnect.
+const LIFETIME_BUCKETS: [(&str, i64); 4] = [
+  ("< 1 hour", 3_600),
+  ("< 1 day", 86_400),
+  ("< 7 days", 7 * 86_400),
+  ("< 30 days", 30 * 86_400),
+];
+const CSV_HEADER: &str = "time,kind,addresses,previousAddresses,domain,latencyMs,errorCode,message";
+
+// `entries` must be oldest first, as returned by `HistoryStore::entries`.
+pub fn compute(prefixes: &[PrefixRecord], entries: &[HistoryEntry], now: DateTime<Utc>) -> HistoryStats {
+  let timed = entries
+    .iter()
+    .filter_map(|entry| Some((parse_time(&entry.time)?, entry)))
+    .collect::<Vec<_>>();
+  let pushes = timed
+    .iter()
+    .filter(|(_, entry)| entry.kind != HistoryEventKind::AddressChanged)
+    .collect::<Vec<_>>();
+
+  let mut pushes_per_day = BTreeMap::<String, DailyPushCount>::new();
+  let mut failures_by_code = BTreeMap::<String, usize>::new();
+  for (time, entry) in &pushes {
+    let date = time.date_naive().to_string();
+    let day = pushes_per_day.entry(date.clone()).or_insert(DailyPushCount {
+      date,
+      succeeded: 0,
+      failed: 0,
+    });
+    if entry.kind == HistoryEventKind::PushSucceeded {
+      day.succeeded += 1;
+    } else {
+      day.failed += 1;
+      let code = entry.error_code.clone().unwrap_or_else(|| "other".to_string());
+      *failures_by_code.entry(code).or_default() += 1;
+    }
+  }
+  let push_failures = failures_by_code.values().sum::<usize>();
+  let latencies = pushes.iter().filter_map(|(_, entry)| entry.latency_ms).collect::<Vec<_>>();
+  let update_delays = update_delays(&timed);
+
+  HistoryStats {
+    generated_time: now.to_rfc3339(),
+    period_start: timed.first().map(|(time, _)| time.to_rfc3339()),
+    prefix_lifetimes: lifetime_distribution(prefixes, now),
+    pushes_per_day: pushes_per_day.into_values().collect(),
+    push_attempts: pushes.len(),
+    push_failures,
+    failure_rate: (!pushes.is_empty()).then_some(push_failures as f64 / pushes.len() as f64),
+    failures_by_code,
+    mean_push_latency_ms: mean(latencies.iter().map(|latency| *latency as f64)),
+    mean_time_to_update_seconds: mean(update_delays.iter().map(|delay| *delay as f64)),
+    updates_measured: update_delays.len(),
+    address_uptime: address_uptime(&timed, now),
+  }
+}
+
+fn lifetime_distribution(prefixes: &[PrefixRecord], now: DateTime<Utc>) -> LifetimeDistribution {
+  let mut lifetimes = prefixes
+    .iter()
+    .filter(|record| record.ended_time.is_some())
+    .filter_map(prefix_history::lifetime_seconds)
+    .collect::<Vec<_>>();
+  lifetimes.sort_unstable();
+
+  let mut buckets = LIFETIME_BUCKETS
+    .iter()
+    .map(|(label, up_to)| LifetimeBucket {
+      label: label.to_string(),
+      up_to_seconds: Some(*up_to),
+      count: 0,
+    })
+    .collect::<Vec<_>>();
+  buckets.push(LifetimeBucket {
+    label: ">= 30 days".to_string(),
+    up_to_seconds: None,
+    count: 0,
+  });
+  for lifetime in &lifetimes {
+    let index = LIFETIME_BUCKETS
+      .iter()
+      .position(|(_, up_to)| lifetime < up_to)
+      .unwrap_or(LIFETIME_BUCKETS.len());
+    buckets[index].count += 1;
+  }
+
+  // Age of the active prefix counts up to now, not to the last persisted `lastSeenTime`.
+  let current_age_seconds = prefixes
+    .last()
+    .filter(|record| record.ended_time.is_none())
+    .and_then(|record| parse_time(&record.first_seen_time))
+    .map(|first_seen| (now - first_seen).num_seconds());
+
+  LifetimeDistribution {
+    completed: lifetimes.len(),
+    min_seconds: lifetimes.first().copied(),
+    median_seconds: (!lifetimes.is_empty()).then(|| lifetimes[lifetimes.len() / 2]),
+    mean_seconds: mean(lifetimes.iter().map(|lifetime| *lifetime as f64)),
+    max_seconds: lifetimes.last().copied(),
+    current_age_seconds,
+    buckets,
+  }
+}
+
+// Seconds from each address change to the first successful push of exactly that set. Changes superseded by
+// another change before any such push are left out rather than counted up to the later push.
+fn update_delays(timed: &[(DateTime<Utc>, &HistoryEntry)]) -> Vec<i64> {
+  let mut delays = Vec::new();
+  let mut pending: Option<(DateTime<Utc>, &[String])> = None;
+  for (time, entry) in timed {
+    match entry.kind {
+      HistoryEventKind::AddressChanged => pending = Some((*time, entry.addresses.as_slice())),
+      HistoryEventKind::PushSucceeded => {
+        if let Some((changed, addresses)) = pending {
+          if entry.addresses == addresses {
+            delays.push((*time - changed).num_seconds());
+            pending = None;
+          }
+        }
+      }
+      HistoryEventKind::PushFailed => {}
+    }
+  }
+  delays
+}
+
+// Uptime is only known from the first recorded change on, since earlier sets were never logged.
+fn address_uptime(timed: &[(DateTime<Utc>, &HistoryEntry)], now: DateTime<Utc>) -> Vec<AddressUptime> {
+  let mut uptime = Vec::<AddressUptime>::new();
+  let mut index_of = HashMap::<String, usize>::new();
+  // Address -> start of its current stint in the committed set.
+  let mut active = HashMap::<String, DateTime<Utc>>::new();
+
+  let changes = timed
+    .iter()
+    .filter(|(_, entry)| entry.kind == HistoryEventKind::AddressChanged);
+  for (time, entry) in changes {
+    let left = active
+      .keys()
+      .filter(|address| !entry.addresses.contains(*address))
+      .cloned()
+      .collect::<Vec<_>>();
+    for address in left {
+      if let Some(since) = active.remove(&address) {
+        let record = &mut uptime[index_of[&address]];
+        record.uptime_seconds += (*time - since).num_seconds();
+        record.last_seen_time = Some(time.to_rfc3339());
+      }
+    }
+    for address in &entry.addresses {
+      if active.contains_key(address) {
+        continue;
+      }
+      active.insert(address.clone(), *time);
+      let index = *index_of.entry(address.clone()).or_insert_with(|| {
+        uptime.push(AddressUptime {
+          address: address.clone(),
+          first_seen_time: time.to_rfc3339(),
+          last_seen_time: None,
+          uptime_seconds: 0,
+        });
+        uptime.len() - 1
+      });
+      uptime[index].last_seen_time = None;
+    }
+  }
+  for (address, since) in active {
+    uptime[index_of[&address]].uptime_seconds += (now - since).num_seconds();
+  }
+  uptime
+}
+
+pub fn export_csv(entries: &[HistoryEntry]) -> String {
+  let mut csv = String::from(CSV_HEADER);
+  csv.push_str("\r\n");
+  for entry in entries {
+    let kind = match entry.kind {
+      HistoryEventKind::AddressChanged => "addressChanged",
+      HistoryEventKind::PushSucceeded => "pushSucceeded",
+      HistoryEventKind::PushFailed => "pushFailed",
+    };
+    let fields = [
+      entry.time.clone(),
+      kind.to_string(),
+      entry.addresses.join(" "),
+      entry.previous_addresses.join(" "),
+      entry.domain.clone().unwrap_or_default(),
+      entry.latency_ms.map(|latency| latency.to_string()).unwrap_or_default(),
+      entry.error_code.clone().unwrap_or_default(),
+      entry.message.clone().unwrap_or_default(),
+    ];
+    let row = fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>();
+    csv.push_str(&row.join(","));
+    csv.push_str("\r\n");
+  }
+  csv
+}
+
+pub fn export_json(stats: &HistoryStats, prefixes: &[PrefixRecord], entries: &[HistoryEntry]) -> Result<String> {
+  let document = json!({
+    "stats": stats,
+    "prefixes": prefixes,
+    "entries": entries,
+  });
+  serde_json::to_string_pretty(&document).context("failed to serialize history export")
+}
+
+// RFC 4180: quote fields containing separators, quotes or line breaks, doubling embedded quotes. Fields a spreadsheet
+// would evaluate as a formula (error messages are server-supplied) get a leading `'` so they open as text.
+fn csv_field(value: &str) -> String {
+  let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+    format!("'{value}")
+  } else {
+    value.to_string()
+  };
+  if value.contains([',', '"', '\r', '\n']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value
+  }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+  DateTime::parse_from_rfc3339(value)
+    .ok()
+    .map(|time| time.with_timezone(&Utc))
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+  let (sum, count) = values.fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+  (count > 0).then_some(sum / count as f64)
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{Duration, TimeZone};
+
+  use super::*;
+
+  fn at(seconds: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::seconds(seconds)
+  }
+
+  fn entry(seconds: i64, kind: HistoryEventKind, addresses: &[&str]) -> HistoryEntry {
+    HistoryEntry {
+      time: at(seconds).to_rfc3339(),
+      kind,
+      addresses: addresses.iter().map(|address| address.to_string()).collect(),
+      previous_addresses: Vec::new(),
+      domain: None,
+      latency_ms: None,
+      error_code: None,
+      message: None,
+    }
+  }
+
+  fn timed(entries: &[HistoryEntry]) -> Vec<(DateTime<Utc>, &HistoryEntry)> {
+    entries
+      .iter()
+      .map(|entry| (parse_time(&entry.time).unwrap(), entry))
+      .collect()
+  }
+
+  fn ended_prefix(lifetime_seconds: i64) -> PrefixRecord {
+    PrefixRecord {
+      prefix: "2001:db8:1::/64".to_string(),
+      prefix_len: 64,
+      first_seen_time: at(0).to_rfc3339(),
+      last_seen_time: at(lifetime_seconds).to_rfc3339(),
+      ended_time: Some(at(lifetime_seconds).to_rfc3339()),
+    }
+  }
+
+  #[test]
+  fn lifetimes_fall_into_the_bucket_below_their_exclusive_bound() {
+    let cases = [
+      (0, 0),
+      (3_599, 0),
+      (3_600, 1),
+      (86_399, 1),
+      (86_400, 2),
+      (7 * 86_400 - 1, 2),
+      (7 * 86_400, 3),
+      (30 * 86_400 - 1, 3),
+      (30 * 86_400, 4),
+      (400 * 86_400, 4),
+    ];
+    for (lifetime, bucket) in cases {
+      let distribution = lifetime_distribution(&[ended_prefix(lifetime)], at(500 * 86_400));
+      let counts = distribution.buckets.iter().map(|bucket| bucket.count).collect::<Vec<_>>();
+      let mut expected = vec![0; LIFETIME_BUCKETS.len() + 1];
+      expected[bucket] = 1;
+      assert_eq!(counts, expected, "lifetime {lifetime}");
+    }
+  }
+
+  #[test]
+  fn the_active_prefix_only_reports_its_age() {
+    let mut active = ended_prefix(0);
+    active.first_seen_time = at(1_000).to_rfc3339();
+    active.ended_time = None;
+    let distribution = lifetime_distribution(&[ended_prefix(7_200), active], at(5_000));
+    assert_eq!(distribution.completed, 1);
+    assert_eq!(distribution.median_seconds, Some(7_200));
+    assert_eq!(distribution.current_age_seconds, Some(4_000));
+  }
+
+  #[test]
+  fn update_delays_skip_superseded_changes() {
+    use HistoryEventKind::{AddressChanged, PushFailed, PushSucceeded};
+    let cases: [(&str, Vec<HistoryEntry>, Vec<i64>); 5] = [
+      (
+        "pushed once",
+        vec![entry(0, AddressChanged, &["a"]), entry(12, PushSucceeded, &["a"])],
+        vec![12],
+      ),
+      (
+        "superseded before any push",
+        vec![
+          entry(0, AddressChanged, &["a"]),
+          entry(10, AddressChanged, &["b"]),
+          entry(30, PushSucceeded, &["b"]),
+        ],
+        vec![20],
+      ),
+      (
+        "push of the superseded set",
+        vec![
+          entry(0, AddressChanged, &["a"]),
+          entry(10, AddressChanged, &["b"]),
+          entry(15, PushSucceeded, &["a"]),
+          entry(40, PushSucceeded, &["b"]),
+        ],
+        vec![30],
+      ),
+      (
+        "failures until the retry succeeds",
+        vec![
+          entry(0, AddressChanged, &["a", "b"]),
+          entry(5, PushFailed, &["a", "b"]),
+          entry(65, PushSucceeded, &["a", "b"]),
+        ],
+        vec![65],
+      ),
+      (
+        "heartbeat pushes without a change",
+        vec![
+          entry(0, AddressChanged, &["a"]),
+          entry(3, PushSucceeded, &["a"]),
+          entry(3_600, PushSucceeded, &["a"]),
+        ],
+        vec![3],
+      ),
+    ];
+    for (name, entries, expected) in cases {
+      assert_eq!(update_delays(&timed(&entries)), expected, "{name}");
+    }
+  }
+
+  #[test]
+  fn address_uptime_adds_up_every_stint() {
+    use HistoryEventKind::{AddressChanged, PushSucceeded};
+    let entries = [
+      entry(0, AddressChanged, &["a"]),
+      entry(5, PushSucceeded, &["a"]),
+      entry(100, AddressChanged, &["b"]),
+      entry(300, AddressChanged, &["a", "b"]),
+      entry(350, AddressChanged, &["b"]),
+    ];
+    let uptime = address_uptime(&timed(&entries), at(400));
+
+    assert_eq!(uptime.len(), 2);
+    let a = &uptime[0];
+    assert_eq!(a.address, "a");
+    assert_eq!(a.first_seen_time, at(0).to_rfc3339());
+    assert_eq!(a.uptime_seconds, 100 + 50);
+    assert_eq!(a.last_seen_time, Some(at(350).to_rfc3339()));
+    let b = &uptime[1];
+    assert_eq!(b.first_seen_time, at(100).to_rfc3339());
+    assert_eq!(b.uptime_seconds, 300);
+    assert_eq!(b.last_seen_time, None);
+  }
+
+  #[test]
+  fn csv_fields_are_quoted_and_kept_as_text() {
+    let cases = [
+      ("2001:db8::1", "2001:db8::1"),
+      ("", ""),
+      ("a,b", "\"a,b\""),
+      ("say \"hi\"", "\"say \"\"hi\"\"\""),
+      ("line\r\nbreak", "\"line\r\nbreak\""),
+      ("=HYPERLINK(\"http://x\")", "\"'=HYPERLINK(\"\"http://x\"\")\""),
+      ("+1", "'+1"),
+      ("-cmd", "'-cmd"),
+      ("@SUM(A1)", "'@SUM(A1)"),
+      ("=1,2", "\"'=1,2\""),
+      ("a=b", "a=b"),
+      ("\t=cmd", "'\t=cmd"),
+      ("\rcmd", "\"'\rcmd\""),
+      ("a\tb", "a\tb"),
+    ];
+    for (value, expected) in cases {
+      assert_eq!(csv_field(value), expected, "{value:?}");
+    }
+  }
+
+  #[test]
+  fn csv_export_writes_one_crlf_row_per_entry() {
+    let mut failed = entry(60, HistoryEventKind::PushFailed, &["2001:db8::1", "2001:db8::2"]);
+    failed.domain = Some("home.example.com".to_string());
+    failed.latency_ms = Some(840);
+    failed.error_code = Some("http403".to_string());
+    failed.message = Some("=cmd|' /C calc'!A0, denied".to_string());
+    let csv = export_csv(&[failed]);
+    let rows = csv.split("\r\n").collect::<Vec<_>>();
+    assert_eq!(rows[0], CSV_HEADER);
+    assert_eq!(
+      rows[1],
+      format!(
+        "{},pushFailed,2001:db8::1 2001:db8::2,,home.example.com,840,http403,\"'=cmd|' /C calc'!A0, denied\"",
+        at(60).to_rfc3339()
+      )
+    );
+    assert_eq!(rows[2], "");
+  }
+}
@@ -0,0 +1,369 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::{PendingPushInfo, PendingPushReason, SyncPolicySettings};
+
+// Tracks the settle window for freshly observed addresses and the hold-down between pushes.
+#[derive(Default)]
+pub struct PushScheduler {
+  pending: Option<PendingObservation>,
+  queued: Option<QueuedPush>,
+  last_push_at: Option<DateTime<Utc>>,
+}
+
+struct PendingObservation {
+  addresses: Vec<String>,
+  first_seen: DateTime<Utc>,
+}
+
+struct QueuedPush {
+  addresses: Vec<String>,
+  queued_at: DateTime<Utc>,
+  heartbeat: bool,
+}
+
+pub struct Observation {
+  pub commit: bool,
+  pub recheck_after: Option<Duration>,
+}
+
+pub enum PushDecision {
+  Idle,
+  Ready(Vec<String>),
+  Wait(Duration),
+}
+
+impl PushScheduler {
+  // A differing address set only commits after it was observed continuously for the settle window.
+  // Sets are compared without regard to order, so rank shuffles alone never trigger a push.
+  pub fn observe(
+    &mut self,
+    committed: &[String],
+    current: &[String],
+    policy: &SyncPolicySettings,
+    now: DateTime<Utc>,
+  ) -> Observation {
+    if same_addresses(committed, current) {
+      // The address flapped back before settling; nothing to push.
+      self.pending = None;
+      return Observation {
+        commit: false,
+        recheck_after: None,
+      };
+    }
+
+    let settle = policy.settle_window();
+    let first_seen = match &self.pending {
+      Some(pending) if same_addresses(&pending.addresses, current) => pending.first_seen,
+      _ => {
+        self.pending = Some(PendingObservation {
+          addresses: current.to_vec(),
+          first_seen: now,
+        });
+        now
+      }
+    };
+
+    let elapsed = now - first_seen;
+    if elapsed >= settle {
+      self.pending = None;
+      return Observation {
+        commit: true,
+        recheck_after: None,
+      };
+    }
+    Observation {
+      commit: false,
+      recheck_after: Some(settle - elapsed),
+    }
+  }
+
+  // An empty set clears any queued push; vanished addresses are never pushed.
+  pub fn queue_push(&mut self, addresses: Vec<String>, now: DateTime<Utc>) {
+    self.queued = (!addresses.is_empty()).then_some(QueuedPush {
+      addresses,
+      queued_at: now,
+      heartbeat: false,
+    });
+  }
+
+  // Re-pushes the committed address once the heartbeat interval has passed since the last successful push, so
+  // records deleted or edited outside the app are restored. Failed pushes do not reset the interval, so a due
+  // heartbeat keeps being retried at the hold-down pace. Returns the time until the next heartbeat.
+  pub fn queue_heartbeat_if_due(
+    &mut self,
+    committed: &[String],
+    last_success_time: Option<&str>,
+    policy: &SyncPolicySettings,
+    now: DateTime<Utc>,
+  ) -> Option<Duration> {
+    let interval = policy.heartbeat_interval()?;
+    if committed.is_empty() || self.pending.is_some() || self.queued.is_some() {
+      return None;
+    }
+    // Only records that were pushed successfully at least once are kept alive.
+    let last_success = DateTime::parse_from_rfc3339(last_success_time?).ok()?.with_timezone(&Utc);
+    let due = saturating_add(last_success, interval);
+    if due > now {
+      return Some(due - now);
+    }
+    self.queued = Some(QueuedPush {
+      addresses: committed.to_vec(),
+      queued_at: now,
+      heartbeat: true,
+    });
+    None
+  }
+
+  pub fn poll_push(&mut self, policy: &SyncPolicySettings, now: DateTime<Utc>) -> PushDecision {
+    if self.queued.is_none() {
+      return PushDecision::Idle;
+    }
+    if let Some(due) = self.next_push_allowed_at(policy) {
+      if due > now {
+        return PushDecision::Wait(due - now);
+      }
+    }
+    match self.queued.take() {
+      Some(queued) => PushDecision::Ready(queued.addresses),
+      None => PushDecision::Idle,
+    }
+  }
+
+  pub fn record_push(&mut self, addresses: &[String], now: DateTime<Utc>) {
+    // Every push attempt (manual or automatic) restarts the hold-down window.
+    self.last_push_at = Some(now);
+    if self
+      .queued
+      .as_ref()
+      .is_some_and(|queued| same_addresses(&queued.addresses, addresses))
+    {
+      self.queued = None;
+    }
+  }
+
+  pub fn pending_info(&self, policy: &SyncPolicySettings) -> Option<PendingPushInfo> {
+    if let Some(pending) = &self.pending {
+      return Some(PendingPushInfo {
+        ipv6: pending.addresses.first().cloned(),
+        addresses: pending.addresses.clone(),
+        reason: PendingPushReason::Settling,
+        first_seen_time: pending.first_seen.to_rfc3339(),
+        due_time: saturating_add(pending.first_seen, policy.settle_window()).to_rfc3339(),
+      });
+    }
+    let queued = self.queued.as_ref()?;
+    let due = self.next_push_allowed_at(policy).unwrap_or(queued.queued_at);
+    Some(PendingPushInfo {
+      ipv6: queued.addresses.first().cloned(),
+      addresses: queued.addresses.clone(),
+      reason: if queued.heartbeat {
+        PendingPushReason::Heartbeat
+      } else {
+        PendingPushReason::RateLimited
+      },
+      first_seen_time: queued.queued_at.to_rfc3339(),
+      due_time: due.max(queued.queued_at).to_rfc3339(),
+    })
+  }
+
+  fn next_push_allowed_at(&self, policy: &SyncPolicySettings) -> Option<DateTime<Utc>> {
+    self
+      .last_push_at
+      .map(|last| saturating_add(last, policy.min_push_interval()))
+  }
+}
+
+// Compares address sets regardless of order.
+pub fn same_addresses(a: &[String], b: &[String]) -> bool {
+  if a.len() != b.len() {
+    return false;
+  }
+  let mut a = a.iter().collect::<Vec<_>>();
+  let mut b = b.iter().collect::<Vec<_>>();
+  a.sort();
+  b.sort();
+  a == b
+}
+
+fn saturating_add(time: DateTime<Utc>, delta: Duration) -> DateTime<Utc> {
+  time.checked_add_signed(delta).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::*;
+
+  fn at(seconds: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap() + Duration::seconds(seconds)
+  }
+
+  fn policy() -> SyncPolicySettings {
+    SyncPolicySettings {
+      settle_seconds: 60,
+      min_push_interval_seconds: 300,
+      poll_interval_seconds: 0,
+      heartbeat_interval_hours: 0,
+    }
+  }
+
+  fn set(addresses: &[&str]) -> Vec<String> {
+    addresses.iter().map(|address| address.to_string()).collect()
+  }
+
+  #[test]
+  fn a_new_set_commits_after_the_settle_window() {
+    let mut scheduler = PushScheduler::default();
+    let committed = set(&["2001:db8::1"]);
+    let current = set(&["2001:db8::2"]);
+
+    let first = scheduler.observe(&committed, &current, &policy(), at(0));
+    assert!(!first.commit);
+    assert_eq!(first.recheck_after, Some(Duration::seconds(60)));
+    let halfway = scheduler.observe(&committed, &current, &policy(), at(45));
+    assert!(!halfway.commit);
+    assert_eq!(halfway.recheck_after, Some(Duration::seconds(15)));
+    assert!(scheduler.observe(&committed, &current, &policy(), at(60)).commit);
+    // The committed set is what the caller compares against next; nothing stays pending.
+    assert!(scheduler.pending_info(&policy()).is_none());
+  }
+
+  #[test]
+  fn flapping_restarts_the_settle_window() {
+    let mut scheduler = PushScheduler::default();
+    let committed = set(&["2001:db8::1"]);
+    let a = set(&["2001:db8::2"]);
+    let b = set(&["2001:db8::3"]);
+
+    scheduler.observe(&committed, &a, &policy(), at(0));
+    // A different new set starts its own window.
+    let switched = scheduler.observe(&committed, &b, &policy(), at(50));
+    assert!(!switched.commit);
+    assert_eq!(switched.recheck_after, Some(Duration::seconds(60)));
+    // Flapping back to the committed set drops the pending observation entirely.
+    let back = scheduler.observe(&committed, &committed, &policy(), at(70));
+    assert!(!back.commit);
+    assert_eq!(back.recheck_after, None);
+    assert!(scheduler.pending_info(&policy()).is_none());
+    let again = scheduler.observe(&committed, &b, &policy(), at(80));
+    assert_eq!(again.recheck_after, Some(Duration::seconds(60)));
+    assert!(!scheduler.observe(&committed, &b, &policy(), at(139)).commit);
+    assert!(scheduler.observe(&committed, &b, &policy(), at(140)).commit);
+  }
+
+  #[test]
+  fn reordering_the_same_addresses_is_not_a_change() {
+    let mut scheduler = PushScheduler::default();
+    let committed = set(&["2001:db8::1", "2001:db8::2"]);
+    let reordered = set(&["2001:db8::2", "2001:db8::1"]);
+    let observation = scheduler.observe(&committed, &reordered, &policy(), at(0));
+    assert!(!observation.commit);
+    assert_eq!(observation.recheck_after, None);
+  }
+
+  #[test]
+  fn pushes_are_held_down_after_a_push() {
+    let mut scheduler = PushScheduler::default();
+    assert!(matches!(scheduler.poll_push(&policy(), at(0)), PushDecision::Idle));
+
+    scheduler.queue_push(set(&["2001:db8::1"]), at(0));
+    match scheduler.poll_push(&policy(), at(0)) {
+      PushDecision::Ready(addresses) => assert_eq!(addresses, set(&["2001:db8::1"])),
+      _ => panic!("the first push is not held down"),
+    }
+    scheduler.record_push(&set(&["2001:db8::1"]), at(0));
+
+    scheduler.queue_push(set(&["2001:db8::2"]), at(100));
+    match scheduler.poll_push(&policy(), at(100)) {
+      PushDecision::Wait(delay) => assert_eq!(delay, Duration::seconds(200)),
+      _ => panic!("a push inside the hold-down window must wait"),
+    }
+    assert!(matches!(scheduler.poll_push(&policy(), at(300)), PushDecision::Ready(_)));
+    assert!(matches!(scheduler.poll_push(&policy(), at(301)), PushDecision::Idle));
+  }
+
+  #[test]
+  fn recording_a_manual_push_clears_a_matching_queued_push() {
+    let mut scheduler = PushScheduler::default();
+    scheduler.queue_push(set(&["2001:db8::1", "2001:db8::2"]), at(0));
+    scheduler.record_push(&set(&["2001:db8::2", "2001:db8::1"]), at(10));
+    assert!(matches!(scheduler.poll_push(&policy(), at(1_000)), PushDecision::Idle));
+
+    scheduler.queue_push(set(&["2001:db8::3"]), at(20));
+    scheduler.record_push(&set(&["2001:db8::1"]), at(30));
+    assert!(matches!(scheduler.poll_push(&policy(), at(30)), PushDecision::Wait(_)));
+  }
+
+  #[test]
+  fn an_empty_set_cancels_the_queued_push() {
+    let mut scheduler = PushScheduler::default();
+    scheduler.queue_push(set(&["2001:db8::1"]), at(0));
+    scheduler.queue_push(Vec::new(), at(5));
+    assert!(matches!(scheduler.poll_push(&policy(), at(5)), PushDecision::Idle));
+    assert!(scheduler.pending_info(&policy()).is_none());
+  }
+
+  #[test]
+  fn a_failed_push_does_not_postpone_the_heartbeat() {
+    let mut policy = policy();
+    policy.heartbeat_interval_hours = 1;
+    let committed = set(&["2001:db8::1"]);
+    let mut scheduler = PushScheduler::default();
+    let last_success = at(0).to_rfc3339();
+
+    assert!(scheduler.queue_heartbeat_if_due(&[], Some(&last_success), &policy, at(0)).is_none());
+    assert!(scheduler.queue_heartbeat_if_due(&committed, None, &policy, at(9_000)).is_none());
+    assert!(matches!(scheduler.poll_push(&policy, at(9_000)), PushDecision::Idle));
+    assert_eq!(
+      scheduler.queue_heartbeat_if_due(&committed, Some(&last_success), &policy, at(600)),
+      Some(Duration::seconds(3_000))
+    );
+
+    // Due at 3600; the push fails, so the last success stays at 0 and the heartbeat is queued again.
+    assert!(scheduler.queue_heartbeat_if_due(&committed, Some(&last_success), &policy, at(3_600)).is_none());
+    assert!(matches!(scheduler.poll_push(&policy, at(3_600)), PushDecision::Ready(_)));
+    scheduler.record_push(&committed, at(3_600));
+    assert!(scheduler.queue_heartbeat_if_due(&committed, Some(&last_success), &policy, at(3_700)).is_none());
+    let retry = scheduler.pending_info(&policy).unwrap();
+    assert!(matches!(retry.reason, PendingPushReason::Heartbeat));
+    assert_eq!(retry.due_time, at(3_900).to_rfc3339());
+    assert!(matches!(scheduler.poll_push(&policy, at(3_900)), PushDecision::Ready(_)));
+
+    // Once a push succeeds, the next heartbeat is a full interval away.
+    scheduler.record_push(&committed, at(3_900));
+    let succeeded = at(3_900).to_rfc3339();
+    assert_eq!(
+      scheduler.queue_heartbeat_if_due(&committed, Some(&succeeded), &policy, at(4_000)),
+      Some(Duration::seconds(3_500))
+    );
+  }
+
+  #[test]
+  fn pending_info_reports_settling_then_rate_limited() {
+    let mut scheduler = PushScheduler::default();
+    let committed = set(&["2001:db8::1"]);
+    let current = set(&["2001:db8::2", "2001:db8::3"]);
+
+    scheduler.observe(&committed, &current, &policy(), at(10));
+    let settling = scheduler.pending_info(&policy()).unwrap();
+    assert!(matches!(settling.reason, PendingPushReason::Settling));
+    assert_eq!(settling.ipv6.as_deref(), Some("2001:db8::2"));
+    assert_eq!(settling.addresses, current);
+    assert_eq!(settling.first_seen_time, at(10).to_rfc3339());
+    assert_eq!(settling.due_time, at(70).to_rfc3339());
+
+    scheduler.record_push(&committed, at(40));
+    assert!(scheduler.observe(&committed, &current, &policy(), at(70)).commit);
+    scheduler.queue_push(current.clone(), at(70));
+    let limited = scheduler.pending_info(&policy()).unwrap();
+    assert!(matches!(limited.reason, PendingPushReason::RateLimited));
+    assert_eq!(limited.first_seen_time, at(70).to_rfc3339());
+    assert_eq!(limited.due_time, at(340).to_rfc3339());
+
+    // Without an earlier push the queued push is due the moment it was queued.
+    let mut fresh = PushScheduler::default();
+    fresh.queue_push(current, at(500));
+    assert_eq!(fresh.pending_info(&policy()).unwrap().due_time, at(500).to_rfc3339());
+  }
+}
@@ -0,0 +1,223 @@
+use std::{path::Path, time::Duration};
+
+// Generous enough for a detection cycle with reachability probes and a Cloudflare round trip.
+const WATCHDOG_SEC: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitScope {
+  User,
+  System,
+}
+
+pub struct UnitOptions<'a> {
+  pub scope: UnitScope,
+  pub executable: &'a Path,
+  pub config_dir: &'a Path,
+  // Account a system unit runs as; user units always run as their owner.
+  pub run_as: Option<&'a str>,
+}
+
+// sd_notify(3): newline-separated assignments sent as one datagram to `$NOTIFY_SOCKET`.
+// Does nothing unless the service manager asked for notifications (`Type=notify`).
+pub fn notify(message: &str) {
+  if let Err(error) = send(message) {
+    eprintln!("failed to notify systemd: {error}");
+  }
+}
+
+#[cfg(target_os = "linux")]
+fn send(message: &str) -> std::io::Result<()> {
+  use std::os::linux::net::SocketAddrExt;
+  use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+  let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+    return Ok(());
+  };
+  let path = path.to_string_lossy();
+  let socket = UnixDatagram::unbound()?;
+  // A leading `@` names a socket in the abstract namespace.
+  match path.strip_prefix('@') {
+    Some(name) => socket.send_to_addr(message.as_bytes(), &SocketAddr::from_abstract_name(name.as_bytes())?)?,
+    None => socket.send_to(message.as_bytes(), &*path)?,
+  };
+  Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send(_message: &str) -> std::io::Result<()> {
+  Ok(())
+}
+
+// sd_watchdog_enabled(3): the timeout from `$WATCHDOG_USEC`, unless `$WATCHDOG_PID` names another process.
+pub fn watchdog_timeout() -> Option<Duration> {
+  if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+    if pid.trim().parse::<u32>().ok() != Some(std::process::id()) {
+      return None;
+    }
+  }
+  let usec = std::env::var("WATCHDOG_USEC").ok()?.trim().parse::<u64>().ok()?;
+  (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+// Builds a unit running the daemon in the foreground with `Type=notify` and the watchdog enabled.
+// Sandboxing follows systemd.exec(5); user managers cannot set up most namespaces, so user units get the
+// seccomp- and privilege-based subset only.
+pub fn unit_file(options: &UnitOptions) -> String {
+  let config_dir = quote(&options.config_dir.to_string_lossy());
+  let mut lines = vec![
+    "[Unit]".to_string(),
+    "Description=Cloudflare IPv6 DDNS daemon".to_string(),
+    "Wants=network-online.target".to_string(),
+    "After=network-online.target".to_string(),
+    String::new(),
+    "[Service]".to_string(),
+    "Type=notify".to_string(),
+    "NotifyAccess=main".to_string(),
+    format!(
+      "ExecStart={} --config {config_dir} run",
+      quote(&options.executable.to_string_lossy())
+    ),
+    "Restart=on-failure".to_string(),
+    "RestartSec=10".to_string(),
+    format!("WatchdogSec={WATCHDOG_SEC}"),
+  ];
+  if options.scope == UnitScope::System {
+    if let Some(user) = options.run_as {
+      lines.push(format!("User={user}"));
+    }
+  }
+
+  lines.extend(
+    [
+      "NoNewPrivileges=yes",
+      "LockPersonality=yes",
+      "MemoryDenyWriteExecute=yes",
+      "RestrictRealtime=yes",
+      "RestrictSUIDSGID=yes",
+      // Netlink carries the Linux network watcher's address events.
+      "RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6 AF_NETLINK",
+      "SystemCallArchitectures=native",
+      "SystemCallFilter=@system-service",
+      "SystemCallFilter=~@privileged @resources",
+      // Fail filtered calls instead of killing the process, so an unexpected call surfaces as an error.
+      "SystemCallErrorNumber=EPERM",
+      "UMask=0077",
+    ]
+    .map(String::from),
+  );
+  if options.scope == UnitScope::System {
+    lines.extend(
+      [
+        "CapabilityBoundingSet=",
+        "AmbientCapabilities=",
+        "ProtectSystem=strict",
+        "ProtectHome=read-only",
+        "PrivateTmp=yes",
+        "PrivateDevices=yes",
+        "ProtectClock=yes",
+        "ProtectHostname=yes",
+        "ProtectKernelTunables=yes",
+        "ProtectKernelModules=yes",
+        "ProtectKernelLogs=yes",
+        "ProtectControlGroups=yes",
+        "RestrictNamespaces=yes",
+      ]
+      .map(String::from),
+    );
+    // Settings, runtime state, GeoIP databases and the control socket all live in the config directory.
+    lines.push(format!("ReadWritePaths={config_dir}"));
+  }
+
+  lines.extend([
+    String::new(),
+    "[Install]".to_string(),
+    match options.scope {
+      UnitScope::User => "WantedBy=default.target".to_string(),
+      UnitScope::System => "WantedBy=multi-user.target".to_string(),
+    },
+  ]);
+  let mut unit = lines.join("\n");
+  unit.push('\n');
+  unit
+}
+
+// systemd.syntax(7) quoting, plus `%%` since specifiers are expanded in command lines and paths.
+fn quote(value: &str) -> String {
+  let escaped = value.replace('%', "%%");
+  if !escaped.contains(|ch: char| ch.is_whitespace() || matches!(ch, '"' | '\'' | '\\')) {
+    return escaped;
+  }
+  format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn unit(scope: UnitScope, executable: &str, config_dir: &str) -> String {
+    unit_file(&UnitOptions {
+      scope,
+      executable: Path::new(executable),
+      config_dir: Path::new(config_dir),
+      run_as: Some("ddns"),
+    })
+  }
+
+  fn has_line(unit: &str, line: &str) -> bool {
+    unit.lines().any(|candidate| candidate == line)
+  }
+
+  #[test]
+  fn quote_escapes_only_when_needed() {
+    let cases = [
+      ("/usr/bin/cf-ddns-daemon", "/usr/bin/cf-ddns-daemon"),
+      ("/opt/My Apps/ddns", "\"/opt/My Apps/ddns\""),
+      ("/srv/100%", "/srv/100%%"),
+      ("/srv/100% done", "\"/srv/100%% done\""),
+      ("/srv/say \"hi\"", "\"/srv/say \\\"hi\\\"\""),
+      ("C:\\odd path", "\"C:\\\\odd path\""),
+      ("/srv/it's", "\"/srv/it's\""),
+      ("/srv/tab\there", "\"/srv/tab\there\""),
+    ];
+    for (value, expected) in cases {
+      assert_eq!(quote(value), expected, "{value:?}");
+    }
+  }
+
+  #[test]
+  fn paths_with_spaces_are_quoted_in_exec_start_and_read_write_paths() {
+    let unit = unit(
+      UnitScope::System,
+      "/opt/Cloudflare DDNS/cf-ddns-daemon",
+      "/var/lib/cf ddns",
+    );
+    assert!(has_line(
+      &unit,
+      "ExecStart=\"/opt/Cloudflare DDNS/cf-ddns-daemon\" --config \"/var/lib/cf ddns\" run"
+    ));
+    assert!(has_line(&unit, "ReadWritePaths=\"/var/lib/cf ddns\""));
+  }
+
+  #[test]
+  fn system_units_add_the_account_and_namespace_sandboxing() {
+    let user = unit(UnitScope::User, "/usr/bin/cf-ddns-daemon", "/home/me/.config/cf-ddns");
+    let system = unit(UnitScope::System, "/usr/bin/cf-ddns-daemon", "/var/lib/cf-ddns");
+
+    for unit in [&user, &system] {
+      assert!(has_line(unit, "Type=notify"));
+      assert!(has_line(unit, &format!("WatchdogSec={WATCHDOG_SEC}")));
+      assert!(has_line(unit, "NoNewPrivileges=yes"));
+      assert!(unit.ends_with('\n'));
+    }
+
+    assert!(has_line(&user, "WantedBy=default.target"));
+    assert!(has_line(&system, "WantedBy=multi-user.target"));
+    // `run_as` only applies to system units; a user unit always runs as its owner.
+    assert!(!user.contains("User="));
+    assert!(has_line(&system, "User=ddns"));
+    for line in ["ProtectSystem=strict", "PrivateTmp=yes", "ReadWritePaths=/var/lib/cf-ddns"] {
+      assert!(!has_line(&user, line), "user unit has {line}");
+      assert!(has_line(&system, line), "system unit lacks {line}");
+    }
+  }
+}
@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::cloudflare::{self, TokenVerification};
+use crate::models::{TokenHealth, TokenStatus};
+
+// Expiry dates are day-granular in the dashboard, so a couple of checks a day is plenty.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+pub fn evaluate(verification: &TokenVerification, warn_days: u32, now: DateTime<Utc>) -> TokenStatus {
+  let remaining = verification.expires_on.map(|expires_on| expires_on - now);
+  // Rounded up: a token expiring in 23 hours has one (partial) day left, not zero.
+  let days_remaining = remaining.map(|remaining| ceil_div(remaining.num_seconds(), SECONDS_PER_DAY));
+  let expired = remaining.is_some_and(|remaining| remaining <= TimeDelta::zero());
+  let not_yet_valid = verification.not_before.is_some_and(|not_before| not_before > now);
+  let expiring_soon = remaining.is_some_and(|remaining| remaining < TimeDelta::days(i64::from(warn_days)));
+
+  let health = match verification.status.as_str() {
+    "expired" => TokenHealth::Expired,
+    "active" if expired => TokenHealth::Expired,
+    "active" if not_yet_valid => TokenHealth::Disabled,
+    "active" if expiring_soon => TokenHealth::ExpiringSoon,
+    "active" => TokenHealth::Active,
+    _ => TokenHealth::Disabled,
+  };
+  let mut status = TokenStatus {
+    health,
+    expires_on: verification.expires_on.map(|time| time.to_rfc3339()),
+    not_before: verification.not_before.map(|time| time.to_rfc3339()),
+    days_remaining,
+    message: None,
+    checked_time: now.to_rfc3339(),
+  };
+  status.message = warning(&status);
+  status
+}
+
+pub fn from_error(error: &anyhow::Error, now: DateTime<Utc>) -> TokenStatus {
+  let health = if cloudflare::is_auth_failure(error) {
+    TokenHealth::Invalid
+  } else {
+    TokenHealth::Unknown
+  };
+  TokenStatus {
+    health,
+    expires_on: None,
+    not_before: None,
+    days_remaining: None,
+    message: Some(format!("failed to verify API token: {error:#}")),
+    checked_time: now.to_rfc3339(),
+  }
+}
+
+// One-line text for the tray, notifications and push errors; `None` while the token is healthy.
+pub fn warning(status: &TokenStatus) -> Option<String> {
+  let expires_on = status
+    .expires_on
+    .as_deref()
+    .and_then(|time| time.get(..10))
+    .unwrap_or("an unknown date");
+  match status.health {
+    TokenHealth::Active | TokenHealth::Unknown => None,
+    TokenHealth::ExpiringSoon => Some(match remaining_seconds(status) {
+      // Under a day, "today" or "tomorrow" would depend on the time zone, so the hours are reported instead.
+      Some(seconds) if seconds < SECONDS_PER_DAY => match ceil_div(seconds, 3_600) {
+        1 => format!("Cloudflare API token expires within an hour ({expires_on})"),
+        hours => format!("Cloudflare API token expires in {hours} hours ({expires_on})"),
+      },
+      Some(seconds) if seconds < 2 * SECONDS_PER_DAY => format!("Cloudflare API token expires in 1 day ({expires_on})"),
+      Some(seconds) => format!(
+        "Cloudflare API token expires in {} days ({expires_on})",
+        seconds / SECONDS_PER_DAY
+      ),
+      None => "Cloudflare API token expires soon".to_string(),
+    }),
+    TokenHealth::Expired => Some(format!("Cloudflare API token expired on {expires_on}; rotate it to resume updates")),
+    TokenHealth::Disabled => Some("Cloudflare API token is disabled or not yet valid".to_string()),
+    TokenHealth::Invalid => Some("Cloudflare rejected the API token; rotate it to resume updates".to_string()),
+  }
+}
+
+fn remaining_seconds(status: &TokenStatus) -> Option<i64> {
+  let expires_on = DateTime::parse_from_rfc3339(status.expires_on.as_deref()?).ok()?;
+  let checked = DateTime::parse_from_rfc3339(&status.checked_time).ok()?;
+  Some((expires_on - checked).num_seconds())
+}
+
+fn ceil_div(value: i64, divisor: i64) -> i64 {
+  -(-value).div_euclid(divisor)
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::*;
+
+  fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()
+  }
+
+  fn verification(status: &str, expires_in: Option<TimeDelta>) -> TokenVerification {
+    TokenVerification {
+      status: status.to_string(),
+      expires_on: expires_in.map(|delta| now() + delta),
+      not_before: None,
+    }
+  }
+
+  #[test]
+  fn days_remaining_round_up_and_the_warning_switches_to_hours_under_a_day() {
+    let cases = [
+      (TimeDelta::hours(23), Some(1), TokenHealth::ExpiringSoon, Some("expires in 23 hours")),
+      (TimeDelta::minutes(30), Some(1), TokenHealth::ExpiringSoon, Some("expires within an hour")),
+      (TimeDelta::hours(24), Some(1), TokenHealth::ExpiringSoon, Some("expires in 1 day")),
+      (TimeDelta::hours(47), Some(2), TokenHealth::ExpiringSoon, Some("expires in 1 day")),
+      (TimeDelta::hours(60), Some(3), TokenHealth::ExpiringSoon, Some("expires in 2 days")),
+      (TimeDelta::hours(6 * 24 + 23), Some(7), TokenHealth::ExpiringSoon, Some("expires in 6 days")),
+      (TimeDelta::days(7), Some(7), TokenHealth::Active, None),
+      (TimeDelta::zero(), Some(0), TokenHealth::Expired, Some("expired on 2026-03-01")),
+      (TimeDelta::hours(-25), Some(-1), TokenHealth::Expired, Some("expired on 2026-02-28")),
+    ];
+    for (expires_in, days, health, message) in cases {
+      let status = evaluate(&verification("active", Some(expires_in)), 7, now());
+      assert_eq!(status.days_remaining, days, "{expires_in}");
+      assert_eq!(status.health, health, "{expires_in}");
+      match message {
+        Some(text) => assert!(
+          status.message.as_deref().is_some_and(|message| message.contains(text)),
+          "{expires_in}: {:?}",
+          status.message
+        ),
+        None => assert_eq!(status.message, None, "{expires_in}"),
+      }
+    }
+  }
+
+  #[test]
+  fn cloudflare_status_wins_over_the_dates() {
+    let cases = [
+      ("active", None, TokenHealth::Active),
+      ("expired", None, TokenHealth::Expired),
+      ("disabled", Some(TimeDelta::days(90)), TokenHealth::Disabled),
+    ];
+    for (cloudflare_status, expires_in, health) in cases {
+      let status = evaluate(&verification(cloudflare_status, expires_in), 7, now());
+      assert_eq!(status.health, health, "{cloudflare_status}");
+    }
+
+    let mut pending = verification("active", Some(TimeDelta::days(90)));
+    pending.not_before = Some(now() + TimeDelta::hours(1));
+    assert_eq!(evaluate(&pending, 7, now()).health, TokenHealth::Disabled);
+  }
+
+  #[test]
+  fn only_refused_tokens_are_invalid() {
+    let refused = anyhow::Error::new(cloudflare::CloudflareApiError {
+      status: 401,
+      message: "1000: Invalid API Token".to_string(),
+    });
+    assert_eq!(from_error(&refused, now()).health, TokenHealth::Invalid);
+    let offline = anyhow::anyhow!("failed to send Cloudflare token verify request");
+    assert_eq!(from_error(&offline, now()).health, TokenHealth::Unknown);
+  }
+}
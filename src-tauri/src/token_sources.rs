@@ -0,0 +1,176 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::models::TokenSource;
+
+// Credential helpers such as `op` or `bw` may need a moment to unlock, but must not stall a push forever.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Returns `Ok(None)` when the source simply has no token, so the next source can be tried.
+pub fn read_token(source: &TokenSource) -> Result<Option<String>> {
+  let token = match source {
+    TokenSource::Env { variable } => std::env::var(variable.trim()).ok(),
+    TokenSource::File { path } => {
+      let path = expand_env(path.trim());
+      match std::fs::read_to_string(&path) {
+        Ok(text) => Some(text),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error).with_context(|| format!("failed to read token file {path}")),
+      }
+    }
+    TokenSource::Command { program, args } => Some(run_helper(program, args)?),
+  };
+  // Secret files and helpers usually end with a newline; only the first line is the token.
+  Ok(token
+    .as_deref()
+    .and_then(|text| text.lines().next())
+    .map(str::trim)
+    .filter(|token| !token.is_empty())
+    .map(str::to_string))
+}
+
+// Label that identifies the source in status output without revealing the token.
+pub fn describe(source: &TokenSource) -> String {
+  match source {
+    TokenSource::Env { variable } => format!("env:{}", variable.trim()),
+    TokenSource::File { path } => format!("file:{}", path.trim()),
+    TokenSource::Command { program, .. } => format!("command:{}", program.trim()),
+  }
+}
+
+fn run_helper(program: &str, args: &[String]) -> Result<String> {
+  let program = program.trim();
+  let mut child = Command::new(program)
+    .args(args)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .with_context(|| format!("failed to start credential helper {program}"))?;
+
+  // Drain stdout while waiting: a helper that prints more than the pipe buffer would otherwise block until the timeout.
+  let mut stdout = child.stdout.take().context("credential helper stdout was not captured")?;
+  let reader = std::thread::spawn(move || {
+    let mut output = String::new();
+    stdout.read_to_string(&mut output).map(|_| output)
+  });
+
+  let started = Instant::now();
+  let status = loop {
+    if let Some(status) = child.try_wait().context("failed waiting for credential helper")? {
+      break status;
+    }
+    if started.elapsed() >= COMMAND_TIMEOUT {
+      let _ = child.kill();
+      let _ = child.wait();
+      return Err(anyhow!(
+        "credential helper {program} timed out after {} s",
+        COMMAND_TIMEOUT.as_secs()
+      ));
+    }
+    std::thread::sleep(Duration::from_millis(50));
+  };
+  if !status.success() {
+    return Err(anyhow!("credential helper {program} exited with {status}"));
+  }
+
+  reader
+    .join()
+    .map_err(|_| anyhow!("credential helper output reader panicked"))?
+    .context("failed to read credential helper output")
+}
+
+// Expands `${NAME}` so paths like `${CREDENTIALS_DIRECTORY}/cloudflare_api_token` work with systemd credentials.
+fn expand_env(path: &str) -> String {
+  let mut expanded = String::new();
+  let mut rest = path;
+  while let Some(start) = rest.find("${") {
+    let Some(length) = rest[start + 2..].find('}') else {
+      break;
+    };
+    let name = &rest[start + 2..start + 2 + length];
+    expanded.push_str(&rest[..start]);
+    expanded.push_str(&std::env::var(name).unwrap_or_default());
+    rest = &rest[start + 3 + length..];
+  }
+  expanded.push_str(rest);
+  expanded
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("cf-ddns-token-sources-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn env_source_trims_the_value_and_skips_empty_variables() {
+    let variable = format!("CF_DDNS_TEST_TOKEN_{}", std::process::id());
+    let source = TokenSource::Env {
+      variable: format!(" {variable} "),
+    };
+    std::env::set_var(&variable, "  secret-token \n");
+    assert_eq!(read_token(&source).unwrap().as_deref(), Some("secret-token"));
+    std::env::set_var(&variable, "   ");
+    assert_eq!(read_token(&source).unwrap(), None);
+    std::env::remove_var(&variable);
+    assert_eq!(read_token(&source).unwrap(), None);
+  }
+
+  #[test]
+  fn file_source_reads_the_first_line_and_expands_variables() {
+    let dir = temp_dir("file");
+    std::fs::write(dir.join("token"), "  secret-token\r\nsecond line\n").unwrap();
+    let variable = format!("CF_DDNS_TEST_CREDENTIALS_{}", std::process::id());
+    std::env::set_var(&variable, &dir);
+
+    let source = TokenSource::File {
+      path: format!("${{{variable}}}/token"),
+    };
+    assert_eq!(read_token(&source).unwrap().as_deref(), Some("secret-token"));
+
+    let missing = TokenSource::File {
+      path: dir.join("missing").display().to_string(),
+    };
+    assert_eq!(read_token(&missing).unwrap(), None);
+
+    std::env::remove_var(&variable);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn command_source_uses_stdout_and_fails_on_a_non_zero_exit() {
+    let helper = |script: &str| TokenSource::Command {
+      program: "sh".to_string(),
+      args: vec!["-c".to_string(), script.to_string()],
+    };
+    assert_eq!(
+      read_token(&helper("printf ' secret-token \\nignored\\n'")).unwrap().as_deref(),
+      Some("secret-token")
+    );
+    assert_eq!(read_token(&helper("true")).unwrap(), None);
+
+    let error = read_token(&helper("echo secret-token; exit 3")).unwrap_err();
+    assert!(error.to_string().contains("exited with"), "{error:#}");
+  }
+
+  #[test]
+  fn command_args_default_to_empty() {
+    let source: TokenSource = serde_json::from_str(r#"{"kind":"command","program":"pass"}"#).unwrap();
+    assert_eq!(
+      source,
+      TokenSource::Command {
+        program: "pass".to_string(),
+        args: Vec::new(),
+      }
+    );
+  }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use crate::models::{AppSettings, CredentialSummary, FieldError, InterfaceSelector, RecordMode, TokenSource, ValidationCode};
+
+// Cloudflare API docs: DNS record `ttl` is 1 ("automatic") or 60..=86400 seconds; Enterprise zones may go down to 30.
+const TTL_AUTOMATIC: u32 = 1;
+const TTL_MIN: u32 = 30;
+const TTL_MAX: u32 = 86_400;
+// Cloudflare zone and record identifiers are 32 lowercase hex characters.
+const CLOUDFLARE_ID_LEN: usize = 32;
+// A settle window or hold-down beyond these would leave a changed address unpublished for longer than any lease.
+const SETTLE_MAX_SECONDS: u64 = 3_600;
+const MIN_PUSH_INTERVAL_MAX_SECONDS: u64 = 86_400;
+// Polling is a safety net; below this it would spend more time enumerating interfaces than waiting.
+const POLL_INTERVAL_MIN_SECONDS: u64 = 10;
+const POLL_INTERVAL_MAX_SECONDS: u64 = 86_400;
+const HEARTBEAT_MAX_HOURS: u64 = 24 * 365;
+
+// Checks everything that can be judged locally; empty Cloudflare fields mean "not configured yet" and are allowed.
+// `credentials` are the named credentials stored on this machine.
+pub fn validate_settings(settings: &AppSettings, credentials: &[CredentialSummary]) -> Vec<FieldError> {
+  let mut errors = Vec::new();
+  let mut push = |field: String, code: ValidationCode| errors.push(FieldError { field, code });
+
+  let cloudflare = &settings.cloudflare;
+  let domain = cloudflare.domain.trim();
+  if !domain.is_empty() && !is_valid_domain(domain) {
+    push("cloudflare.domain".to_string(), ValidationCode::DomainInvalid);
+  }
+  if !is_valid_cloudflare_id(&cloudflare.zone_id) {
+    push("cloudflare.zoneId".to_string(), ValidationCode::ZoneIdInvalid);
+  }
+  if !is_valid_cloudflare_id(&cloudflare.record_id) {
+    push("cloudflare.recordId".to_string(), ValidationCode::RecordIdInvalid);
+  }
+  if let Some(ttl) = cloudflare.ttl {
+    if ttl != TTL_AUTOMATIC && !(TTL_MIN..=TTL_MAX).contains(&ttl) {
+      push("cloudflare.ttl".to_string(), ValidationCode::TtlOutOfRange);
+    }
+  }
+  if cloudflare.record_mode == RecordMode::MultiValue && cloudflare.max_records == 0 {
+    push("cloudflare.maxRecords".to_string(), ValidationCode::MaxRecordsOutOfRange);
+  }
+  if let Some(credential_id) = cloudflare.credential_id.as_deref() {
+    if !credentials.iter().any(|credential| credential.id == credential_id) {
+      push("cloudflare.credentialId".to_string(), ValidationCode::CredentialUnknown);
+    }
+  }
+
+  let policy = &settings.sync_policy;
+  if policy.settle_seconds > SETTLE_MAX_SECONDS {
+    push("syncPolicy.settleSeconds".to_string(), ValidationCode::SettleOutOfRange);
+  }
+  if policy.min_push_interval_seconds > MIN_PUSH_INTERVAL_MAX_SECONDS {
+    push("syncPolicy.minPushIntervalSeconds".to_string(), ValidationCode::MinPushIntervalOutOfRange);
+  }
+  // Zero disables polling and the heartbeat.
+  if policy.poll_interval_seconds != 0
+    && !(POLL_INTERVAL_MIN_SECONDS..=POLL_INTERVAL_MAX_SECONDS).contains(&policy.poll_interval_seconds)
+  {
+    push("syncPolicy.pollIntervalSeconds".to_string(), ValidationCode::PollIntervalOutOfRange);
+  }
+  if policy.heartbeat_interval_hours > HEARTBEAT_MAX_HOURS {
+    push("syncPolicy.heartbeatIntervalHours".to_string(), ValidationCode::HeartbeatIntervalOutOfRange);
+  }
+
+  for (index, selector) in settings.interface_candidates.iter().enumerate() {
+    let value = match selector {
+      InterfaceSelector::Name(value) | InterfaceSelector::Mac(value) | InterfaceSelector::Glob(value) => value,
+    };
+    if value.trim().is_empty() {
+      push(format!("interfaceCandidates[{index}]"), ValidationCode::InterfaceSelectorEmpty);
+    }
+  }
+
+  for (index, source) in settings.token_sources.iter().enumerate() {
+    let value = match source {
+      TokenSource::Env { variable } => variable,
+      TokenSource::File { path } => path,
+      TokenSource::Command { program, .. } => program,
+    };
+    if value.trim().is_empty() {
+      push(format!("tokenSources[{index}]"), ValidationCode::TokenSourceEmpty);
+    }
+  }
+
+  let homepage = &settings.local_homepage;
+  let mut seen_ids = HashMap::new();
+  let mut seen_ports = HashMap::new();
+  for (index, service) in homepage.services.iter().enumerate() {
+    let id = service.id.trim();
+    if id.is_empty() {
+      push(format!("localHomepage.services[{index}].id"), ValidationCode::ServiceIdRequired);
+    } else if seen_ids.insert(id.to_string(), index).is_some() {
+      push(format!("localHomepage.services[{index}].id"), ValidationCode::ServiceIdDuplicate);
+    }
+
+    let port_field = format!("localHomepage.services[{index}].port");
+    if service.port == 0 {
+      push(port_field, ValidationCode::ServicePortInvalid);
+    } else if service.port == homepage.web_port {
+      push(port_field, ValidationCode::ServicePortConflictsWithHomepage);
+    } else if seen_ports.insert(service.port, index).is_some() {
+      push(port_field, ValidationCode::ServicePortDuplicate);
+    }
+  }
+
+  errors
+}
+
+// Joins field errors into one line for callers that can only surface a string.
+pub fn describe_errors(errors: &[FieldError]) -> String {
+  let details = errors
+    .iter()
+    .map(|error| format!("{} ({})", error.field, error.code.as_str()))
+    .collect::<Vec<_>>()
+    .join(", ");
+  format!("invalid settings: {details}")
+}
+
+fn is_valid_cloudflare_id(value: &str) -> bool {
+  let value = value.trim();
+  value.is_empty() || (value.len() == CLOUDFLARE_ID_LEN && value.chars().all(|ch| ch.is_ascii_hexdigit()))
+}
+
+// RFC 1035 section 2.3.4 limits: 253 characters overall, 63 per label; a leading `*` label is a Cloudflare wildcard.
+// Internationalized names must be entered in their punycode (`xn--`) form, which is what the DNS record holds.
+fn is_valid_domain(domain: &str) -> bool {
+  let domain = domain.strip_suffix('.').unwrap_or(domain);
+  if domain.len() > 253 {
+    return false;
+  }
+  let labels = domain.split('.').collect::<Vec<_>>();
+  if labels.len() < 2 {
+    return false;
+  }
+  labels.iter().enumerate().all(|(index, label)| {
+    if index == 0 && *label == "*" {
+      return true;
+    }
+    !label.is_empty()
+      && label.len() <= 63
+      && !label.starts_with('-')
+      && !label.ends_with('-')
+      && label.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn codes_for(settings: &AppSettings) -> Vec<(String, &'static str)> {
+    validate_settings(settings, &[])
+      .into_iter()
+      .map(|error| (error.field, error.code.as_str()))
+      .collect()
+  }
+
+  fn with_domain(domain: &str) -> AppSettings {
+    let mut settings = AppSettings::default();
+    settings.cloudflare.domain = domain.to_string();
+    settings
+  }
+
+  #[test]
+  fn defaults_are_valid() {
+    assert!(codes_for(&AppSettings::default()).is_empty());
+  }
+
+  #[test]
+  fn ttl_is_automatic_or_within_cloudflare_bounds() {
+    let cases = [
+      (None, true),
+      (Some(1), true),
+      (Some(2), false),
+      (Some(29), false),
+      (Some(30), true),
+      (Some(86_400), true),
+      (Some(86_401), false),
+      (Some(0), false),
+    ];
+    for (ttl, valid) in cases {
+      let mut settings = AppSettings::default();
+      settings.cloudflare.ttl = ttl;
+      let expected = if valid {
+        Vec::new()
+      } else {
+        vec![("cloudflare.ttl".to_string(), "ttlOutOfRange")]
+      };
+      assert_eq!(codes_for(&settings), expected, "{ttl:?}");
+    }
+  }
+
+  #[test]
+  fn hostnames_follow_ldh_rules_in_ascii() {
+    let long_label = "a".repeat(64);
+    let long_name = format!("{}.example.com", vec!["a".repeat(63); 4].join("."));
+    let cases = [
+      ("home.example.com", true),
+      ("home.example.com.", true),
+      ("*.example.com", true),
+      ("_dmarc.example.com", true),
+      ("xn--bcher-kva.example", true),
+      ("bücher.example", false),
+      ("家.example.com", false),
+      ("localhost", false),
+      ("home..example.com", false),
+      ("-home.example.com", false),
+      ("home-.example.com", false),
+      ("home.*.example.com", false),
+      ("home example.com", false),
+      (long_label.as_str(), false),
+      (&format!("{long_label}.example.com"), false),
+      (long_name.as_str(), false),
+      ("", true),
+    ];
+    for (domain, valid) in cases {
+      let expected = if valid {
+        Vec::new()
+      } else {
+        vec![("cloudflare.domain".to_string(), "domainInvalid")]
+      };
+      assert_eq!(codes_for(&with_domain(domain)), expected, "{domain:?}");
+    }
+  }
+
+  #[test]
+  fn sync_intervals_stay_within_their_limits() {
+    type Case = (fn(&mut AppSettings, u64), u64, Option<(&'static str, &'static str)>);
+    let cases: [Case; 10] = [
+      (|s, v| s.sync_policy.settle_seconds = v, 3_600, None),
+      (|s, v| s.sync_policy.settle_seconds = v, 3_601, Some(("syncPolicy.settleSeconds", "settleOutOfRange"))),
+      (|s, v| s.sync_policy.min_push_interval_seconds = v, 86_400, None),
+      (
+        |s, v| s.sync_policy.min_push_interval_seconds = v,
+        86_401,
+        Some(("syncPolicy.minPushIntervalSeconds", "minPushIntervalOutOfRange")),
+      ),
+      (|s, v| s.sync_policy.poll_interval_seconds = v, 0, None),
+      (|s, v| s.sync_policy.poll_interval_seconds = v, 10, None),
+      (
+        |s, v| s.sync_policy.poll_interval_seconds = v,
+        9,
+        Some(("syncPolicy.pollIntervalSeconds", "pollIntervalOutOfRange")),
+      ),
+      (
+        |s, v| s.sync_policy.poll_interval_seconds = v,
+        86_401,
+        Some(("syncPolicy.pollIntervalSeconds", "pollIntervalOutOfRange")),
+      ),
+      (|s, v| s.sync_policy.heartbeat_interval_hours = v, 8_760, None),
+      (
+        |s, v| s.sync_policy.heartbeat_interval_hours = v,
+        8_761,
+        Some(("syncPolicy.heartbeatIntervalHours", "heartbeatIntervalOutOfRange")),
+      ),
+    ];
+    for (index, (set, value, expected)) in cases.into_iter().enumerate() {
+      let mut settings = AppSettings::default();
+      set(&mut settings, value);
+      let expected = expected
+        .map(|(field, code)| vec![(field.to_string(), code)])
+        .unwrap_or_default();
+      assert_eq!(codes_for(&settings), expected, "case {index}");
+    }
+  }
+}
@@ -1,9 +1,30 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import type { AppSnapshot, LookupRecordIdRequest, SaveSettingsRequest } from "./types";
+import type {
+  AppSnapshot,
+  ExportSettingsRequest,
+  ExternalImportReport,
+  ExternalImportRequest,
+  FieldError,
+  ImportSettingsRequest,
+  LookupRecordIdRequest,
+  PrefixChangedEvent,
+  PrefixRecord,
+  HistoryPage,
+  HistoryQuery,
+  HistoryStats,
+  ExportHistoryRequest,
+  AppSettings,
+  RotateTokenRequest,
+  SaveCredentialRequest,
+  SaveSettingsRequest,
+  SettingsImportPreview,
+  TokenRotationReport,
+} from "./types";
 
 export const SNAPSHOT_EVENT = "ddns://snapshot";
 export const NETWORK_CHANGED_EVENT = "ddns://network-changed";
+export const PREFIX_CHANGED_EVENT = "ddns://prefix-changed";
 
 export async function getSnapshot(): Promise<AppSnapshot> {
   return invoke<AppSnapshot>("get_snapshot");
@@ -13,6 +34,22 @@ export async function saveSettings(request: SaveSettingsRequest): Promise<AppSna
   return invoke<AppSnapshot>("save_settings", { request });
 }
 
+export async function validateSettings(settings: AppSettings): Promise<FieldError[]> {
+  return invoke<FieldError[]>("validate_settings", { settings });
+}
+
+export async function saveCredential(request: SaveCredentialRequest): Promise<AppSnapshot> {
+  return invoke<AppSnapshot>("save_credential", { request });
+}
+
+export async function deleteCredential(id: string): Promise<AppSnapshot> {
+  return invoke<AppSnapshot>("delete_credential", { id });
+}
+
+export async function rotateToken(request: RotateTokenRequest): Promise<TokenRotationReport> {
+  return invoke<TokenRotationReport>("rotate_token", { request });
+}
+
 export async function pushNow(): Promise<AppSnapshot> {
   return invoke<AppSnapshot>("manual_push_now");
 }
@@ -21,6 +58,38 @@ export async function lookupRecordId(request: LookupRecordIdRequest): Promise<Ap
   return invoke<AppSnapshot>("lookup_record_id", { request });
 }
 
+export async function getPrefixHistory(): Promise<PrefixRecord[]> {
+  return invoke<PrefixRecord[]>("get_prefix_history");
+}
+
+export async function getHistory(request: HistoryQuery = {}): Promise<HistoryPage> {
+  return invoke<HistoryPage>("get_history", { request });
+}
+
+export async function getHistoryStats(): Promise<HistoryStats> {
+  return invoke<HistoryStats>("get_history_stats");
+}
+
+export async function exportHistory(request: ExportHistoryRequest): Promise<string | null> {
+  return invoke<string | null>("export_history", { request });
+}
+
+export async function exportSettingsBundle(request: ExportSettingsRequest): Promise<void> {
+  return invoke<void>("export_settings_bundle", { request });
+}
+
+export async function previewSettingsImport(request: ImportSettingsRequest): Promise<SettingsImportPreview> {
+  return invoke<SettingsImportPreview>("preview_settings_import", { request });
+}
+
+export async function importSettingsBundle(request: ImportSettingsRequest): Promise<AppSnapshot> {
+  return invoke<AppSnapshot>("import_settings_bundle", { request });
+}
+
+export async function importExternalConfig(request: ExternalImportRequest): Promise<ExternalImportReport> {
+  return invoke<ExternalImportReport>("import_external_config", { request });
+}
+
 export async function restartApp(): Promise<void> {
   return invoke<void>("restart_app");
 }
@@ -40,3 +109,12 @@ export async function subscribeNetworkChanged(onChanged: () => void): Promise<()
   });
   return unlisten;
 }
+
+export async function subscribePrefixChanged(
+  onChanged: (event: PrefixChangedEvent) => void
+): Promise<() => void> {
+  const unlisten = await listen<PrefixChangedEvent>(PREFIX_CHANGED_EVENT, (event) => {
+    onChanged(event.payload);
+  });
+  return unlisten;
+}
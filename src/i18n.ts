@@ -1,4 +1,4 @@
-import type { LanguageMode } from "./types";
+import type { LanguageMode, ValidationCode } from "./types";
 
 export type UiLocale = "en" | "zh-CN";
 
@@ -136,6 +136,7 @@ export interface UiStrings {
     save: string;
     cancel: string;
   };
+  validation: Record<ValidationCode, string>;
 }
 
 const EN_STRINGS: UiStrings = {
@@ -271,6 +272,25 @@ const EN_STRINGS: UiStrings = {
     dragSortHint: "Tip: drag cards to reorder services.",
     save: "Save",
     cancel: "Cancel"
+  },
+  validation: {
+    domainInvalid: "Enter a valid domain name, e.g. home.example.com.",
+    zoneIdInvalid: "Zone ID must be 32 hexadecimal characters.",
+    recordIdInvalid: "Record ID must be 32 hexadecimal characters.",
+    ttlOutOfRange: "TTL must be 1 (automatic) or between 30 and 86400 seconds.",
+    maxRecordsOutOfRange: "Publish at least one address in multi-value mode.",
+    settleOutOfRange: "Settle window must be at most 3600 seconds.",
+    minPushIntervalOutOfRange: "Minimum push interval must be at most 86400 seconds.",
+    pollIntervalOutOfRange: "Polling interval must be 0 (off) or between 10 and 86400 seconds.",
+    heartbeatIntervalOutOfRange: "Heartbeat interval must be at most 8760 hours.",
+    interfaceSelectorEmpty: "Interface selector cannot be empty.",
+    tokenSourceEmpty: "Token source needs a variable name, file path or command.",
+    credentialUnknown: "Selected credential does not exist on this machine.",
+    serviceIdRequired: "Service ID is required.",
+    serviceIdDuplicate: "Service ID is already used by another service.",
+    servicePortInvalid: "Port must be between 1 and 65535.",
+    servicePortDuplicate: "Port is already used by another service.",
+    servicePortConflictsWithHomepage: "Port is already used by the local homepage."
   }
 };
 
@@ -407,6 +427,25 @@ const ZH_CN_STRINGS: UiStrings = {
     dragSortHint: "提示：可拖动卡片手动排序。",
     save: "保存",
     cancel: "取消"
+  },
+  validation: {
+    domainInvalid: "请输入有效域名，例如 home.example.com。",
+    zoneIdInvalid: "Zone ID 必须为 32 位十六进制字符。",
+    recordIdInvalid: "Record ID 必须为 32 位十六进制字符。",
+    ttlOutOfRange: "TTL 必须为 1（自动）或 30 到 86400 秒之间。",
+    maxRecordsOutOfRange: "多值模式下至少需要发布一个地址。",
+    settleOutOfRange: "稳定等待时间不能超过 3600 秒。",
+    minPushIntervalOutOfRange: "最小推送间隔不能超过 86400 秒。",
+    pollIntervalOutOfRange: "轮询间隔必须为 0（关闭）或 10 到 86400 秒之间。",
+    heartbeatIntervalOutOfRange: "心跳间隔不能超过 8760 小时。",
+    interfaceSelectorEmpty: "网卡选择条件不能为空。",
+    tokenSourceEmpty: "Token 来源需要填写变量名、文件路径或命令。",
+    credentialUnknown: "所选凭据在本机不存在。",
+    serviceIdRequired: "服务 ID 不能为空。",
+    serviceIdDuplicate: "服务 ID 已被其他服务使用。",
+    servicePortInvalid: "端口必须在 1 到 65535 之间。",
+    servicePortDuplicate: "端口已被其他服务使用。",
+    servicePortConflictsWithHomepage: "端口已被本机主页占用。"
   }
 };
 
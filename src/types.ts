@@ -2,11 +2,16 @@ export type ThemeMode = "light" | "dark";
 export type LanguageMode = "system" | "zh-CN" | "en";
 export type SyncStatusKind = "idle" | "success" | "error";
 
+export type RecordMode = "single" | "multiValue";
+
 export interface CloudflareSettings {
   zoneId: string;
   domain: string;
   recordId: string;
   ttl: number | null;
+  recordMode: RecordMode;
+  maxRecords: number;
+  credentialId: string | null;
 }
 
 export interface ServiceModel {
@@ -23,8 +28,50 @@ export interface LocalHomepageSettings {
   services: ServiceModel[];
 }
 
+export interface SyncPolicySettings {
+  settleSeconds: number;
+  minPushIntervalSeconds: number;
+  pollIntervalSeconds: number;
+  heartbeatIntervalHours: number;
+}
+
+export interface TokenExpirySettings {
+  warnDays: number;
+  notify: boolean;
+}
+
+export type InterfaceSelector =
+  | { kind: "name"; value: string }
+  | { kind: "mac"; value: string }
+  | { kind: "glob"; value: string };
+
+export type InterfaceKind =
+  | "physical"
+  | "virtual"
+  | "loopback"
+  | "vpn"
+  | "tunnel"
+  | "container"
+  | "virtualMachine"
+  | "unknown";
+
+export interface InterfaceFilterSettings {
+  excludedKinds: InterfaceKind[];
+  alwaysInclude: string[];
+  alwaysExclude: string[];
+}
+
+export interface ReachabilitySettings {
+  enabled: boolean;
+  probeEndpoint: string;
+  timeoutMs: number;
+}
+
 export interface AppSettings {
   selectedInterface: string | null;
+  interfaceCandidates: InterfaceSelector[];
+  interfaceFilter: InterfaceFilterSettings;
+  reachability: ReachabilitySettings;
   autoPush: boolean;
   launchOnStartup: boolean;
   lightweightMode: boolean;
@@ -33,6 +80,9 @@ export interface AppSettings {
   languageMode: LanguageMode;
   cloudflare: CloudflareSettings;
   localHomepage: LocalHomepageSettings;
+  syncPolicy: SyncPolicySettings;
+  tokenSources: TokenSource[];
+  tokenExpiry: TokenExpirySettings;
 }
 
 export interface SyncStatus {
@@ -49,17 +99,266 @@ export interface IpGeoInfo {
 
 export interface RuntimeCache {
   lastKnownIpv6: string | null;
+  lastKnownIpv6Set: string[];
   lastIpv6ChangeTime: string | null;
   lastSyncTime: string | null;
+  lastSuccessTime: string | null;
   lastSyncStatus: SyncStatus;
 }
 
+export type PendingPushReason = "settling" | "rateLimited" | "heartbeat";
+
+export interface PendingPushInfo {
+  ipv6: string | null;
+  addresses: string[];
+  reason: PendingPushReason;
+  firstSeenTime: string;
+  dueTime: string;
+}
+
 export interface InterfaceInfo {
   id: string;
   label: string;
   macAddress: string | null;
   ipv6Addresses: string[];
   linkSpeedMbps: number | null;
+  kind: InterfaceKind;
+  driver: string | null;
+  excluded: boolean;
+}
+
+export interface ReachabilityResult {
+  address: string;
+  reachable: boolean;
+  latencyMs: number | null;
+  error: string | null;
+}
+
+export interface ReachabilityReport {
+  probeEndpoint: string;
+  checkedTime: string;
+  results: ReachabilityResult[];
+  error: string | null;
+}
+
+export interface InterfaceSelectionStatus {
+  resolvedInterface: string | null;
+  warning: string | null;
+}
+
+export interface PrefixRecord {
+  prefix: string;
+  prefixLen: number;
+  firstSeenTime: string;
+  lastSeenTime: string;
+  endedTime: string | null;
+}
+
+export type HistoryEventKind = "addressChanged" | "pushSucceeded" | "pushFailed";
+
+export interface HistoryEntry {
+  time: string;
+  kind: HistoryEventKind;
+  addresses: string[];
+  previousAddresses?: string[];
+  domain?: string;
+  latencyMs?: number;
+  errorCode?: string;
+  message?: string;
+}
+
+export interface HistoryQuery {
+  offset?: number;
+  limit?: number;
+  kind?: HistoryEventKind;
+}
+
+export interface HistoryPage {
+  entries: HistoryEntry[];
+  total: number | null;
+  offset: number;
+  hasMore: boolean;
+}
+
+export interface LifetimeBucket {
+  label: string;
+  upToSeconds: number | null;
+  count: number;
+}
+
+export interface LifetimeDistribution {
+  completed: number;
+  minSeconds: number | null;
+  medianSeconds: number | null;
+  meanSeconds: number | null;
+  maxSeconds: number | null;
+  currentAgeSeconds: number | null;
+  buckets: LifetimeBucket[];
+}
+
+export interface DailyPushCount {
+  date: string;
+  succeeded: number;
+  failed: number;
+}
+
+export interface AddressUptime {
+  address: string;
+  firstSeenTime: string;
+  lastSeenTime: string | null;
+  uptimeSeconds: number;
+}
+
+export interface HistoryStats {
+  generatedTime: string;
+  periodStart: string | null;
+  prefixLifetimes: LifetimeDistribution;
+  pushesPerDay: DailyPushCount[];
+  pushAttempts: number;
+  pushFailures: number;
+  failureRate: number | null;
+  failuresByCode: Record<string, number>;
+  meanPushLatencyMs: number | null;
+  meanTimeToUpdateSeconds: number | null;
+  updatesMeasured: number;
+  addressUptime: AddressUptime[];
+}
+
+export type HistoryExportFormat = "csv" | "json";
+
+export interface ExportHistoryRequest {
+  path?: string | null;
+  format: HistoryExportFormat;
+}
+
+export interface PrefixChangedEvent {
+  previousPrefix: string;
+  prefix: string;
+  prefixLen: number;
+  changedTime: string;
+  lifetimeSeconds: number | null;
+}
+
+export type TokenSource =
+  | { kind: "env"; variable: string }
+  | { kind: "file"; path: string }
+  | { kind: "command"; program: string; args: string[] };
+
+export type TokenBackendKind = "keyring" | "encryptedFile";
+
+export interface CredentialSummary {
+  id: string;
+  name: string;
+  createdTime: string;
+}
+
+export type TokenHealth = "active" | "expiringSoon" | "expired" | "disabled" | "invalid" | "unknown";
+
+export interface TokenStatus {
+  health: TokenHealth;
+  expiresOn: string | null;
+  notBefore: string | null;
+  daysRemaining: number | null;
+  message: string | null;
+  checkedTime: string;
+}
+
+export interface RotateTokenRequest {
+  token: string;
+  apply: boolean;
+}
+
+export interface TokenRotationReport {
+  status: TokenStatus;
+  zoneAccessVerified: boolean;
+  applied: boolean;
+}
+
+export interface SaveCredentialRequest {
+  id: string | null;
+  name: string;
+  token: string;
+}
+
+export interface ConfigRecovery {
+  recoveredFrom: string;
+  reason: string;
+  corruptCopy: string | null;
+  recoveredTime: string;
+}
+
+export interface ConfigReloadStatus {
+  applied: boolean;
+  message: string;
+  time: string;
+}
+
+export type ValidationCode =
+  | "domainInvalid"
+  | "zoneIdInvalid"
+  | "recordIdInvalid"
+  | "ttlOutOfRange"
+  | "maxRecordsOutOfRange"
+  | "settleOutOfRange"
+  | "minPushIntervalOutOfRange"
+  | "pollIntervalOutOfRange"
+  | "heartbeatIntervalOutOfRange"
+  | "interfaceSelectorEmpty"
+  | "tokenSourceEmpty"
+  | "credentialUnknown"
+  | "serviceIdRequired"
+  | "serviceIdDuplicate"
+  | "servicePortInvalid"
+  | "servicePortDuplicate"
+  | "servicePortConflictsWithHomepage";
+
+export interface FieldError {
+  field: string;
+  code: ValidationCode;
+}
+
+export interface ExportSettingsRequest {
+  path: string;
+  passphrase: string | null;
+}
+
+export interface ImportSettingsRequest {
+  path: string;
+  passphrase: string | null;
+}
+
+export interface SettingsChange {
+  path: string;
+  from: unknown;
+  to: unknown;
+}
+
+export interface SettingsImportPreview {
+  exportedTime: string;
+  appVersion: string;
+  changes: SettingsChange[];
+  includesToken: boolean;
+  tokenWillImport: boolean;
+  warnings: string[];
+  validationErrors: FieldError[];
+}
+
+export type ExternalConfigKind = "ddnsGo" | "inadyn";
+
+export interface ExternalImportRequest {
+  kind: ExternalConfigKind;
+  path: string;
+  apply: boolean;
+}
+
+export interface ExternalImportReport {
+  changes: SettingsChange[];
+  unsupported: string[];
+  zoneName: string | null;
+  zoneIdResolved: boolean;
+  tokenFound: boolean;
+  validationErrors: FieldError[];
+  applied: boolean;
 }
 
 export interface AppSnapshot {
@@ -67,9 +366,20 @@ export interface AppSnapshot {
   settings: AppSettings;
   cache: RuntimeCache;
   currentIpv6: string | null;
+  currentIpv6Set: string[];
   currentIpv6Geo: IpGeoInfo | null;
+  currentPrefix: PrefixRecord | null;
+  reachability: ReachabilityReport | null;
+  pendingPush: PendingPushInfo | null;
   interfaces: InterfaceInfo[];
+  interfaceSelection: InterfaceSelectionStatus;
   hasToken: boolean;
+  tokenBackend: TokenBackendKind;
+  tokenSource: string | null;
+  credentials: CredentialSummary[];
+  tokenStatus: TokenStatus | null;
+  configRecovery: ConfigRecovery | null;
+  configReload: ConfigReloadStatus | null;
   linuxThemeHint: ThemeMode | null;
   localHomepage: LocalHomepageRuntime;
 }
@@ -94,6 +404,6 @@ export interface SaveSettingsRequest {
 }
 
 export interface LookupRecordIdRequest {
-  zoneId: string;
-  domain: string;
+  zoneId?: string | null;
+  domain?: string | null;
 }